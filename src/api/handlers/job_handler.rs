@@ -0,0 +1,256 @@
+//! Admin job queue handlers.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::api::middleware::{require_admin, CurrentUser};
+use crate::api::AppState;
+use crate::errors::{AppError, AppResult};
+use crate::jobs::{JobDetail, JobFilter, JobStore, JobSummary, StatusCount, Throughput};
+use crate::types::PaginationParams;
+
+/// Job listing query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct JobListQuery {
+    /// Only jobs in this status (pending, running, done, failed, killed, canceled, dead)
+    pub status: Option<String>,
+    /// Only jobs of this type
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    pub job_type: Option<String>,
+}
+
+/// A page of jobs
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobPage {
+    pub jobs: Vec<JobSummary>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+}
+
+/// Jobs finished in the last hour
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ThroughputSummary {
+    pub since: DateTime<Utc>,
+    pub completed: i64,
+    pub completed_per_minute: f64,
+    pub by_type: Vec<Throughput>,
+}
+
+/// Create admin job routes
+pub fn job_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs))
+        .route("/stats", get(job_stats))
+        .route("/throughput", get(job_throughput))
+        .route("/:id", get(get_job))
+        .route("/:id/retry", post(retry_job))
+        .route("/:id/cancel", post(cancel_job))
+}
+
+fn job_store(state: &AppState) -> AppResult<JobStore> {
+    state.database.require_postgres("The job queue")?;
+    Ok(JobStore::new(state.database.get_connection()))
+}
+
+/// Job counts per type and status (admin only)
+#[utoipa::path(
+    get,
+    path = "/admin/jobs/stats",
+    tag = "Jobs",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Job counts per type and status", body = Vec<StatusCount>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only")
+    )
+)]
+pub async fn job_stats(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+) -> AppResult<Json<Vec<StatusCount>>> {
+    require_admin(&current_user)?;
+    Ok(Json(job_store(&state)?.counts().await?))
+}
+
+/// Jobs finished in the last hour, per type (admin only)
+#[utoipa::path(
+    get,
+    path = "/admin/jobs/throughput",
+    tag = "Jobs",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Throughput over the last hour", body = ThroughputSummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only")
+    )
+)]
+pub async fn job_throughput(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+) -> AppResult<Json<ThroughputSummary>> {
+    require_admin(&current_user)?;
+
+    let since = Utc::now() - chrono::Duration::hours(1);
+    let by_type = job_store(&state)?.throughput(since).await?;
+    let completed = by_type.iter().map(|t| t.completed).sum();
+
+    Ok(Json(ThroughputSummary {
+        since,
+        completed,
+        completed_per_minute: completed as f64 / 60.0,
+        by_type,
+    }))
+}
+
+/// List jobs, most recent first (admin only)
+#[utoipa::path(
+    get,
+    path = "/admin/jobs",
+    tag = "Jobs",
+    security(("bearer_auth" = [])),
+    params(JobListQuery, PaginationParams),
+    responses(
+        (status = 200, description = "A page of jobs", body = JobPage),
+        (status = 400, description = "Unknown status or page out of range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only")
+    )
+)]
+pub async fn list_jobs(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(query): Query<JobListQuery>,
+    Query(pagination): Query<PaginationParams>,
+) -> AppResult<Json<JobPage>> {
+    require_admin(&current_user)?;
+
+    let filter = JobFilter {
+        status: query.status.as_deref().map(str::parse).transpose()?,
+        job_type: query.job_type,
+        limit: pagination.limit(),
+        offset: pagination.offset()?,
+    };
+
+    let store = job_store(&state)?;
+    let (jobs, total) = tokio::try_join!(store.list(&filter), store.count(&filter))?;
+
+    Ok(Json(JobPage {
+        jobs,
+        page: pagination.page(),
+        per_page: pagination.limit(),
+        total,
+    }))
+}
+
+/// Get a job with its payload and attempt history (admin only)
+#[utoipa::path(
+    get,
+    path = "/admin/jobs/{id}",
+    tag = "Jobs",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "Job ID")
+    ),
+    responses(
+        (status = 200, description = "Job detail", body = JobDetail),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only"),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn get_job(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<JobDetail>> {
+    require_admin(&current_user)?;
+    let job = job_store(&state)?
+        .get(&id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(job))
+}
+
+/// Requeue a failed, canceled or dead-lettered job (admin only)
+#[utoipa::path(
+    post,
+    path = "/admin/jobs/{id}/retry",
+    tag = "Jobs",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "Job ID")
+    ),
+    responses(
+        (status = 204, description = "Job requeued"),
+        (status = 400, description = "Job is not failed, canceled or dead"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only"),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn retry_job(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<StatusCode> {
+    require_admin(&current_user)?;
+
+    let store = job_store(&state)?;
+    let job = store.get(&id).await?.ok_or(AppError::NotFound)?;
+    if !store.retry(&id).await? {
+        return Err(AppError::BadRequest(format!(
+            "job is {} and cannot be retried",
+            job.summary.status
+        )));
+    }
+
+    tracing::info!(job_id = %id, admin_id = %current_user.id, "Job requeued");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Cancel a pending job (admin only)
+#[utoipa::path(
+    post,
+    path = "/admin/jobs/{id}/cancel",
+    tag = "Jobs",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "Job ID")
+    ),
+    responses(
+        (status = 204, description = "Job canceled"),
+        (status = 400, description = "Job is not pending"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only"),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn cancel_job(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<StatusCode> {
+    require_admin(&current_user)?;
+
+    let store = job_store(&state)?;
+    let job = store.get(&id).await?.ok_or(AppError::NotFound)?;
+    if !store.cancel(&id).await? {
+        return Err(AppError::BadRequest(format!(
+            "job is {} and cannot be canceled",
+            job.summary.status
+        )));
+    }
+
+    tracing::info!(job_id = %id, admin_id = %current_user.id, "Job canceled");
+    Ok(StatusCode::NO_CONTENT)
+}
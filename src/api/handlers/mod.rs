@@ -1,7 +1,9 @@
 //! HTTP request handlers.
 
 pub mod auth_handler;
+pub mod job_handler;
 pub mod user_handler;
 
 pub use auth_handler::auth_routes;
+pub use job_handler::job_routes;
 pub use user_handler::user_routes;
@@ -1,14 +1,14 @@
 //! User handlers.
 
 use axum::{
-    extract::{Extension, Path, State},
-    http::StatusCode,
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::Json,
     routing::{get, post},
     Router,
 };
-use serde::Deserialize;
-use utoipa::ToSchema;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -16,8 +16,9 @@ use crate::api::extractors::ValidatedJson;
 use crate::api::middleware::{require_admin, CurrentUser};
 use crate::api::AppState;
 use crate::config::is_valid_role;
-use crate::domain::UserResponse;
+use crate::domain::{SearchQuery, User, UserResponse, UserSearch, UserSearchResult};
 use crate::errors::{AppError, AppResult};
+use crate::types::PaginationParams;
 
 /// User update request with validation
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -31,11 +32,86 @@ pub struct UpdateUserRequest {
     pub role: Option<String>,
 }
 
+/// User search query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UserSearchQuery {
+    /// Partial name or email; typos are tolerated on PostgreSQL
+    pub q: String,
+}
+
+/// A page of search results, best match first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchPage {
+    pub results: Vec<UserSearchResult>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+}
+
+/// User body with its `ETag` header
+type TaggedUser = ([(header::HeaderName, String); 1], Json<UserResponse>);
+
+fn tagged(user: User) -> TaggedUser {
+    (
+        [(header::ETAG, user.etag())],
+        Json(UserResponse::from(user)),
+    )
+}
+
+/// Active user, from the cache when present.
+///
+/// Every write keeps the cache current: the handlers below refresh or
+/// invalidate the entry, and the purge, reset and email rewrite commands
+/// invalidate the users they change.
+async fn cached_user(state: &AppState, id: Uuid) -> AppResult<User> {
+    if let Some(user) = state.cache.get_user(&id).await? {
+        return Ok(user);
+    }
+
+    // Cache miss - fetch and cache for future requests
+    let user = state.user_service.get_user(id).await?;
+    state.cache.set_user(&user).await?;
+
+    Ok(user)
+}
+
+/// Version named by the `If-Match` header; `None` for `*` or no header.
+///
+/// Accepts a single strong tag (`"3"`). If-Match uses strong comparison
+/// (RFC 7232), so a weak tag (`W/"3"`) never matches.
+fn if_match_version(headers: &HeaderMap, required: bool) -> AppResult<Option<i32>> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return if required {
+            Err(AppError::PreconditionRequired)
+        } else {
+            Ok(None)
+        };
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("If-Match must be ASCII".to_string()))?
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    if value.starts_with("W/") {
+        return Err(AppError::PreconditionFailed);
+    }
+
+    value
+        .strip_prefix('"')
+        .and_then(|tag| tag.strip_suffix('"'))
+        .and_then(|tag| tag.parse().ok())
+        .map(Some)
+        .ok_or_else(|| AppError::BadRequest(format!("Unrecognized ETag in If-Match: {}", value)))
+}
+
 /// Create user routes
 pub fn user_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_users))
         .route("/me", get(get_current_user))
+        .route("/search", get(search_users))
         .route("/:id", get(get_user).put(update_user).delete(delete_user))
         .route("/:id/restore", post(restore_user))
 }
@@ -47,26 +123,16 @@ pub fn user_routes() -> Router<AppState> {
     tag = "Users",
     security(("bearer_auth" = [])),
     responses(
-        (status = 200, description = "Current user profile", body = UserResponse),
+        (status = 200, description = "Current user profile", body = UserResponse,
+            headers(("ETag" = String, description = "Current version of the user"))),
         (status = 401, description = "Unauthorized")
     )
 )]
 pub async fn get_current_user(
     Extension(current_user): Extension<CurrentUser>,
     State(state): State<AppState>,
-) -> AppResult<Json<UserResponse>> {
-    // Try cache first
-    if let Some(user) = state.cache.get_user(&current_user.id).await? {
-        return Ok(Json(UserResponse::from(user)));
-    }
-
-    // Cache miss - fetch from service
-    let user = state.user_service.get_user(current_user.id).await?;
-
-    // Cache for future requests
-    state.cache.set_user(&user).await?;
-
-    Ok(Json(UserResponse::from(user)))
+) -> AppResult<TaggedUser> {
+    Ok(tagged(cached_user(&state, current_user.id).await?))
 }
 
 /// List all users (admin only)
@@ -90,6 +156,43 @@ pub async fn list_users(
     Ok(Json(users.into_iter().map(UserResponse::from).collect()))
 }
 
+/// Search users by partial name or email (admin only)
+#[utoipa::path(
+    get,
+    path = "/users/search",
+    tag = "Users",
+    security(("bearer_auth" = [])),
+    params(UserSearchQuery, PaginationParams),
+    responses(
+        (status = 200, description = "Matching users, best first", body = UserSearchPage),
+        (status = 400, description = "Empty or too long query, or page out of range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin only")
+    )
+)]
+pub async fn search_users(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(query): Query<UserSearchQuery>,
+    Query(pagination): Query<PaginationParams>,
+) -> AppResult<Json<UserSearchPage>> {
+    require_admin(&current_user)?;
+
+    let search = UserSearch {
+        query: SearchQuery::parse(&query.q)?,
+        limit: pagination.limit(),
+        offset: pagination.offset()?,
+    };
+
+    let (hits, total) = state.user_service.search_users(search).await?;
+    Ok(Json(UserSearchPage {
+        results: hits.into_iter().map(UserSearchResult::from).collect(),
+        page: pagination.page(),
+        per_page: pagination.limit(),
+        total,
+    }))
+}
+
 /// Get user by ID (own profile or admin)
 #[utoipa::path(
     get,
@@ -100,7 +203,8 @@ pub async fn list_users(
         ("id" = Uuid, Path, description = "User ID")
     ),
     responses(
-        (status = 200, description = "User profile", body = UserResponse),
+        (status = 200, description = "User profile", body = UserResponse,
+            headers(("ETag" = String, description = "Current version of the user"))),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - Can only view own profile unless admin"),
         (status = 404, description = "User not found")
@@ -110,24 +214,13 @@ pub async fn get_user(
     Extension(current_user): Extension<CurrentUser>,
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> AppResult<Json<UserResponse>> {
+) -> AppResult<TaggedUser> {
     // Users can only view their own profile unless admin
     if current_user.id != id {
         require_admin(&current_user)?;
     }
 
-    // Try cache first
-    if let Some(user) = state.cache.get_user(&id).await? {
-        return Ok(Json(UserResponse::from(user)));
-    }
-
-    // Cache miss - fetch from service
-    let user = state.user_service.get_user(id).await?;
-
-    // Cache for future requests
-    state.cache.set_user(&user).await?;
-
-    Ok(Json(UserResponse::from(user)))
+    Ok(tagged(cached_user(&state, id).await?))
 }
 
 /// Update user (own profile or admin for role changes)
@@ -137,23 +230,28 @@ pub async fn get_user(
     tag = "Users",
     security(("bearer_auth" = [])),
     params(
-        ("id" = Uuid, Path, description = "User ID")
+        ("id" = Uuid, Path, description = "User ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag from GET /users/{id}")
     ),
     request_body = UpdateUserRequest,
     responses(
-        (status = 200, description = "User updated successfully", body = UserResponse),
+        (status = 200, description = "User updated successfully", body = UserResponse,
+            headers(("ETag" = String, description = "New version of the user"))),
         (status = 400, description = "Validation error"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - Can only update own profile unless admin"),
-        (status = 404, description = "User not found")
+        (status = 404, description = "User not found"),
+        (status = 412, description = "User changed since the If-Match ETag was read"),
+        (status = 428, description = "If-Match header is required")
     )
 )]
 pub async fn update_user(
     Extension(current_user): Extension<CurrentUser>,
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
     ValidatedJson(payload): ValidatedJson<UpdateUserRequest>,
-) -> AppResult<Json<UserResponse>> {
+) -> AppResult<TaggedUser> {
     // Users can only update their own profile
     if current_user.id != id {
         require_admin(&current_user)?;
@@ -171,6 +269,8 @@ pub async fn update_user(
         }
     }
 
+    let expected_version = if_match_version(&headers, state.require_if_match)?;
+
     // Acquire distributed lock to prevent concurrent updates
     let lock_key = format!("user:{}:update", id);
     let _lock = state.cache.acquire_lock(&lock_key).await?;
@@ -178,7 +278,7 @@ pub async fn update_user(
     // Critical section - only one request can update this user at a time
     let user = state
         .user_service
-        .update_user(id, payload.name, payload.role)
+        .update_user(id, payload.name, payload.role, expected_version)
         .await?;
 
     // Update cache with new user data
@@ -186,7 +286,7 @@ pub async fn update_user(
 
     // Lock automatically released when _lock goes out of scope
 
-    Ok(Json(UserResponse::from(user)))
+    Ok(tagged(user))
 }
 
 /// Delete user (admin only, cannot delete self)
@@ -236,7 +336,8 @@ pub async fn delete_user(
         ("id" = Uuid, Path, description = "User ID to restore")
     ),
     responses(
-        (status = 200, description = "User restored successfully", body = UserResponse),
+        (status = 200, description = "User restored successfully", body = UserResponse,
+            headers(("ETag" = String, description = "New version of the user"))),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - Admin only"),
         (status = 404, description = "User not found or not deleted")
@@ -246,7 +347,7 @@ pub async fn restore_user(
     Extension(current_user): Extension<CurrentUser>,
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> AppResult<Json<UserResponse>> {
+) -> AppResult<TaggedUser> {
     require_admin(&current_user)?;
 
     let user = state.user_service.restore_user(id).await?;
@@ -254,5 +355,35 @@ pub async fn restore_user(
     // Update cache with restored user
     state.cache.set_user(&user).await?;
 
-    Ok(Json(UserResponse::from(user)))
+    Ok(tagged(user))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn if_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_if_match_version() {
+        assert_eq!(if_match_version(&if_match("\"3\""), true).unwrap(), Some(3));
+        assert!(matches!(
+            if_match_version(&if_match("W/\"3\""), true),
+            Err(AppError::PreconditionFailed)
+        ));
+        assert_eq!(if_match_version(&if_match("*"), true).unwrap(), None);
+        assert_eq!(if_match_version(&HeaderMap::new(), false).unwrap(), None);
+        assert!(matches!(
+            if_match_version(&HeaderMap::new(), true),
+            Err(AppError::PreconditionRequired)
+        ));
+        assert!(matches!(
+            if_match_version(&if_match("3"), true),
+            Err(AppError::BadRequest(_))
+        ));
+    }
 }
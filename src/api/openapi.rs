@@ -5,8 +5,11 @@
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa::{Modify, OpenApi};
 
-use crate::api::handlers::{auth_handler, user_handler};
-use crate::domain::{CreateUser, UpdateUser, UserResponse, UserRole};
+use crate::api::handlers::{auth_handler, job_handler, user_handler};
+use crate::domain::{
+    CreateUser, Highlight, HighlightField, UpdateUser, UserResponse, UserRole, UserSearchResult,
+};
+use crate::jobs::{AttemptRecord, JobDetail, JobSummary, StatusCount, Throughput};
 use crate::services::TokenResponse;
 
 /// OpenAPI documentation for the Rust API Starter
@@ -30,10 +33,18 @@ use crate::services::TokenResponse;
         // User endpoints
         user_handler::get_current_user,
         user_handler::list_users,
+        user_handler::search_users,
         user_handler::get_user,
         user_handler::update_user,
         user_handler::delete_user,
         user_handler::restore_user,
+        // Admin job endpoints
+        job_handler::job_stats,
+        job_handler::job_throughput,
+        job_handler::list_jobs,
+        job_handler::get_job,
+        job_handler::retry_job,
+        job_handler::cancel_job,
     ),
     components(
         schemas(
@@ -48,12 +59,25 @@ use crate::services::TokenResponse;
             TokenResponse,
             // User handler types
             user_handler::UpdateUserRequest,
+            user_handler::UserSearchPage,
+            UserSearchResult,
+            Highlight,
+            HighlightField,
+            // Job types
+            StatusCount,
+            JobSummary,
+            JobDetail,
+            AttemptRecord,
+            Throughput,
+            job_handler::JobPage,
+            job_handler::ThroughputSummary,
         )
     ),
     modifiers(&SecurityAddon),
     tags(
         (name = "Authentication", description = "User registration and login"),
-        (name = "Users", description = "User management operations")
+        (name = "Users", description = "User management operations"),
+        (name = "Jobs", description = "Background job queue administration")
     )
 )]
 pub struct ApiDoc;
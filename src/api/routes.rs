@@ -6,10 +6,11 @@ use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use super::handlers::{auth_routes, user_routes};
+use super::handlers::{auth_routes, job_routes, user_routes};
 use super::middleware::{auth_middleware, rate_limit_auth_middleware, rate_limit_middleware};
 use super::openapi::ApiDoc;
 use super::AppState;
+use crate::infra::StartupMigrations;
 
 /// Create the application router with all routes configured
 pub fn create_router(state: AppState) -> Router {
@@ -40,6 +41,19 @@ pub fn create_router(state: AppState) -> Router {
                     rate_limit_middleware,
                 )),
         )
+        // Admin job queue routes (require JWT + admin role)
+        .nest(
+            "/admin/jobs",
+            job_routes()
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth_middleware,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    rate_limit_middleware,
+                )),
+        )
         // Global middleware
         .layer(TraceLayer::new_for_http())
         .with_state(state)
@@ -55,6 +69,7 @@ async fn root() -> &'static str {
 struct HealthResponse {
     status: &'static str,
     services: ServiceHealth,
+    migrations: MigrationHealth,
 }
 
 /// Individual service health status
@@ -72,7 +87,19 @@ struct ServiceStatus {
     error: Option<String>,
 }
 
+/// Schema status: `up_to_date`, `pending` or `unknown`
+#[derive(Serialize)]
+struct MigrationHealth {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pending: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 /// Health check endpoint with database and Redis connectivity check
+/// and pending migrations, which return 503 unless `MIGRATIONS_ON_START`
+/// is `off`
 async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
     // Check database health
     let db_status = match state.database.ping().await {
@@ -98,7 +125,31 @@ async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthRespon
         },
     };
 
-    let all_healthy = db_status.status == "healthy" && redis_status.status == "healthy";
+    // Check for migrations the running code expects but were not applied
+    let migrations = match state.database.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => MigrationHealth {
+            status: "up_to_date",
+            pending,
+            error: None,
+        },
+        Ok(pending) => MigrationHealth {
+            status: "pending",
+            pending,
+            error: None,
+        },
+        Err(e) => MigrationHealth {
+            status: "unknown",
+            pending: Vec::new(),
+            error: Some(e.to_string()),
+        },
+    };
+
+    // With migrations off, pending ones were accepted at startup and are
+    // only reported
+    let migrations_ok = migrations.status == "up_to_date"
+        || (migrations.status == "pending" && state.migrations_on_start == StartupMigrations::Off);
+    let all_healthy =
+        db_status.status == "healthy" && redis_status.status == "healthy" && migrations_ok;
 
     let response = HealthResponse {
         status: if all_healthy { "healthy" } else { "degraded" },
@@ -106,6 +157,7 @@ async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthRespon
             database: db_status,
             redis: redis_status,
         },
+        migrations,
     };
 
     let status_code = if all_healthy {
@@ -4,7 +4,11 @@
 
 use std::sync::Arc;
 
-use crate::infra::{Cache, Database};
+use sea_orm::DatabaseBackend;
+
+use crate::config::DEFAULT_REQUIRE_IF_MATCH;
+use crate::infra::{Cache, Database, Persistence, RedisEventBus, StartupMigrations};
+use crate::jobs::{InMemoryJobQueue, JobQueue, PostgresJobQueue};
 use crate::services::{AuthService, ServiceContainer, Services, UserService};
 
 /// Application state containing all services (DI container).
@@ -21,6 +25,12 @@ pub struct AppState {
     pub cache: Arc<Cache>,
     /// Database connection
     pub database: Arc<Database>,
+    /// Background job queue
+    pub jobs: Arc<dyn JobQueue>,
+    /// Reject updates without an `If-Match` header
+    pub require_if_match: bool,
+    /// How pending migrations were handled at startup
+    pub migrations_on_start: StartupMigrations,
     /// Internal service container (optional, only with from_config)
     service_container: Option<Arc<Services>>,
 }
@@ -35,22 +45,47 @@ impl AppState {
         cache: Arc<Cache>,
         config: crate::config::Config,
     ) -> Self {
-        let container = Arc::new(Services::from_connection(
-            database.get_connection(),
-            config,
-        ));
+        let require_if_match = config.require_if_match;
+        let migrations_on_start = StartupMigrations::from_config(&config).unwrap_or_default();
+        let events = Arc::new(RedisEventBus::new(cache.connection()));
+        let jobs: Arc<dyn JobQueue> = match database.backend() {
+            DatabaseBackend::Postgres => Arc::new(PostgresJobQueue::new(
+                database.connection().get_postgres_connection_pool().clone(),
+            )),
+            _ => {
+                tracing::warn!(
+                    "Job queue requires PostgreSQL; jobs are kept in memory and not run"
+                );
+                Arc::new(InMemoryJobQueue::new())
+            }
+        };
+        let container = Arc::new(
+            Services::from_persistence(
+                Persistence::new(database.get_connection())
+                    .with_replica(database.get_read_connection()),
+                config,
+                events,
+            )
+            .with_jobs(jobs.clone()),
+        );
 
         Self {
             auth_service: container.auth(),
             user_service: container.users(),
             cache,
             database,
+            jobs,
+            require_if_match,
+            migrations_on_start,
             service_container: Some(container),
         }
     }
 
     /// Create new application state with manually injected services.
     ///
+    /// Jobs are recorded in an [`InMemoryJobQueue`]; use `with_jobs()` to
+    /// attach another queue.
+    ///
     /// Note: This method does not provide ServiceContainer access.
     /// Use `from_config()` for full functionality.
     pub fn new(
@@ -64,10 +99,31 @@ impl AppState {
             user_service,
             cache,
             database,
+            jobs: Arc::new(InMemoryJobQueue::new()),
+            require_if_match: DEFAULT_REQUIRE_IF_MATCH,
+            migrations_on_start: StartupMigrations::default(),
             service_container: None,
         }
     }
 
+    /// Use the given background job queue.
+    pub fn with_jobs(mut self, jobs: Arc<dyn JobQueue>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Require (or not) `If-Match` on user updates.
+    pub fn with_require_if_match(mut self, require: bool) -> Self {
+        self.require_if_match = require;
+        self
+    }
+
+    /// Set how pending migrations were handled at startup.
+    pub fn with_migrations_on_start(mut self, mode: StartupMigrations) -> Self {
+        self.migrations_on_start = mode;
+        self
+    }
+
     /// Get the service container for centralized service access.
     ///
     /// Returns `Some` only if created via `from_config()`.
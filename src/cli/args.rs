@@ -2,7 +2,9 @@
 //!
 //! Uses clap derive macros for type-safe argument parsing.
 
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
 
 /// Rust API Starter - Production-ready API with Clean Architecture
 #[derive(Parser, Debug)]
@@ -30,9 +32,15 @@ pub enum Commands {
     /// Run database migrations
     Migrate(MigrateArgs),
 
+    /// Seed or reset the database
+    Db(DbArgs),
+
     /// Manage background jobs
     Jobs(JobsArgs),
 
+    /// Work with email templates
+    Email(EmailArgs),
+
     /// Generate project components
     Generate(GenerateArgs),
 }
@@ -60,31 +68,201 @@ pub struct MigrateArgs {
 #[derive(Subcommand, Debug)]
 pub enum MigrateAction {
     /// Run pending migrations
-    Up,
-    /// Rollback last migration
-    Down,
+    Up {
+        /// Stop after applying this migration
+        #[arg(long)]
+        to: Option<String>,
+        /// Print the SQL of each pending migration instead of running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rollback applied migrations (the last one by default)
+    Down {
+        /// Number of migrations to roll back
+        #[arg(long, default_value_t = 1, conflicts_with = "to")]
+        steps: u32,
+        /// Roll back every migration applied after this one
+        #[arg(long)]
+        to: Option<String>,
+        /// Print the SQL of each rollback instead of running it
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip the confirmation for non-local databases
+        #[arg(long)]
+        force: bool,
+    },
     /// Show migration status
     Status,
     /// Reset and re-run all migrations
-    Fresh,
+    Fresh {
+        /// Skip the confirmation for non-local databases
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Arguments for the db command
+#[derive(Parser, Debug)]
+pub struct DbArgs {
+    #[command(subcommand)]
+    pub action: DbAction,
+}
+
+/// Database actions
+#[derive(Subcommand, Debug)]
+pub enum DbAction {
+    /// Create seed users that do not exist yet
+    Seed(SeedArgs),
+    /// Re-run all migrations from scratch, then seed
+    Reset {
+        #[command(flatten)]
+        seed: SeedArgs,
+        /// Skip the confirmation for non-local databases
+        #[arg(long)]
+        force: bool,
+    },
+    /// List users whose emails differ only by case (they block migrating)
+    Duplicates,
+    /// Rewrite existing emails under the current EMAIL_LOCAL_PART policy
+    NormalizeEmails {
+        /// List the emails that would change without rewriting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Permanently delete users soft-deleted longer than the retention period
+    PurgeUsers {
+        /// Days deleted users are kept, at least 1 (default: DELETED_USER_RETENTION_DAYS)
+        #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+        retention_days: Option<i64>,
+        /// Users deleted per transaction (default: USER_PURGE_BATCH_SIZE)
+        #[arg(long)]
+        batch_size: Option<u64>,
+        /// List the users that would be purged without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Built-in seed sets accepted by `db seed`
+const SEED_SETS: [&str; 2] = ["development", "staging"];
+
+/// Which seed data to load
+#[derive(Args, Debug)]
+pub struct SeedArgs {
+    /// Built-in seed set
+    #[arg(default_value = "development", value_parser = SEED_SETS)]
+    pub set: String,
+    /// Load the seed set from a YAML or JSON file instead
+    #[arg(long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+    /// Number of fake users to generate
+    #[arg(long)]
+    pub count: Option<usize>,
 }
 
 /// Arguments for the jobs command
 #[derive(Parser, Debug)]
 pub struct JobsArgs {
+    /// Print machine-readable JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
     #[command(subcommand)]
     pub action: JobsAction,
 }
 
+/// Job statuses accepted by `--status`
+const JOB_STATUSES: [&str; 7] = [
+    "pending", "running", "done", "failed", "killed", "canceled", "dead",
+];
+
+/// Queues accepted by `--queue`
+const JOB_QUEUES: [&str; 1] = ["email"];
+
 /// Job management actions
 #[derive(Subcommand, Debug)]
 pub enum JobsAction {
     /// Start background job worker
-    Work,
-    /// List pending jobs
-    List,
+    Work {
+        /// Only consume this queue (repeatable; defaults to all queues)
+        #[arg(long = "queue", value_parser = JOB_QUEUES)]
+        queues: Vec<String>,
+    },
+    /// Show job counts and list jobs
+    List {
+        /// Only jobs in this status
+        #[arg(long, value_parser = JOB_STATUSES)]
+        status: Option<String>,
+        /// Only jobs of this type (e.g. email::send)
+        #[arg(long = "type")]
+        job_type: Option<String>,
+        /// Maximum number of jobs to list
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+    },
+    /// Show a job with its payload, attempts and last error
+    Show {
+        /// Job id
+        id: String,
+    },
+    /// Requeue a failed, canceled or dead-lettered job
+    Retry {
+        /// Job id
+        #[arg(required_unless_present = "all_failed", conflicts_with = "all_failed")]
+        id: Option<String>,
+        /// Requeue every failed, canceled and dead-lettered job
+        #[arg(long)]
+        all_failed: bool,
+    },
+    /// Cancel a pending job
+    Cancel {
+        /// Job id
+        id: String,
+    },
+    /// Delete jobs in a status older than a given age
+    Purge {
+        #[arg(long, value_parser = JOB_STATUSES)]
+        status: String,
+        /// Minimum age, e.g. 30m, 12h, 7d or 2w
+        #[arg(long)]
+        older_than: String,
+    },
     /// Clear failed jobs
     Clear,
+    /// Show cron schedules and their next run times
+    Schedule {
+        /// Number of upcoming runs to show per job
+        #[arg(long, default_value_t = 3)]
+        next: usize,
+    },
+}
+
+/// Arguments for the email command
+#[derive(Parser, Debug)]
+pub struct EmailArgs {
+    #[command(subcommand)]
+    pub action: EmailAction,
+}
+
+/// Email actions
+#[derive(Subcommand, Debug)]
+pub enum EmailAction {
+    /// List built-in email templates
+    Templates,
+    /// Render a template with sample data
+    Preview {
+        /// Template name (e.g., "welcome")
+        template: String,
+        /// Locale to render
+        #[arg(short, long, default_value = "en")]
+        locale: String,
+        /// Template variable overriding the sample data (key=value, repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// Part to print
+        #[arg(long, default_value = "all", value_parser = ["all", "text", "html"])]
+        part: String,
+    },
 }
 
 /// Arguments for the generate command
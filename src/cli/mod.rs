@@ -4,6 +4,7 @@
 //! - `serve` - Start the HTTP server
 //! - `migrate` - Database migrations
 //! - `jobs` - Background job management
+//! - `email` - Email template preview
 //! - `generate` - Code generation
 
 pub mod args;
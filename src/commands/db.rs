@@ -0,0 +1,245 @@
+//! Db command - Seed data for development and staging.
+//!
+//! ## Usage
+//!
+//! ```bash
+//! # Admin, a few users and a soft-deleted one
+//! cargo run -- db seed
+//!
+//! # Staging data with 500 generated users
+//! cargo run -- db seed staging --count 500
+//!
+//! # Users from a file
+//! cargo run -- db seed --file seeds/demo.yaml
+//!
+//! # Drop everything, re-run migrations and seed
+//! cargo run -- db reset
+//!
+//! # Emails used by several users in different casing
+//! cargo run -- db duplicates
+//!
+//! # Rewrite existing emails after changing EMAIL_LOCAL_PART
+//! cargo run -- db normalize-emails --dry-run
+//! cargo run -- db normalize-emails
+//!
+//! # Hard-delete users soft-deleted more than 30 days ago
+//! cargo run -- db purge-users --dry-run
+//! cargo run -- db purge-users --retention-days 30
+//! ```
+//!
+//! `seed` and `reset` refuse to run against production-looking database URLs.
+
+use std::sync::Arc;
+
+use sea_orm::TransactionTrait;
+
+use super::guard::{confirm_destructive, refuse_production};
+use super::optional_cache;
+use crate::cli::args::{DbAction, DbArgs, SeedArgs};
+use crate::config::Config;
+use crate::errors::{AppError, AppResult};
+use crate::infra::{
+    duplicate_emails, email_collisions, email_rewrites, normalize_emails, Database, DuplicateEmail,
+    Persistence, SeedSet, Seeder,
+};
+use crate::jobs::PurgeDeletedUsersTask;
+use crate::services::{ServiceContainer, Services};
+
+/// Execute the db command
+pub async fn execute(args: DbArgs, config: Config) -> AppResult<()> {
+    match args.action {
+        DbAction::Seed(seed) => {
+            refuse_production(&config.database_url, "seed")?;
+            let set = seed_set(&seed)?;
+            let db = connect(&config).await?;
+            seed_database(&db, &set, &config).await
+        }
+        DbAction::Reset { seed, force } => {
+            refuse_production(&config.database_url, "reset")?;
+            let set = seed_set(&seed)?;
+            confirm_destructive(&config.database_url, "drop every table in", force)?;
+
+            let db = connect(&config).await?;
+            tracing::warn!("Resetting database and running all migrations...");
+            db.fresh_migrations()
+                .await
+                .map_err(|e| AppError::internal(e.to_string()))?;
+            if let Some(cache) = optional_cache(&config).await {
+                cache.invalidate_all_users().await?;
+            }
+            seed_database(&db, &set, &config).await
+        }
+        DbAction::Duplicates => {
+            let db = connect(&config).await?;
+            report_duplicates(&db).await
+        }
+        DbAction::NormalizeEmails { dry_run } => {
+            let db = connect(&config).await?;
+            rewrite_emails(&db, &config, dry_run).await
+        }
+        DbAction::PurgeUsers {
+            retention_days,
+            batch_size,
+            dry_run,
+        } => {
+            let db = connect(&config).await?;
+            let services = Services::from_connection(db.get_connection(), config.clone());
+            let task = PurgeDeletedUsersTask::new(
+                services.users(),
+                retention_days.unwrap_or(config.deleted_user_retention_days),
+            )?
+            .with_batch_size(batch_size.unwrap_or(config.user_purge_batch_size));
+            if dry_run {
+                return list_expired_users(&services, &task).await;
+            }
+            purge_users(task, &config).await
+        }
+    }
+}
+
+/// Seed set named on the command line or loaded from `--file`
+fn seed_set(args: &SeedArgs) -> AppResult<SeedSet> {
+    let set = match &args.file {
+        Some(path) => SeedSet::from_file(path)?,
+        None => SeedSet::builtin(&args.set)?,
+    };
+    Ok(match args.count {
+        Some(count) => set.with_generated(count),
+        None => set,
+    })
+}
+
+async fn connect(config: &Config) -> AppResult<Database> {
+    Database::connect_without_migrations(config)
+        .await
+        .map_err(|e| AppError::internal(format!("Database connection failed: {}", e)))
+}
+
+async fn seed_database(db: &Database, set: &SeedSet, config: &Config) -> AppResult<()> {
+    tracing::info!("Seeding '{}'...", set.name);
+    let report = Seeder::new(Arc::new(Persistence::new(db.get_connection())))
+        .with_email_policy(config.email_local_part)
+        .run(set)
+        .await?;
+    println!(
+        "Seeded '{}': {} created ({} soft-deleted), {} already present",
+        set.name, report.created, report.deleted, report.existing
+    );
+    Ok(())
+}
+
+/// Print users sharing an email in different casing; fails if there are any
+async fn report_duplicates(db: &Database) -> AppResult<()> {
+    let duplicates = duplicate_emails(db.connection())
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?;
+    if duplicates.is_empty() {
+        println!("No duplicate emails");
+        return Ok(());
+    }
+
+    print_duplicates(&duplicates);
+    Err(AppError::validation(format!(
+        "{} emails are shared in different casing; merge or rename these users before migrating",
+        duplicates.len()
+    )))
+}
+
+/// Rewrite stored emails under `EMAIL_LOCAL_PART` in one transaction;
+/// fails without changes if two users would end up with one address
+async fn rewrite_emails(db: &Database, config: &Config, dry_run: bool) -> AppResult<()> {
+    let policy = config.email_local_part;
+    let collisions = email_collisions(db.connection(), policy)
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?;
+    if !collisions.is_empty() {
+        print_duplicates(&collisions);
+        return Err(AppError::validation(format!(
+            "{} emails would be shared under {:?}; merge or rename these users first",
+            collisions.len(),
+            policy
+        )));
+    }
+
+    let rewrites = if dry_run {
+        email_rewrites(db.connection(), policy).await?
+    } else {
+        let txn = db.connection().begin().await?;
+        let rewrites = normalize_emails(&txn, policy).await?;
+        txn.commit().await?;
+        if let Some(cache) = optional_cache(config).await {
+            for rewrite in &rewrites {
+                cache.invalidate_user(&rewrite.user_id).await?;
+            }
+        }
+        rewrites
+    };
+
+    for rewrite in &rewrites {
+        println!("{}  {} -> {}", rewrite.user_id, rewrite.from, rewrite.to);
+    }
+    let verb = if dry_run {
+        "would be rewritten"
+    } else {
+        "rewritten"
+    };
+    println!("{} emails {}", rewrites.len(), verb);
+    Ok(())
+}
+
+/// Print each shared email with its users, oldest first
+fn print_duplicates(duplicates: &[DuplicateEmail]) {
+    for duplicate in duplicates {
+        println!("{} ({} users)", duplicate.email, duplicate.users.len());
+        for user in &duplicate.users {
+            let deleted = if user.is_deleted() { "  [deleted]" } else { "" };
+            println!(
+                "  {}  {}  created {}{}",
+                user.id,
+                user.email,
+                user.created_at.format("%Y-%m-%d"),
+                deleted
+            );
+        }
+    }
+}
+
+/// Print the first batch a purge would delete and how many are due
+async fn list_expired_users(services: &Services, task: &PurgeDeletedUsersTask) -> AppResult<()> {
+    let cutoff = task.cutoff();
+    let users = services.users();
+    let first = users
+        .list_deleted_users_before(cutoff, task.batch_size())
+        .await?;
+    let total = users.count_deleted_users_before(cutoff).await?;
+
+    for user in &first {
+        if let Some(deleted_at) = user.deleted_at {
+            println!("{}  deleted {}", user.id, deleted_at.format("%Y-%m-%d"));
+        }
+    }
+    if total > first.len() as u64 {
+        println!("... and {} more", total - first.len() as u64);
+    }
+    println!(
+        "{} users deleted before {} would be purged",
+        total,
+        cutoff.format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
+
+/// Purge expired users, invalidating their cache entries when Redis is up
+async fn purge_users(task: PurgeDeletedUsersTask, config: &Config) -> AppResult<()> {
+    let task = match optional_cache(config).await {
+        Some(cache) => task.with_cache(Arc::new(cache)),
+        None => task,
+    };
+
+    let report = task.purge().await?;
+    println!(
+        "Purged {} users in {} batches",
+        report.purged, report.batches
+    );
+    Ok(())
+}
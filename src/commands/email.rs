@@ -0,0 +1,94 @@
+//! Email command - Template preview.
+//!
+//! Renders transactional email templates with sample data so they can be
+//! checked without sending anything. Templates from `EMAIL_TEMPLATE_DIR`
+//! override the built-in ones.
+//!
+//! ## Usage
+//!
+//! ```bash
+//! # List templates
+//! cargo run -- email templates
+//!
+//! # Preview the German password reset email
+//! cargo run -- email preview password_reset --locale de
+//!
+//! # Override sample variables and write the HTML part to a file
+//! cargo run -- email preview welcome --var name=Grace --part html > welcome.html
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::cli::args::{EmailAction, EmailArgs};
+use crate::config::EMAIL_TEMPLATES;
+use crate::errors::{AppError, AppResult};
+use crate::infra::EmailTemplates;
+
+/// Execute the email command
+pub async fn execute(args: EmailArgs) -> AppResult<()> {
+    match args.action {
+        EmailAction::Templates => {
+            for name in EMAIL_TEMPLATES {
+                println!("{}", name);
+            }
+            Ok(())
+        }
+        EmailAction::Preview {
+            template,
+            locale,
+            vars,
+            part,
+        } => preview(&template, &locale, &vars, &part),
+    }
+}
+
+/// Render a template and print the requested part
+fn preview(template: &str, locale: &str, overrides: &[String], part: &str) -> AppResult<()> {
+    let mut vars = sample_vars();
+    for pair in overrides {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| AppError::validation(format!("Expected KEY=VALUE, got '{}'", pair)))?;
+        vars.insert(key.to_string(), Value::String(value.to_string()));
+    }
+
+    let rendered = EmailTemplates::from_env()
+        .render(template, locale, &vars)
+        .map_err(|e| AppError::validation(e.to_string()))?;
+
+    match part {
+        "text" => println!("{}", rendered.text),
+        "html" => println!("{}", rendered.html.unwrap_or_default()),
+        _ => {
+            println!("Locale:  {}", rendered.locale);
+            println!("Subject: {}", rendered.subject);
+            println!("\n----- text/plain -----\n{}", rendered.text);
+            match rendered.html {
+                Some(html) => println!("\n----- text/html -----\n{}", html),
+                None => println!("\n(no HTML part)"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sample values for every variable used by the built-in templates
+fn sample_vars() -> Map<String, Value> {
+    let vars = json!({
+        "name": "Ada Lovelace",
+        "verify_url": "https://example.com/verify?token=sample",
+        "reset_url": "https://example.com/reset-password?token=sample",
+        "expires_in_hours": 24,
+        "expires_in_minutes": 30,
+        "unlock_at": "2030-01-01 12:00 UTC",
+        "since": "2030-01-01 08:00 UTC",
+        "new_users": 3,
+        "deleted_users": 1,
+        "active_users": 42,
+    });
+    match vars {
+        Value::Object(map) => map,
+        _ => unreachable!("sample vars are an object"),
+    }
+}
@@ -12,7 +12,17 @@ pub async fn execute(args: GenerateArgs) -> AppResult<()> {
             templates::generate_entity(&name)?;
             println!("Created: src/domain/{}.rs", name);
             println!("Created: src/infra/repositories/{}_repository.rs", name);
-            println!("Don't forget to update mod.rs files!");
+            if templates::add_transaction_accessor(&name)? {
+                println!(
+                    "Updated: src/infra/unit_of_work.rs (TransactionContext::{}s)",
+                    templates::to_snake_case(&name)
+                );
+            }
+            println!(
+                "Don't forget to update mod.rs files and export {pascal}Store and \
+                 Tx{pascal}Repository from src/infra/repositories/mod.rs!",
+                pascal = templates::to_pascal_case(&name),
+            );
         }
         GenerateComponent::Migration { name } => {
             tracing::info!("Generating migration: {}", name);
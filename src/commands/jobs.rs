@@ -2,8 +2,20 @@
 //!
 //! Provides CLI commands to manage background jobs:
 //! - `work`: Start the job worker process
-//! - `list`: Show pending/failed jobs
+//! - `list`: Show job counts, live workers and list jobs by status and type
+//! - `show`: Show a job with its payload and attempt history
+//! - `retry`: Requeue a failed or dead-lettered job (or all of them)
+//! - `cancel`: Cancel a pending job
+//! - `purge`: Delete jobs in a status older than an age
 //! - `clear`: Remove failed jobs from the queue
+//! - `schedule`: Show cron jobs and their next run times
+//!
+//! Every subcommand accepts `--json` for scripting.
+//!
+//! Besides queued jobs the worker runs the cron jobs from
+//! [`default_schedules`], see [`crate::jobs::cron`]. Failed jobs are
+//! retried per [`crate::jobs::RetryPolicy`] and end up in `dead_letter_jobs`
+//! once they run out of attempts.
 //!
 //! ## Usage
 //!
@@ -11,191 +23,571 @@
 //! # Start the job worker
 //! cargo run -- jobs work
 //!
+//! # Only consume the email queue
+//! cargo run -- jobs work --queue email
+//!
 //! # List job queue status
 //! cargo run -- jobs list
 //!
+//! # Dead-lettered email jobs as JSON
+//! cargo run -- jobs list --status dead --type email::send --limit 50 --json
+//!
+//! # Inspect and requeue a job
+//! cargo run -- jobs show 01HV9Z8K3M2N4P5Q6R7S8T9V0W
+//! cargo run -- jobs retry 01HV9Z8K3M2N4P5Q6R7S8T9V0W
+//! cargo run -- jobs retry --all-failed
+//!
+//! # Delete finished jobs older than a week
+//! cargo run -- jobs purge --status done --older-than 7d
+//!
 //! # Clear failed jobs
 //! cargo run -- jobs clear
+//!
+//! # Show the next 5 runs of every cron job
+//! cargo run -- jobs schedule --next 5
 //! ```
 
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+
+use super::{optional_cache, shutdown_signal};
 use crate::cli::args::{JobsAction, JobsArgs};
-use crate::config::Config;
+use crate::config::{Config, JOB_QUEUE_EMAIL, WORKER_LIVE_WINDOW_SECONDS};
 use crate::errors::{AppError, AppResult};
+use crate::infra::{Database, StartupMigrations};
+use crate::jobs::cron::{
+    default_schedules, CRON_JOB_ADMIN_DIGEST, CRON_JOB_PRUNE_DONE_JOBS,
+    CRON_JOB_PURGE_DELETED_USERS,
+};
+use crate::jobs::store::{JobFilter, JobStatus, JobStore};
+use crate::jobs::{
+    AdminDigestTask, CronHandle, CronJob, CronScheduler, CronStore, EmailJob, PostgresJobQueue,
+    PruneDoneJobsTask, PurgeDeletedUsersTask, WorkerSettings,
+};
+use crate::services::{ServiceContainer, Services};
 
 /// Execute the jobs command
 pub async fn execute(args: JobsArgs, config: Config) -> AppResult<()> {
     match args.action {
-        JobsAction::Work => run_worker(&config).await,
-        JobsAction::List => list_jobs(&config).await,
-        JobsAction::Clear => clear_failed_jobs(&config).await,
+        JobsAction::Work { queues } => run_worker(&config, &queues).await,
+        JobsAction::List {
+            status,
+            job_type,
+            limit,
+        } => list_jobs(&config, status, job_type, limit, args.json).await,
+        JobsAction::Show { id } => show_job(&config, &id, args.json).await,
+        JobsAction::Retry { id, .. } => retry_jobs(&config, id, args.json).await,
+        JobsAction::Cancel { id } => cancel_job(&config, &id, args.json).await,
+        JobsAction::Purge { status, older_than } => {
+            purge_jobs(&config, &status, &older_than, args.json).await
+        }
+        JobsAction::Clear => clear_failed_jobs(&config, args.json).await,
+        JobsAction::Schedule { next } => show_schedule(&config, next, args.json).await,
     }
 }
 
 /// Start the background job worker
 ///
-/// Connects to the database and starts processing jobs from the queue.
-/// Uses apalis with PostgreSQL storage for job persistence.
-async fn run_worker(config: &Config) -> AppResult<()> {
+/// Connects to the database and starts processing jobs from the selected
+/// queues (all of them if none are given), with the workers and concurrency
+/// from [`WorkerSettings`]. Uses apalis with PostgreSQL storage for job
+/// persistence. Cron jobs run alongside the queue consumers and are stopped
+/// with them.
+///
+/// On Ctrl+C or SIGTERM the workers stop fetching jobs and running jobs get
+/// the drain timeout to finish.
+async fn run_worker(config: &Config, queues: &[String]) -> AppResult<()> {
     use apalis::prelude::*;
     use apalis_sql::postgres::PostgresStorage;
-    use apalis_sql::sqlx::postgres::PgPoolOptions;
 
-    use crate::jobs::{email_job_handler, EmailJob};
+    use crate::infra::EmailConfig;
+    use crate::jobs::{email_job_handler, JobRetries, Mailer, RetrySweeper};
+
+    let settings = WorkerSettings::from_env(queues)?;
+    let migrations = StartupMigrations::from_config(config)
+        .map_err(|e| AppError::validation(format!("Invalid MIGRATIONS_ON_START: {}", e)))?;
 
     tracing::info!("Connecting to database for job worker...");
 
-    // Create connection pool
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&config.database_url)
+    let db = Database::connect_without_migrations(config)
         .await
         .map_err(|e| AppError::internal(format!("Failed to connect to database: {}", e)))?;
-
-    // Run migrations for apalis tables first (associated function on pool)
-    PostgresStorage::setup(&pool)
+    db.require_postgres("The job worker")?;
+    db.startup_migrations(migrations)
         .await
-        .map_err(|e| AppError::internal(format!("Failed to setup job storage: {}", e)))?;
+        .map_err(|e| AppError::internal(format!("Failed to run migrations: {}", e)))?;
+    let pool = db.connection().get_postgres_connection_pool().clone();
+
+    // Create the apalis job tables if needed
+    PostgresJobQueue::setup(&pool).await?;
+
+    // Email transport is built once and shared by every job
+    let mailer = EmailConfig::from_env()
+        .and_then(|email_config| Mailer::from_config(&email_config))
+        .map_err(|e| AppError::internal(format!("Invalid email configuration: {}", e)))?;
+    tracing::info!(
+        transport = mailer.transport_name(),
+        "Email transport configured"
+    );
+
+    // Failed jobs are requeued or dead-lettered by the sweeper
+    let retries = JobRetries::from_env(db.get_connection())?;
+    let (stop_sweeper, sweeper_stopped) = tokio::sync::watch::channel(false);
+    let sweeper = tokio::spawn(RetrySweeper::new(db.get_connection()).run(sweeper_stopped));
+
+    let cron = start_cron(config, &db, PostgresJobQueue::new(pool.clone())).await?;
+
+    // Every worker polls its own storage, which fetches at most
+    // `concurrency` jobs at a time
+    let mut monitor = Monitor::new();
+    let mut worker_ids = Vec::new();
+    for queue in &settings.queues {
+        for index in 0..queue.workers {
+            let name = queue.worker_name(index);
+            monitor = match queue.name {
+                JOB_QUEUE_EMAIL => monitor.register(
+                    WorkerBuilder::new(&name)
+                        .concurrency(queue.concurrency)
+                        .data(mailer.clone())
+                        .data(retries.clone())
+                        .backend(PostgresStorage::<EmailJob>::new_with_config(
+                            pool.clone(),
+                            queue.storage_config(),
+                        ))
+                        .build_fn(email_job_handler),
+                ),
+                other => return Err(AppError::internal(format!("Unknown queue '{}'", other))),
+            };
+            worker_ids.push(name);
+        }
+        tracing::info!(
+            queue = queue.name,
+            workers = queue.workers,
+            concurrency = queue.concurrency,
+            "Queue registered"
+        );
+    }
 
-    // Initialize PostgreSQL storage for email jobs
-    let email_storage: PostgresStorage<EmailJob> = PostgresStorage::new(pool);
+    // Jobs still running when the drain timeout expires are left locked
+    // and requeued by the next worker
+    let drain_timeout = settings.drain_timeout;
+    let monitor = monitor.with_terminator(async move {
+        tokio::time::sleep(drain_timeout).await;
+        tracing::warn!(
+            timeout_seconds = drain_timeout.as_secs(),
+            "Drain timeout reached, stopping with jobs still running"
+        );
+    });
 
     tracing::info!("Job worker started. Press Ctrl+C to stop.");
 
-    // Build and run the worker
-    let worker = WorkerBuilder::new("email-worker")
-        .backend(email_storage)
-        .build_fn(email_job_handler);
+    let result = monitor
+        .run_with_signal(async {
+            shutdown_signal().await;
+            tracing::info!(
+                timeout_seconds = drain_timeout.as_secs(),
+                "Received shutdown signal, waiting for running jobs..."
+            );
+            Ok(())
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Worker error: {}", e);
+            AppError::internal(format!("Worker failed: {}", e))
+        });
+
+    if let Err(e) = JobStore::new(db.get_connection())
+        .mark_workers_stopped(&worker_ids)
+        .await
+    {
+        tracing::warn!("Failed to clear worker heartbeats: {}", e);
+    }
+
+    // Let running cron jobs finish and release their leases
+    cron.shutdown().await;
+    stop_sweeper.send_replace(true);
+    let _ = sweeper.await;
+
+    tracing::info!("Job worker stopped.");
+    result
+}
+
+/// Register every enabled cron job and start scheduling them
+async fn start_cron(
+    config: &Config,
+    db: &Database,
+    queue: PostgresJobQueue,
+) -> AppResult<CronHandle> {
+    let cache = optional_cache(config).await.map(Arc::new);
+    let services =
+        Services::from_connection(db.get_connection(), config.clone()).with_jobs(Arc::new(queue));
+
+    let mut scheduler = CronScheduler::new(CronStore::new(db.get_connection()));
+    for schedule in default_schedules()? {
+        let job: Arc<dyn CronJob> = match schedule.name.as_str() {
+            CRON_JOB_PURGE_DELETED_USERS => {
+                let task = PurgeDeletedUsersTask::new(
+                    services.users(),
+                    config.deleted_user_retention_days,
+                )?
+                .with_batch_size(config.user_purge_batch_size);
+                match &cache {
+                    Some(cache) => Arc::new(task.with_cache(cache.clone())),
+                    None => Arc::new(task),
+                }
+            }
+            CRON_JOB_PRUNE_DONE_JOBS => Arc::new(PruneDoneJobsTask::new(
+                db.get_connection(),
+                config.done_job_retention_days,
+            )),
+            CRON_JOB_ADMIN_DIGEST => {
+                Arc::new(AdminDigestTask::new(services.users(), services.jobs()))
+            }
+            other => return Err(AppError::internal(format!("Unknown cron job '{}'", other))),
+        };
+        tracing::info!(
+            job = %schedule.name,
+            expression = %schedule.expression,
+            policy = %schedule.policy,
+            "Cron job registered"
+        );
+        scheduler = scheduler.register(schedule, job);
+    }
 
-    // Run with graceful shutdown on Ctrl+C
-    let monitor = Monitor::new().register(worker);
+    Ok(scheduler.start())
+}
 
-    tokio::select! {
-        result = monitor.run() => {
-            if let Err(e) = result {
-                tracing::error!("Worker error: {}", e);
-                return Err(AppError::internal(format!("Worker failed: {}", e)));
+/// Show cron schedules, their next runs and, if the database is reachable,
+/// the outcome of their last run
+async fn show_schedule(config: &Config, next: usize, json: bool) -> AppResult<()> {
+    let schedules = default_schedules()?;
+
+    let state = match Database::connect_without_migrations(config).await {
+        Ok(db) => CronStore::new(db.get_connection())
+            .all()
+            .await
+            .unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("Database unavailable, showing schedules only: {}", e);
+            Vec::new()
+        }
+    };
+
+    let now = Utc::now();
+    if json {
+        let entries: Vec<_> = schedules
+            .iter()
+            .map(|schedule| {
+                let last = state.iter().find(|s| s.name == schedule.name);
+                json!({
+                    "name": schedule.name,
+                    "expression": schedule.expression,
+                    "policy": schedule.policy.to_string(),
+                    "last_run_at": last.and_then(|l| l.last_scheduled_at),
+                    "last_status": last.and_then(|l| l.last_status.clone()),
+                    "last_error": last.and_then(|l| l.last_error.clone()),
+                    "next_runs": schedule.upcoming(now, next),
+                })
+            })
+            .collect();
+        return print_json(&entries);
+    }
+
+    println!("\n=== Cron Schedules ===");
+    if schedules.is_empty() {
+        println!("All cron jobs are disabled.");
+    }
+    for schedule in &schedules {
+        println!("\n{}", schedule.name);
+        println!("  Expression: {}", schedule.expression);
+        println!("  Missed:     {}", schedule.policy);
+        if let Some(last) = state.iter().find(|s| s.name == schedule.name) {
+            match last.last_scheduled_at {
+                Some(at) => println!(
+                    "  Last run:   {} ({})",
+                    at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    last.last_status.as_deref().unwrap_or("running")
+                ),
+                None => println!("  Last run:   never"),
+            }
+            if let Some(error) = &last.last_error {
+                println!("  Last error: {}", error);
             }
         }
-        _ = tokio::signal::ctrl_c() => {
-            tracing::info!("Received shutdown signal, stopping worker...");
+        for (i, at) in schedule.upcoming(now, next).into_iter().enumerate() {
+            let label = if i == 0 { "Next runs:" } else { "" };
+            println!("  {:<11} {}", label, at.format("%Y-%m-%d %H:%M:%S UTC"));
         }
     }
+    println!("\n======================\n");
 
-    tracing::info!("Job worker stopped.");
     Ok(())
 }
 
-/// List pending and failed jobs
-///
-/// Queries the apalis job tables and displays status counts.
-async fn list_jobs(config: &Config) -> AppResult<()> {
-    use sea_orm::{ConnectionTrait, Database, Statement};
-
+/// Connect to the job tables, failing if the queue was never set up
+async fn job_store(config: &Config) -> AppResult<JobStore> {
     tracing::info!("Connecting to database...");
 
-    let db = Database::connect(&config.database_url)
+    let db = Database::connect_without_migrations(config)
         .await
         .map_err(|e| AppError::internal(format!("Failed to connect to database: {}", e)))?;
+    db.require_postgres("The job queue")?;
+    let store = JobStore::new(db.get_connection());
 
-    // Check if apalis schema exists
-    let result = db
-        .query_one(Statement::from_string(
-            sea_orm::DatabaseBackend::Postgres,
-            "SELECT EXISTS(SELECT 1 FROM information_schema.schemata WHERE schema_name = 'apalis') as exists".to_string(),
-        ))
-        .await
-        .map_err(|e| AppError::internal(format!("Query failed: {}", e)))?;
+    if !store.is_initialized().await? {
+        return Err(AppError::validation(
+            "Job queue not initialized. Run 'jobs work' first to create the queue tables.",
+        ));
+    }
+    Ok(store)
+}
 
-    let schema_exists = result
-        .and_then(|r| r.try_get::<bool>("", "exists").ok())
-        .unwrap_or(false);
+fn print_json<T: Serialize>(value: &T) -> AppResult<()> {
+    let output = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::internal(format!("Failed to serialize output: {}", e)))?;
+    println!("{}", output);
+    Ok(())
+}
 
-    if !schema_exists {
-        println!("\n=== Job Queue Status ===");
-        println!("Job queue not initialized.");
-        println!("Run 'jobs work' first to create the queue tables.");
-        println!("========================\n");
-        return Ok(());
-    }
+fn format_time(at: Option<DateTime<Utc>>) -> String {
+    at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
 
-    // Query job counts
-    let rows = db
-        .query_all(Statement::from_string(
-            sea_orm::DatabaseBackend::Postgres,
-            "SELECT status::text as status, COUNT(*)::bigint as count FROM apalis.jobs GROUP BY status".to_string(),
-        ))
-        .await
-        .unwrap_or_default();
-
-    let mut pending = 0i64;
-    let mut running = 0i64;
-    let mut failed = 0i64;
-    let mut done = 0i64;
-
-    for row in rows {
-        if let (Ok(status), Ok(count)) = (
-            row.try_get::<String>("", "status"),
-            row.try_get::<i64>("", "count"),
-        ) {
-            match status.as_str() {
-                "Pending" => pending = count,
-                "Running" => running = count,
-                "Failed" => failed = count,
-                "Done" => done = count,
-                _ => {}
-            }
+/// Parse an age such as `90s`, `30m`, `12h`, `7d` or `2w`
+fn parse_age(age: &str) -> AppResult<chrono::Duration> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (amount, unit) = age.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| AppError::validation(format!("Invalid age '{}'", age)))?;
+
+    let duration = match unit {
+        "" | "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        "w" => chrono::Duration::try_weeks(amount),
+        _ => {
+            return Err(AppError::validation(format!(
+                "Invalid age unit in '{}', expected s, m, h, d or w",
+                age
+            )))
         }
+    };
+    duration.ok_or_else(|| AppError::validation(format!("Age '{}' is out of range", age)))
+}
+
+/// Show job counts per status and list jobs
+async fn list_jobs(
+    config: &Config,
+    status: Option<String>,
+    job_type: Option<String>,
+    limit: u64,
+    json: bool,
+) -> AppResult<()> {
+    let store = job_store(config).await?;
+    let filter = JobFilter {
+        status: status.as_deref().map(str::parse).transpose()?,
+        job_type,
+        limit,
+        offset: 0,
+    };
+    let counts = store.counts().await?;
+    let jobs = store.list(&filter).await?;
+    let workers = store
+        .workers(Utc::now() - chrono::Duration::seconds(WORKER_LIVE_WINDOW_SECONDS))
+        .await?;
+
+    if json {
+        return print_json(&json!({ "counts": counts, "workers": workers, "jobs": jobs }));
     }
 
     println!("\n=== Job Queue Status ===");
-    println!("Pending:  {}", pending);
-    println!("Running:  {}", running);
-    println!("Failed:   {}", failed);
-    println!("Done:     {}", done);
+    for status in JobStatus::ALL {
+        let count: i64 = counts
+            .iter()
+            .filter(|c| c.status == status.as_str())
+            .map(|c| c.count)
+            .sum();
+        println!("{:<9} {}", format!("{}:", status), count);
+    }
     println!("========================\n");
 
+    if workers.is_empty() {
+        println!("No live workers.\n");
+    } else {
+        println!(
+            "{:<40}  {:<14}  {:>7}  LAST SEEN",
+            "WORKER", "TYPE", "RUNNING"
+        );
+        for worker in &workers {
+            println!(
+                "{:<40}  {:<14}  {:>7}  {}",
+                worker.id,
+                worker.job_type,
+                worker.running,
+                format_time(Some(worker.last_seen))
+            );
+        }
+        println!();
+    }
+
+    if jobs.is_empty() {
+        println!("No jobs found.");
+        return Ok(());
+    }
+    println!(
+        "{:<26}  {:<14}  {:<8}  {:>8}  {:<19}  {:<19}  LAST ERROR",
+        "ID", "TYPE", "STATUS", "ATTEMPTS", "RUN AT", "DONE AT"
+    );
+    for job in jobs {
+        println!(
+            "{:<26}  {:<14}  {:<8}  {:>8}  {:<19}  {:<19}  {}",
+            job.id,
+            job.job_type,
+            job.status,
+            job.attempts,
+            format_time(job.run_at),
+            format_time(job.done_at),
+            job.last_error.as_deref().unwrap_or("")
+        );
+    }
+
     Ok(())
 }
 
-/// Clear failed jobs from the queue
-async fn clear_failed_jobs(config: &Config) -> AppResult<()> {
-    use sea_orm::{ConnectionTrait, Database, Statement};
+/// Show one job with its payload and attempt history
+async fn show_job(config: &Config, id: &str, json: bool) -> AppResult<()> {
+    let job = job_store(config)
+        .await?
+        .get(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
-    tracing::info!("Connecting to database...");
+    if json {
+        return print_json(&job);
+    }
 
-    let db = Database::connect(&config.database_url)
-        .await
-        .map_err(|e| AppError::internal(format!("Failed to connect to database: {}", e)))?;
+    println!("ID:         {}", job.summary.id);
+    println!("Type:       {}", job.summary.job_type);
+    println!("Status:     {}", job.summary.status);
+    println!("Attempts:   {}", job.summary.attempts);
+    println!("Run at:     {}", format_time(job.summary.run_at));
+    println!("Done at:    {}", format_time(job.summary.done_at));
+    if let Some(worker) = &job.lock_by {
+        println!("Locked by:  {} at {}", worker, format_time(job.lock_at));
+    }
+    println!(
+        "Last error: {}",
+        job.summary.last_error.as_deref().unwrap_or("-")
+    );
+    println!(
+        "\nPayload:\n{}",
+        serde_json::to_string_pretty(&job.payload).unwrap_or_default()
+    );
+    if !job.history.is_empty() {
+        println!("\nAttempts:");
+        for attempt in &job.history {
+            println!(
+                "  #{} {} [{}] {}",
+                attempt.attempt,
+                attempt.failed_at.format("%Y-%m-%d %H:%M:%S"),
+                attempt.kind,
+                attempt.error
+            );
+        }
+    }
 
-    // Check if apalis schema exists
-    let result = db
-        .query_one(Statement::from_string(
-            sea_orm::DatabaseBackend::Postgres,
-            "SELECT EXISTS(SELECT 1 FROM information_schema.schemata WHERE schema_name = 'apalis') as exists".to_string(),
-        ))
-        .await
-        .map_err(|e| AppError::internal(format!("Query failed: {}", e)))?;
+    Ok(())
+}
 
-    let schema_exists = result
-        .and_then(|r| r.try_get::<bool>("", "exists").ok())
-        .unwrap_or(false);
+/// Requeue one job or every failed job
+async fn retry_jobs(config: &Config, id: Option<String>, json: bool) -> AppResult<()> {
+    let store = job_store(config).await?;
+    let count = match &id {
+        Some(id) => u64::from(store.retry(id).await?),
+        None => store.retry_all_failed().await?,
+    };
 
-    if !schema_exists {
-        println!("Job queue not initialized. Nothing to clear.");
-        return Ok(());
+    if json {
+        return print_json(&json!({ "retried": count }));
+    }
+    match id {
+        Some(id) if count == 0 => println!("Job {} is not failed, canceled or dead.", id),
+        Some(id) => println!("Requeued job {}.", id),
+        None => println!("Requeued {} job(s).", count),
     }
+    Ok(())
+}
 
-    // Delete failed jobs
-    let result = db
-        .execute(Statement::from_string(
-            sea_orm::DatabaseBackend::Postgres,
-            "DELETE FROM apalis.jobs WHERE status = 'Failed'".to_string(),
-        ))
-        .await
-        .map_err(|e| AppError::internal(format!("Failed to clear jobs: {}", e)))?;
+/// Cancel a pending job
+async fn cancel_job(config: &Config, id: &str, json: bool) -> AppResult<()> {
+    let canceled = job_store(config).await?.cancel(id).await?;
 
-    let count = result.rows_affected();
-    println!("Cleared {} failed job(s) from the queue.", count);
+    if json {
+        return print_json(&json!({ "id": id, "canceled": canceled }));
+    }
+    if canceled {
+        println!("Canceled job {}.", id);
+    } else {
+        println!(
+            "Job {} is not pending; only pending jobs can be canceled.",
+            id
+        );
+    }
+    Ok(())
+}
 
+/// Delete jobs in a status older than the given age
+async fn purge_jobs(config: &Config, status: &str, older_than: &str, json: bool) -> AppResult<()> {
+    let status: JobStatus = status.parse()?;
+    let before = Utc::now()
+        .checked_sub_signed(parse_age(older_than)?)
+        .ok_or_else(|| AppError::validation(format!("Age '{}' is out of range", older_than)))?;
+    let count = job_store(config).await?.purge(status, before).await?;
+
+    if json {
+        return print_json(&json!({ "status": status, "purged": count }));
+    }
+    println!(
+        "Purged {} {} job(s).",
+        count,
+        status.as_str().to_lowercase()
+    );
     Ok(())
 }
+
+/// Clear failed jobs from the queue
+async fn clear_failed_jobs(config: &Config, json: bool) -> AppResult<()> {
+    let count = job_store(config)
+        .await?
+        .purge(JobStatus::Failed, Utc::now())
+        .await?;
+
+    if json {
+        return print_json(&json!({ "cleared": count }));
+    }
+    println!("Cleared {} failed job(s) from the queue.", count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("7d").unwrap(), chrono::Duration::days(7));
+        assert_eq!(parse_age("30m").unwrap(), chrono::Duration::minutes(30));
+        assert_eq!(parse_age("45").unwrap(), chrono::Duration::seconds(45));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+        assert!(parse_age("9223372036854775807w").is_err());
+        assert!(parse_age("99999999999999999999d").is_err());
+    }
+}
@@ -1,9 +1,13 @@
 //! Migrate command - Database migration management.
+//!
+//! `fresh` and `down` drop data, so on a database that does not look local
+//! or like a test database they ask for its name first, or need `--force`.
 
+use super::guard::confirm_destructive;
 use crate::cli::args::{MigrateAction, MigrateArgs};
 use crate::config::Config;
 use crate::errors::{AppError, AppResult};
-use crate::infra::Database;
+use crate::infra::{Database, MigrationDrift, Rollback};
 
 /// Execute the migrate command
 pub async fn execute(args: MigrateArgs, config: Config) -> AppResult<()> {
@@ -15,18 +19,55 @@ pub async fn execute(args: MigrateArgs, config: Config) -> AppResult<()> {
         .map_err(|e| AppError::internal(format!("Database connection failed: {}", e)))?;
 
     match args.action {
-        MigrateAction::Up => {
+        MigrateAction::Up { to, dry_run } => {
+            warn_on_drift(&db).await?;
+            if dry_run {
+                let plan = db
+                    .dry_run_up(to.as_deref())
+                    .await
+                    .map_err(|e| AppError::internal(e.to_string()))?;
+                print_plan(&plan, "No pending migrations");
+                return Ok(());
+            }
+
             tracing::info!("Running pending migrations...");
-            db.run_migrations()
+            let applied = db
+                .migrate_up(to.as_deref())
                 .await
                 .map_err(|e| AppError::internal(e.to_string()))?;
+            for name in &applied {
+                println!("applied: {}", name);
+            }
             tracing::info!("Migrations completed successfully");
         }
-        MigrateAction::Down => {
-            tracing::info!("Rolling back last migration...");
-            db.rollback_migration()
+        MigrateAction::Down {
+            steps,
+            to,
+            dry_run,
+            force,
+        } => {
+            let rollback = match to {
+                Some(to) => Rollback::To(to),
+                None => Rollback::Steps(steps),
+            };
+            if dry_run {
+                let plan = db
+                    .dry_run_down(&rollback)
+                    .await
+                    .map_err(|e| AppError::internal(e.to_string()))?;
+                print_plan(&plan, "No migrations to roll back");
+                return Ok(());
+            }
+
+            confirm_destructive(&config.database_url, "roll back migrations on", force)?;
+            tracing::info!("Rolling back migrations...");
+            let reverted = db
+                .migrate_down(&rollback)
                 .await
                 .map_err(|e| AppError::internal(e.to_string()))?;
+            for name in &reverted {
+                println!("rolled back: {}", name);
+            }
             tracing::info!("Rollback completed successfully");
         }
         MigrateAction::Status => {
@@ -35,12 +76,28 @@ pub async fn execute(args: MigrateArgs, config: Config) -> AppResult<()> {
                 .migration_status()
                 .await
                 .map_err(|e| AppError::internal(e.to_string()))?;
+            let drift = db
+                .migration_drift()
+                .await
+                .map_err(|e| AppError::internal(e.to_string()))?;
             for (name, applied) in status {
-                let status_str = if applied { "applied" } else { "pending" };
+                let status_str = if drift.contains(&MigrationDrift::Changed(name.clone())) {
+                    "applied (changed since it ran)"
+                } else if applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
                 println!("{}: {}", name, status_str);
             }
+            for drift in drift {
+                if let MigrationDrift::Missing(name) = drift {
+                    println!("{}: applied (missing from the code)", name);
+                }
+            }
         }
-        MigrateAction::Fresh => {
+        MigrateAction::Fresh { force } => {
+            confirm_destructive(&config.database_url, "drop every table in", force)?;
             tracing::warn!("Resetting database and running all migrations...");
             db.fresh_migrations()
                 .await
@@ -51,3 +108,36 @@ pub async fn execute(args: MigrateArgs, config: Config) -> AppResult<()> {
 
     Ok(())
 }
+
+/// Print the SQL of each migration in a dry run
+fn print_plan(plan: &[(String, Vec<String>)], empty: &str) {
+    if plan.is_empty() {
+        println!("{}", empty);
+    }
+    for (name, statements) in plan {
+        println!("-- {}", name);
+        for statement in statements {
+            println!("{};", statement);
+        }
+        println!();
+    }
+}
+
+/// Log applied migrations whose code changed since they ran
+async fn warn_on_drift(db: &Database) -> AppResult<()> {
+    let drift = db
+        .migration_drift()
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?;
+    for drift in drift {
+        match drift {
+            MigrationDrift::Changed(name) => {
+                tracing::warn!("Migration {} changed after it was applied", name)
+            }
+            MigrationDrift::Missing(name) => {
+                tracing::warn!("Migration {} is applied but missing from the code", name)
+            }
+        }
+    }
+    Ok(())
+}
@@ -2,7 +2,57 @@
 //!
 //! Each command is implemented in its own module for separation of concerns.
 
+use crate::config::Config;
+use crate::infra::Cache;
+
+pub mod db;
+pub mod email;
 pub mod generate;
+mod guard;
 pub mod jobs;
 pub mod migrate;
 pub mod serve;
+
+/// Redis for invalidating cached users, if reachable.
+///
+/// Commands that change users outside the API need Redis for nothing else;
+/// without it, the stale entries expire with their TTL.
+pub(crate) async fn optional_cache(config: &Config) -> Option<Cache> {
+    match Cache::try_connect(config).await {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            tracing::warn!(
+                "Redis unavailable ({}); cached users expire with their TTL",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Resolve when Ctrl+C or SIGTERM is received.
+pub(crate) async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => tracing::error!("Failed to listen for SIGTERM: {}", e),
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
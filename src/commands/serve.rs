@@ -2,11 +2,17 @@
 
 use std::sync::Arc;
 
+use sea_orm::DatabaseBackend;
+
+use super::shutdown_signal;
 use crate::api::{create_router, AppState};
 use crate::cli::args::ServeArgs;
-use crate::config::Config;
+use crate::config::{Config, LEADER_RESOURCE_MAINTENANCE};
 use crate::errors::{AppError, AppResult};
-use crate::infra::{Cache, Database};
+use crate::infra::{Cache, Database, LeaderElection, RedisEventBus};
+use crate::jobs::{
+    OutboxRelay, PostgresJobQueue, SessionCleanupTask, SingletonScheduler, UserStatsTask,
+};
 
 /// Execute the serve command
 pub async fn execute(args: ServeArgs, config: Config) -> AppResult<()> {
@@ -16,13 +22,36 @@ pub async fn execute(args: ServeArgs, config: Config) -> AppResult<()> {
     let db = Arc::new(Database::connect(&config).await);
     tracing::info!("Database connected");
 
+    // Job tables must exist before the API can enqueue jobs
+    if db.backend() == DatabaseBackend::Postgres {
+        PostgresJobQueue::setup(db.connection().get_postgres_connection_pool()).await?;
+    }
+
     // Initialize Redis cache
     let cache = Arc::new(Cache::connect(&config).await);
     tracing::info!("Redis cache connected");
 
     // Create application state with centralized service container
     // Uses Unit of Work internally for repository access
-    let app_state = AppState::from_config(db, cache, config);
+    let app_state = AppState::from_config(db, cache.clone(), config);
+
+    // Maintenance tasks run only on the elected leader instance; database
+    // cleanup runs as cron jobs in `jobs work`
+    let scheduler = SingletonScheduler::new(LeaderElection::new(
+        cache.clone(),
+        LEADER_RESOURCE_MAINTENANCE,
+    ))
+    .register(SessionCleanupTask::new(cache.clone()))
+    .register(UserStatsTask::new(
+        app_state.user_service.clone(),
+        cache.clone(),
+    ))
+    .register(OutboxRelay::new(
+        app_state.database.get_connection(),
+        app_state.jobs.clone(),
+        Arc::new(RedisEventBus::new(cache.connection())),
+    ))
+    .start();
 
     // Build router
     let app = create_router(app_state);
@@ -35,9 +64,16 @@ pub async fn execute(args: ServeArgs, config: Config) -> AppResult<()> {
 
     tracing::info!("Server running on http://{}", addr);
 
-    axum::serve(listener, app)
+    let result = axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            shutdown_signal().await;
+            tracing::info!("Received shutdown signal, stopping server...");
+        })
         .await
-        .map_err(|e| AppError::internal(format!("Server error: {}", e)))?;
+        .map_err(|e| AppError::internal(format!("Server error: {}", e)));
+
+    // Finish in-flight maintenance and hand leadership to another instance
+    scheduler.shutdown().await;
 
-    Ok(())
+    result
 }
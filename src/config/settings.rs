@@ -2,31 +2,109 @@
 
 use std::env;
 
+use crate::domain::LocalPartPolicy;
+
 use super::constants::{
-    DEFAULT_DATABASE_URL, DEFAULT_JWT_EXPIRATION_HOURS, DEFAULT_REDIS_URL, DEFAULT_SERVER_HOST,
-    DEFAULT_SERVER_PORT, MIN_JWT_SECRET_LENGTH,
+    DEFAULT_DATABASE_ACQUIRE_TIMEOUT_SECONDS, DEFAULT_DATABASE_IDLE_TIMEOUT_SECONDS,
+    DEFAULT_DATABASE_MAX_CONNECTIONS, DEFAULT_DATABASE_MIN_CONNECTIONS, DEFAULT_DATABASE_URL,
+    DEFAULT_DELETED_USER_RETENTION_DAYS, DEFAULT_DONE_JOB_RETENTION_DAYS,
+    DEFAULT_JWT_EXPIRATION_HOURS, DEFAULT_REDIS_URL, DEFAULT_REQUIRE_IF_MATCH, DEFAULT_SERVER_HOST,
+    DEFAULT_SERVER_PORT, DEFAULT_USER_PURGE_BATCH_SIZE, MIN_JWT_SECRET_LENGTH,
 };
 
 /// Application configuration
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
+    /// What `serve` does with pending migrations (`auto`, `check`, `off`).
+    /// When unset, they are applied.
+    pub migrations_on_start: Option<String>,
+    /// Read replica for lists, counts and searches outside transactions.
+    /// None = primary.
+    pub database_replica_url: Option<String>,
+    /// Maximum connections per pool (primary and replica each)
+    pub database_max_connections: u32,
+    /// Idle connections kept open per pool
+    pub database_min_connections: u32,
+    /// Seconds to wait for a free pooled connection
+    pub database_acquire_timeout_seconds: u64,
+    /// Seconds an unused connection stays open
+    pub database_idle_timeout_seconds: u64,
+    /// Abort statements running longer than this (PostgreSQL). None = never.
+    /// Startup migrations and their lock wait are exempt.
+    pub database_statement_timeout_ms: Option<u64>,
     pub redis_url: String,
+    /// Explicit Redis topology (`standalone`, `sentinel`, `cluster`).
+    /// When unset, the `redis_url` scheme decides.
+    pub redis_mode: Option<String>,
+    /// Independent Redis nodes for Redlock. Empty = single-node locks.
+    pub redis_lock_nodes: Vec<String>,
+    /// Serialization format for cached values (`json`, `msgpack`)
+    pub cache_codec: Option<String>,
+    /// Compress cached values larger than this many bytes. None = never.
+    pub cache_compression_threshold: Option<usize>,
     jwt_secret: String,
     pub jwt_expiration_hours: i64,
     pub server_host: String,
     pub server_port: u16,
+    /// Reject user updates without an `If-Match` header (428)
+    pub require_if_match: bool,
+    /// How the part of an email before `@` is stored (`EMAIL_LOCAL_PART`:
+    /// `preserve`, `lowercase`, `strip_tag`). When unset, it is kept as typed.
+    /// Applies to emails as they are written; `db normalize-emails`
+    /// rewrites existing users after a change.
+    pub email_local_part: LocalPartPolicy,
+    /// Days a soft-deleted user is kept before being purged (at least 1)
+    pub deleted_user_retention_days: i64,
+    /// Users purged per transaction
+    pub user_purge_batch_size: u64,
+    /// Days finished background jobs are kept before being pruned
+    pub done_job_retention_days: i64,
 }
 
 impl std::fmt::Debug for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Config")
             .field("database_url", &"[REDACTED]")
+            .field("migrations_on_start", &self.migrations_on_start)
+            .field(
+                "database_replica_url",
+                &self.database_replica_url.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("database_max_connections", &self.database_max_connections)
+            .field("database_min_connections", &self.database_min_connections)
+            .field(
+                "database_acquire_timeout_seconds",
+                &self.database_acquire_timeout_seconds,
+            )
+            .field(
+                "database_idle_timeout_seconds",
+                &self.database_idle_timeout_seconds,
+            )
+            .field(
+                "database_statement_timeout_ms",
+                &self.database_statement_timeout_ms,
+            )
             .field("redis_url", &"[REDACTED]")
+            .field("redis_mode", &self.redis_mode)
+            .field("redis_lock_nodes", &self.redis_lock_nodes.len())
+            .field("cache_codec", &self.cache_codec)
+            .field(
+                "cache_compression_threshold",
+                &self.cache_compression_threshold,
+            )
             .field("jwt_secret", &"[REDACTED]")
             .field("jwt_expiration_hours", &self.jwt_expiration_hours)
             .field("server_host", &self.server_host)
             .field("server_port", &self.server_port)
+            .field("require_if_match", &self.require_if_match)
+            .field("email_local_part", &self.email_local_part)
+            .field(
+                "deleted_user_retention_days",
+                &self.deleted_user_retention_days,
+            )
+            .field("user_purge_batch_size", &self.user_purge_batch_size)
+            .field("done_job_retention_days", &self.done_job_retention_days)
             .finish()
     }
 }
@@ -35,7 +113,8 @@ impl Config {
     /// Load configuration from environment variables.
     ///
     /// # Panics
-    /// Panics if JWT_SECRET is not set or is too short (security requirement).
+    /// Panics if JWT_SECRET is not set or is too short (security requirement),
+    /// or if EMAIL_LOCAL_PART names an unknown policy.
     pub fn from_env() -> Self {
         dotenvy::dotenv().ok();
 
@@ -61,8 +140,44 @@ impl Config {
         Self {
             database_url: env::var("DATABASE_URL")
                 .unwrap_or_else(|_| DEFAULT_DATABASE_URL.to_string()),
+            migrations_on_start: env::var("MIGRATIONS_ON_START").ok(),
+            database_replica_url: env::var("DATABASE_REPLICA_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            database_max_connections: env::var("DATABASE_MAX_CONNECTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DATABASE_MAX_CONNECTIONS),
+            database_min_connections: env::var("DATABASE_MIN_CONNECTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DATABASE_MIN_CONNECTIONS),
+            database_acquire_timeout_seconds: env::var("DATABASE_ACQUIRE_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DATABASE_ACQUIRE_TIMEOUT_SECONDS),
+            database_idle_timeout_seconds: env::var("DATABASE_IDLE_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DATABASE_IDLE_TIMEOUT_SECONDS),
+            database_statement_timeout_ms: env::var("DATABASE_STATEMENT_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
             redis_url: env::var("REDIS_URL")
                 .unwrap_or_else(|_| DEFAULT_REDIS_URL.to_string()),
+            redis_mode: env::var("REDIS_MODE").ok(),
+            redis_lock_nodes: env::var("REDIS_LOCK_NODES")
+                .map(|v| {
+                    v.split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            cache_codec: env::var("CACHE_CODEC").ok(),
+            cache_compression_threshold: env::var("CACHE_COMPRESSION_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok()),
             jwt_secret,
             jwt_expiration_hours: env::var("JWT_EXPIRATION_HOURS")
                 .ok()
@@ -74,6 +189,31 @@ impl Config {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(DEFAULT_SERVER_PORT),
+            require_if_match: env::var("REQUIRE_IF_MATCH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_REQUIRE_IF_MATCH),
+            email_local_part: env::var("EMAIL_LOCAL_PART")
+                .ok()
+                .map(|v| {
+                    v.parse()
+                        .unwrap_or_else(|e| panic!("Invalid EMAIL_LOCAL_PART: {}", e))
+                })
+                .unwrap_or_default(),
+            deleted_user_retention_days: env::var("DELETED_USER_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|days| *days > 0)
+                .unwrap_or(DEFAULT_DELETED_USER_RETENTION_DAYS),
+            user_purge_batch_size: env::var("USER_PURGE_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(DEFAULT_USER_PURGE_BATCH_SIZE),
+            done_job_retention_days: env::var("DONE_JOB_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DONE_JOB_RETENTION_DAYS),
         }
     }
 
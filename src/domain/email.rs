@@ -0,0 +1,137 @@
+//! Email address value object - Normalized user identity.
+//!
+//! Emails identify users case-insensitively: `Alice@X.com` and
+//! `alice@x.com` are the same account. [`EmailAddress`] trims the input,
+//! lowercases the domain and applies a [`LocalPartPolicy`] to the part
+//! before `@`; the database enforces uniqueness on `lower(email)`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::{
+    EMAIL_LOCAL_PART_LOWERCASE, EMAIL_LOCAL_PART_PRESERVE, EMAIL_LOCAL_PART_STRIP_TAG,
+};
+use crate::errors::{AppError, AppResult};
+
+/// How the local part (before `@`) is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LocalPartPolicy {
+    /// Keep it as typed; lookups still ignore case
+    #[default]
+    Preserve,
+    /// Lowercase it
+    Lowercase,
+    /// Lowercase it and drop a `+tag` suffix (`bob+news` -> `bob`)
+    StripTag,
+}
+
+impl FromStr for LocalPartPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            EMAIL_LOCAL_PART_PRESERVE => Ok(Self::Preserve),
+            EMAIL_LOCAL_PART_LOWERCASE => Ok(Self::Lowercase),
+            EMAIL_LOCAL_PART_STRIP_TAG => Ok(Self::StripTag),
+            other => Err(format!("unknown EMAIL_LOCAL_PART policy '{}'", other)),
+        }
+    }
+}
+
+/// Normalized email address.
+///
+/// DDD: Value object - immutable, compared by value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    /// Normalize `raw` under `policy`.
+    ///
+    /// # Errors
+    /// Returns validation error unless there is exactly one `@` with text
+    /// on both sides.
+    pub fn parse(raw: &str, policy: LocalPartPolicy) -> AppResult<Self> {
+        let invalid = || AppError::validation("Invalid email format");
+        let (local, domain) = raw.trim().split_once('@').ok_or_else(invalid)?;
+        if local.is_empty() || domain.is_empty() || domain.contains('@') {
+            return Err(invalid());
+        }
+
+        let local = match policy {
+            LocalPartPolicy::Preserve => local.to_string(),
+            LocalPartPolicy::Lowercase => local.to_lowercase(),
+            LocalPartPolicy::StripTag => match local.split_once('+') {
+                Some((base, _)) if !base.is_empty() => base.to_lowercase(),
+                _ => local.to_lowercase(),
+            },
+        };
+        Ok(Self(format!("{}@{}", local, domain.to_lowercase())))
+    }
+
+    /// Get the normalized address.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consume and return the normalized address.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<EmailAddress> for String {
+    fn from(email: EmailAddress) -> Self {
+        email.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(raw: &str, policy: LocalPartPolicy) -> String {
+        EmailAddress::parse(raw, policy).unwrap().into_string()
+    }
+
+    #[test]
+    fn test_domain_is_lowercased_and_input_trimmed() {
+        assert_eq!(
+            normalize("  Alice@Example.COM ", LocalPartPolicy::Preserve),
+            "Alice@example.com"
+        );
+    }
+
+    #[test]
+    fn test_local_part_policies() {
+        let raw = "Bob+News@Example.com";
+        assert_eq!(
+            normalize(raw, LocalPartPolicy::Preserve),
+            "Bob+News@example.com"
+        );
+        assert_eq!(
+            normalize(raw, LocalPartPolicy::Lowercase),
+            "bob+news@example.com"
+        );
+        assert_eq!(normalize(raw, LocalPartPolicy::StripTag), "bob@example.com");
+        assert_eq!(normalize("+x@a.io", LocalPartPolicy::StripTag), "+x@a.io");
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        for raw in ["", "alice", "@example.com", "alice@", "a@b@c"] {
+            assert!(EmailAddress::parse(raw, LocalPartPolicy::Preserve).is_err());
+        }
+    }
+
+    #[test]
+    fn test_policy_from_str() {
+        assert_eq!("Strip_Tag".parse(), Ok(LocalPartPolicy::StripTag));
+        assert!("upper".parse::<LocalPartPolicy>().is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Domain events - Facts about what happened to users.
+//!
+//! Services write the events of a change to the transactional outbox in
+//! the same transaction; the outbox relay hands them to the [`EventBus`]
+//! once it commits. Delivery (Redis Streams, in-process, none) is an
+//! infrastructure concern.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::UserRole;
+use crate::errors::AppResult;
+
+#[cfg(any(test, feature = "test-utils"))]
+use mockall::automock;
+
+/// Something that happened to a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserEvent {
+    /// A new account was registered
+    Registered { user_id: Uuid },
+    /// The user logged in successfully
+    LoggedIn { user_id: Uuid },
+    /// Profile details changed
+    Updated { user_id: Uuid },
+    /// The user's role changed
+    RoleChanged {
+        user_id: Uuid,
+        from: UserRole,
+        to: UserRole,
+    },
+    /// The user was soft deleted
+    Deleted { user_id: Uuid },
+    /// A soft-deleted user was restored
+    Restored { user_id: Uuid },
+    /// The user was permanently removed
+    Purged { user_id: Uuid },
+}
+
+impl UserEvent {
+    /// Stable event name used for routing and filtering.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Registered { .. } => "user.registered",
+            Self::LoggedIn { .. } => "user.logged_in",
+            Self::Updated { .. } => "user.updated",
+            Self::RoleChanged { .. } => "user.role_changed",
+            Self::Deleted { .. } => "user.deleted",
+            Self::Restored { .. } => "user.restored",
+            Self::Purged { .. } => "user.purged",
+        }
+    }
+
+    /// The user the event is about.
+    pub fn user_id(&self) -> Uuid {
+        match self {
+            Self::Registered { user_id }
+            | Self::LoggedIn { user_id }
+            | Self::Updated { user_id }
+            | Self::RoleChanged { user_id, .. }
+            | Self::Deleted { user_id }
+            | Self::Restored { user_id }
+            | Self::Purged { user_id } => *user_id,
+        }
+    }
+}
+
+/// Event envelope with identity and timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainEvent {
+    /// Unique event ID (consumers can use it for deduplication)
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub event: UserEvent,
+}
+
+impl DomainEvent {
+    /// Wrap an event that happened now.
+    pub fn new(event: UserEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            occurred_at: Utc::now(),
+            event,
+        }
+    }
+
+    /// Stable event name used for routing and filtering.
+    pub fn name(&self) -> &'static str {
+        self.event.name()
+    }
+}
+
+impl From<UserEvent> for DomainEvent {
+    fn from(event: UserEvent) -> Self {
+        Self::new(event)
+    }
+}
+
+/// Publisher of domain events.
+#[cfg_attr(any(test, feature = "test-utils"), automock)]
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    /// Publish an event to subscribers.
+    async fn publish(&self, event: DomainEvent) -> AppResult<()>;
+}
+
+/// Event bus that discards every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopEventBus;
+
+#[async_trait]
+impl EventBus for NoopEventBus {
+    async fn publish(&self, _event: DomainEvent) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization_is_tagged() {
+        let user_id = Uuid::new_v4();
+        let event = DomainEvent::new(UserEvent::RoleChanged {
+            user_id,
+            from: UserRole::User,
+            to: UserRole::Admin,
+        });
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"]["type"], "role_changed");
+        assert_eq!(json["event"]["to"], "admin");
+
+        let parsed: DomainEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, event);
+        assert_eq!(parsed.name(), "user.role_changed");
+        assert_eq!(parsed.event.user_id(), user_id);
+    }
+}
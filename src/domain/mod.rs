@@ -6,8 +6,19 @@
 //! DDD: Domain layer has NO external dependencies (except error types).
 //! Contains: Entities, Value Objects, Domain Services.
 
+pub mod email;
+pub mod events;
 pub mod password;
+pub mod search;
 pub mod user;
 
+pub use email::{EmailAddress, LocalPartPolicy};
+pub use events::{DomainEvent, EventBus, NoopEventBus, UserEvent};
 pub use password::Password;
+pub use search::{
+    Highlight, HighlightField, SearchQuery, UserSearch, UserSearchHit, UserSearchResult,
+};
 pub use user::{CreateUser, UpdateUser, User, UserResponse, UserRole};
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use events::MockEventBus;
@@ -0,0 +1,340 @@
+//! User search - Query terms, ranked hits and match highlights.
+//!
+//! [`SearchQuery`] splits the input into lowercase alphanumeric terms. On
+//! PostgreSQL they become a prefix `tsquery` and the raw text feeds trigram
+//! similarity, so partial words and typos still match; elsewhere each term
+//! must appear in the name or email. Highlights mark where terms occur, or
+//! for a misspelt term the closest substring.
+
+use std::ops::Range;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::{User, UserResponse};
+use crate::config::MAX_SEARCH_QUERY_LENGTH;
+use crate::errors::{AppError, AppResult};
+
+/// Parsed search input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    text: String,
+    terms: Vec<String>,
+}
+
+impl SearchQuery {
+    /// Parse `raw` into terms.
+    ///
+    /// # Errors
+    /// Returns validation error if it has no letters or digits, or is
+    /// longer than [`MAX_SEARCH_QUERY_LENGTH`] characters.
+    pub fn parse(raw: &str) -> AppResult<Self> {
+        let text = raw.trim();
+        if text.chars().count() > MAX_SEARCH_QUERY_LENGTH {
+            return Err(AppError::validation(format!(
+                "Search query must be at most {} characters",
+                MAX_SEARCH_QUERY_LENGTH
+            )));
+        }
+
+        let terms: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        if terms.is_empty() {
+            return Err(AppError::validation("Search query cannot be empty"));
+        }
+
+        Ok(Self {
+            text: text.to_lowercase(),
+            terms,
+        })
+    }
+
+    /// Lowercased input, for similarity matching
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Lowercase alphanumeric terms
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Prefix query for `to_tsquery`: `ada:* & love:*`
+    pub fn tsquery(&self) -> String {
+        self.terms
+            .iter()
+            .map(|term| format!("{}:*", term))
+            .collect::<Vec<_>>()
+            .join(" & ")
+    }
+
+    /// Where the terms occur in the user's name and email.
+    ///
+    /// A term found in neither, as in hits matched by trigram similarity
+    /// alone, highlights its closest substring instead.
+    pub fn highlights(&self, user: &User) -> Vec<Highlight> {
+        let name: Vec<char> = user.name.chars().map(fold).collect();
+        let email: Vec<char> = user.email.chars().map(fold).collect();
+        let mut in_name = vec![false; name.len()];
+        let mut in_email = vec![false; email.len()];
+
+        for term in &self.terms {
+            let term: Vec<char> = term.chars().collect();
+            let found = mark_occurrences(&name, &term, &mut in_name)
+                | mark_occurrences(&email, &term, &mut in_email);
+            if found {
+                continue;
+            }
+
+            // Name wins ties, like the weighting on PostgreSQL
+            let near_name = closest_substring(&name, &term);
+            let near_email = closest_substring(&email, &term);
+            match (near_name, near_email) {
+                (Some((n, range)), Some((e, _))) if n <= e => in_name[range].fill(true),
+                (Some((_, range)), None) => in_name[range].fill(true),
+                (_, Some((_, range))) => in_email[range].fill(true),
+                (None, None) => {}
+            }
+        }
+
+        let mut highlights = merge(HighlightField::Name, &in_name);
+        highlights.extend(merge(HighlightField::Email, &in_email));
+        highlights
+    }
+
+    /// Share of the name and email covered by highlights, from 0 to 1.
+    pub fn coverage(&self, user: &User) -> f64 {
+        let length = user.name.chars().count() + user.email.chars().count();
+        let covered: usize = self
+            .highlights(user)
+            .iter()
+            .map(|highlight| highlight.end - highlight.start)
+            .sum();
+        if length == 0 {
+            0.0
+        } else {
+            covered as f64 / length as f64
+        }
+    }
+}
+
+/// Mark where `term` occurs in `value`; false if it does not
+fn mark_occurrences(value: &[char], term: &[char], matched: &mut [bool]) -> bool {
+    if term.len() > value.len() {
+        return false;
+    }
+    let mut found = false;
+    for start in 0..=value.len() - term.len() {
+        if value[start..start + term.len()] == term[..] {
+            matched[start..start + term.len()].fill(true);
+            found = true;
+        }
+    }
+    found
+}
+
+/// Substring of `value` with the fewest edits from `term`, and that number.
+///
+/// Only substrings at most one character longer or shorter than the term
+/// are tried, and a match needs at most half the term's length in edits.
+fn closest_substring(value: &[char], term: &[char]) -> Option<(usize, Range<usize>)> {
+    let mut best: Option<(usize, Range<usize>)> = None;
+    for start in 0..value.len() {
+        for length in term.len().saturating_sub(1).max(1)..=term.len() + 1 {
+            if start + length > value.len() {
+                break;
+            }
+            let distance = edit_distance(&value[start..start + length], term);
+            if best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                best = Some((distance, start..start + length));
+            }
+        }
+    }
+    best.filter(|(distance, _)| distance * 2 <= term.len())
+}
+
+/// Levenshtein distance between two character slices
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Runs of matched characters as highlights of `field`
+fn merge(field: HighlightField, matched: &[bool]) -> Vec<Highlight> {
+    let mut highlights = Vec::new();
+    let mut start = None;
+    for (index, is_match) in matched.iter().chain([&false]).enumerate() {
+        match (start, is_match) {
+            (None, true) => start = Some(index),
+            (Some(from), false) => {
+                highlights.push(Highlight {
+                    field,
+                    start: from,
+                    end: index,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    highlights
+}
+
+/// Lowercase a character without changing the character count
+fn fold(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(single), None) => single,
+        _ => c,
+    }
+}
+
+/// Field a highlight refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightField {
+    Name,
+    Email,
+}
+
+/// Matched range of a field, in characters (end exclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Highlight {
+    pub field: HighlightField,
+    #[schema(example = 4)]
+    pub start: usize,
+    #[schema(example = 8)]
+    pub end: usize,
+}
+
+/// Search request with offset pagination.
+#[derive(Debug, Clone)]
+pub struct UserSearch {
+    pub query: SearchQuery,
+    pub limit: u64,
+    pub offset: u64,
+}
+
+/// A user matching a search.
+#[derive(Debug, Clone)]
+pub struct UserSearchHit {
+    pub user: User,
+    /// Higher is better; only comparable within one search
+    pub rank: f64,
+    pub highlights: Vec<Highlight>,
+}
+
+/// Search hit for API responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchResult {
+    pub user: UserResponse,
+    #[schema(example = 0.42)]
+    pub rank: f64,
+    pub highlights: Vec<Highlight>,
+}
+
+impl From<UserSearchHit> for UserSearchResult {
+    fn from(hit: UserSearchHit) -> Self {
+        Self {
+            user: UserResponse::from(hit.user),
+            rank: hit.rank,
+            highlights: hit.highlights,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str, email: &str) -> User {
+        User::new(
+            uuid::Uuid::new_v4(),
+            email.to_string(),
+            "hash".to_string(),
+            name.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        let query = SearchQuery::parse("  Ada LOVE-lace ").unwrap();
+        assert_eq!(query.terms(), ["ada", "love", "lace"]);
+        assert_eq!(query.text(), "ada love-lace");
+        assert_eq!(query.tsquery(), "ada:* & love:* & lace:*");
+
+        assert!(SearchQuery::parse(" -- ").is_err());
+        assert!(SearchQuery::parse(&"a".repeat(MAX_SEARCH_QUERY_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_highlights_merge_and_ignore_case() {
+        let query = SearchQuery::parse("love lace ada").unwrap();
+        let highlights = query.highlights(&user("Ada Lovelace", "ada@example.com"));
+        assert_eq!(
+            highlights,
+            vec![
+                Highlight {
+                    field: HighlightField::Name,
+                    start: 0,
+                    end: 3
+                },
+                Highlight {
+                    field: HighlightField::Name,
+                    start: 4,
+                    end: 12
+                },
+                Highlight {
+                    field: HighlightField::Email,
+                    start: 0,
+                    end: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_misspelt_terms_highlight_the_closest_substring() {
+        let query = SearchQuery::parse("lovleace ada").unwrap();
+        let highlights = query.highlights(&user("Ada Lovelace", "countess@example.com"));
+        assert_eq!(
+            highlights,
+            vec![
+                Highlight {
+                    field: HighlightField::Name,
+                    start: 0,
+                    end: 3
+                },
+                Highlight {
+                    field: HighlightField::Name,
+                    start: 4,
+                    end: 12
+                },
+            ]
+        );
+
+        // Too far from anything to highlight
+        let query = SearchQuery::parse("zzzzzz").unwrap();
+        assert!(query.highlights(&user("Ada", "ada@x.io")).is_empty());
+    }
+
+    #[test]
+    fn test_coverage_prefers_closer_matches() {
+        let query = SearchQuery::parse("grace").unwrap();
+        let exact = user("Grace", "grace@x.io");
+        let partial = user("Grace Hopper", "hopper@example.com");
+        assert!(query.coverage(&exact) > query.coverage(&partial));
+        assert_eq!(query.coverage(&user("Bob", "bob@x.io")), 0.0);
+    }
+}
@@ -62,7 +62,8 @@ impl std::fmt::Display for UserRole {
 pub struct User {
     pub id: Uuid,
     pub email: String,
-    #[serde(skip_serializing)]
+    /// Never serialized; empty when a user is read back from the cache
+    #[serde(skip_serializing, default)]
     pub password_hash: String,
     pub name: String,
     pub role: UserRole,
@@ -71,6 +72,9 @@ pub struct User {
     /// Soft delete timestamp (None = active, Some = deleted)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deleted_at: Option<DateTime<Utc>>,
+    /// Row version, incremented on every write; 0 when unknown
+    #[serde(default)]
+    pub version: i32,
 }
 
 impl User {
@@ -86,6 +90,7 @@ impl User {
             created_at: now,
             updated_at: now,
             deleted_at: None,
+            version: 1,
         }
     }
 
@@ -94,6 +99,11 @@ impl User {
         self.role.is_admin()
     }
 
+    /// Entity tag of this version of the user, e.g. `"3"`
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.version)
+    }
+
     /// Check if user is soft deleted
     pub fn is_deleted(&self) -> bool {
         self.deleted_at.is_some()
@@ -32,6 +32,12 @@ pub enum AppError {
     #[error("{0} already exists")]
     Conflict(String),
 
+    #[error("Resource was modified, fetch it again and retry")]
+    PreconditionFailed,
+
+    #[error("If-Match header is required")]
+    PreconditionRequired,
+
     // Validation
     #[error("{0}")]
     Validation(String),
@@ -72,6 +78,8 @@ impl AppError {
             AppError::InvalidCredentials => "INVALID_CREDENTIALS",
             AppError::NotFound => "NOT_FOUND",
             AppError::Conflict(_) => "CONFLICT",
+            AppError::PreconditionFailed => "PRECONDITION_FAILED",
+            AppError::PreconditionRequired => "PRECONDITION_REQUIRED",
             AppError::Validation(_) => "VALIDATION_ERROR",
             AppError::BadRequest(_) => "BAD_REQUEST",
             AppError::Database(_) => "DATABASE_ERROR",
@@ -89,6 +97,8 @@ impl AppError {
             AppError::Forbidden => StatusCode::FORBIDDEN,
             AppError::NotFound => StatusCode::NOT_FOUND,
             AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            AppError::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
             AppError::Validation(_) | AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
             AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -2,25 +2,143 @@
 //!
 //! Provides a type-safe caching layer with connection pooling,
 //! distributed locks, and semaphores for concurrency control.
+//! Works against standalone, Sentinel and Cluster deployments, and
+//! switches locks to Redlock when independent lock nodes are configured.
 
-use redis::{aio::ConnectionManager, AsyncCommands, Client, RedisError};
+use redis::{AsyncCommands, ErrorKind, RedisError};
 use serde::{de::DeserializeOwned, Serialize};
 use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
 use uuid::Uuid;
 
+use super::codec::{CacheCodec, CacheFormat};
+use super::redis_client::{BlockingPool, CacheConnection, RedisTopology};
+use super::redlock::Redlock;
 use crate::config::{
     Config, CACHE_PREFIX_LOCK, CACHE_PREFIX_RATE_LIMIT, CACHE_PREFIX_SEMAPHORE,
-    CACHE_PREFIX_SESSION, CACHE_PREFIX_USER, DEFAULT_CACHE_TTL_SECONDS,
-    DEFAULT_LOCK_RETRY_DELAY_MS, DEFAULT_LOCK_RETRIES, DEFAULT_LOCK_TTL_SECONDS,
+    CACHE_PREFIX_SESSION, CACHE_PREFIX_USER, DEFAULT_CACHE_TTL_SECONDS, DEFAULT_LOCK_RETRIES,
+    DEFAULT_LOCK_RETRY_DELAY_MS, DEFAULT_LOCK_TTL_SECONDS, SEMAPHORE_BLOCKING_POOL_SIZE,
+    SEMAPHORE_WAITER_TTL_MS, SEMAPHORE_WAIT_SLICE_MS, SEMAPHORE_WAKE_TTL_MS,
 };
 use crate::domain::User;
 use crate::errors::{AppError, AppResult};
 
+/// Lua script: delete the key only if we still own it.
+pub(super) const RELEASE_LOCK_SCRIPT: &str = r#"
+    if redis.call("GET", KEYS[1]) == ARGV[1] then
+        return redis.call("DEL", KEYS[1])
+    else
+        return 0
+    end
+"#;
+
+/// Lua script: extend the key TTL only if we still own it.
+pub(super) const EXTEND_LOCK_SCRIPT: &str = r#"
+    if redis.call("GET", KEYS[1]) == ARGV[1] then
+        return redis.call("EXPIRE", KEYS[1], ARGV[2])
+    else
+        return 0
+    end
+"#;
+
+/// Lua script: append a ticket to a fair semaphore queue, returning its position.
+///
+/// KEYS: queue, waiters, seq. ARGV: ticket, waiter TTL (ms), key TTL (ms).
+const FAIR_ENQUEUE_SCRIPT: &str = r#"
+    local t = redis.call("TIME")
+    local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+    local seq = redis.call("INCR", KEYS[3])
+    redis.call("ZADD", KEYS[1], seq, ARGV[1])
+    redis.call("ZADD", KEYS[2], now + tonumber(ARGV[2]), ARGV[1])
+    for i = 1, 3 do redis.call("PEXPIRE", KEYS[i], ARGV[3]) end
+    return redis.call("ZRANK", KEYS[1], ARGV[1])
+"#;
+
+/// Lua script: take a permit if the ticket is within the free slots at the
+/// head of the queue. Expired holders and waiters that stopped heartbeating
+/// are purged first. Returns `{result, tickets to wake}`: result is -1 when
+/// acquired, -2 when the ticket is no longer queued, otherwise the ticket's
+/// queue position. Tickets to wake are the waiters that can take slots
+/// freed by the purge.
+///
+/// KEYS: holders, queue, waiters, the ticket's wake list.
+/// ARGV: ticket, max permits, permit TTL (ms), waiter TTL (ms), key TTL (ms).
+const FAIR_ACQUIRE_SCRIPT: &str = r#"
+    local t = redis.call("TIME")
+    local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+    local reaped = redis.call("ZREMRANGEBYSCORE", KEYS[1], "-inf", now)
+    for _, stale in ipairs(redis.call("ZRANGEBYSCORE", KEYS[3], "-inf", now)) do
+        redis.call("ZREM", KEYS[2], stale)
+        redis.call("ZREM", KEYS[3], stale)
+        reaped = reaped + 1
+    end
+
+    local rank = redis.call("ZRANK", KEYS[2], ARGV[1])
+    local result = -2
+    if rank then
+        result = rank
+        if rank < tonumber(ARGV[2]) - redis.call("ZCARD", KEYS[1]) then
+            redis.call("ZREM", KEYS[2], ARGV[1])
+            redis.call("ZREM", KEYS[3], ARGV[1])
+            redis.call("ZADD", KEYS[1], now + tonumber(ARGV[3]), ARGV[1])
+            redis.call("DEL", KEYS[4])
+            result = -1
+        else
+            redis.call("ZADD", KEYS[3], now + tonumber(ARGV[4]), ARGV[1])
+        end
+        for i = 1, 3 do redis.call("PEXPIRE", KEYS[i], ARGV[5]) end
+    end
+
+    local wake = {}
+    local free = tonumber(ARGV[2]) - redis.call("ZCARD", KEYS[1])
+    if reaped > 0 and free > 0 then
+        for _, ticket in ipairs(redis.call("ZRANGE", KEYS[2], 0, free - 1)) do
+            if ticket ~= ARGV[1] then table.insert(wake, ticket) end
+        end
+    end
+    return {result, wake}
+"#;
+
+/// Lua script: remove a ticket (holder or waiter). Returns `{removed,
+/// tickets to wake}`, the waiters that can now take the freed slots.
+///
+/// KEYS: holders, queue, waiters. ARGV: ticket, max permits.
+const FAIR_RELEASE_SCRIPT: &str = r#"
+    local removed = redis.call("ZREM", KEYS[1], ARGV[1])
+    redis.call("ZREM", KEYS[2], ARGV[1])
+    redis.call("ZREM", KEYS[3], ARGV[1])
+    local wake = {}
+    local free = tonumber(ARGV[2]) - redis.call("ZCARD", KEYS[1])
+    if free > 0 then
+        wake = redis.call("ZRANGE", KEYS[2], 0, free - 1)
+    end
+    return {removed, wake}
+"#;
+
+/// Lua script: push a holder's expiry forward if it still holds a permit.
+///
+/// KEYS: holders. ARGV: ticket, permit TTL (ms).
+const FAIR_EXTEND_SCRIPT: &str = r#"
+    if not redis.call("ZSCORE", KEYS[1], ARGV[1]) then
+        return 0
+    end
+    local t = redis.call("TIME")
+    local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+    redis.call("ZADD", KEYS[1], now + tonumber(ARGV[2]), ARGV[1])
+    return 1
+"#;
+
 /// Redis cache wrapper with connection pooling.
 #[derive(Clone)]
 pub struct Cache {
-    connection: ConnectionManager,
+    connection: CacheConnection,
+    /// Dedicated connections for fair semaphore waiters blocked on `BLPOP`
+    blocking: Arc<BlockingPool>,
+    /// Present when locks are spread across independent nodes (Redlock)
+    redlock: Option<Arc<Redlock>>,
+    /// Encoding for values written by `set`; reads accept any known encoding
+    codec: CacheCodec,
     default_ttl: u64,
 }
 
@@ -30,53 +148,89 @@ impl Cache {
     /// # Panics
     /// Panics if Redis connection fails.
     pub async fn connect(config: &Config) -> Self {
-        let client = Client::open(config.redis_url.as_str())
-            .expect("Failed to create Redis client");
-
-        let connection = ConnectionManager::new(client)
+        let cache = Self::try_connect(config)
             .await
             .expect("Failed to connect to Redis");
 
         tracing::info!("Redis cache connected");
 
-        Self {
-            connection,
-            default_ttl: DEFAULT_CACHE_TTL_SECONDS,
-        }
+        cache
     }
 
     /// Try to connect to Redis, returning an error instead of panicking.
     pub async fn try_connect(config: &Config) -> Result<Self, RedisError> {
-        let client = Client::open(config.redis_url.as_str())?;
-        let connection = ConnectionManager::new(client).await?;
+        let topology = RedisTopology::parse(&config.redis_url, config.redis_mode.as_deref())?;
+        let connection = CacheConnection::open(&topology).await?;
+
+        let redlock = if config.redis_lock_nodes.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Redlock::connect(&config.redis_lock_nodes).await?))
+        };
+
+        let format: CacheFormat = match config.cache_codec.as_deref() {
+            Some(name) => name.parse().map_err(|e: String| {
+                RedisError::from((ErrorKind::InvalidClientConfig, "Invalid cache codec", e))
+            })?,
+            None => CacheFormat::default(),
+        };
+        let codec = match config.cache_compression_threshold {
+            Some(threshold) => CacheCodec::new(format).with_compression(threshold),
+            None => CacheCodec::new(format),
+        };
 
         Ok(Self {
+            blocking: Arc::new(BlockingPool::new(
+                connection.clone(),
+                SEMAPHORE_BLOCKING_POOL_SIZE,
+            )),
             connection,
+            redlock,
+            codec,
             default_ttl: DEFAULT_CACHE_TTL_SECONDS,
         })
     }
 
-    /// Get the connection manager for direct Redis operations.
-    pub fn connection(&self) -> ConnectionManager {
+    /// Get the connection for direct Redis operations.
+    ///
+    /// Returns a [`CacheConnection`] rather than a `ConnectionManager`, so
+    /// the same accessor works for Sentinel and Cluster. It implements
+    /// `ConnectionLike`; code calling `AsyncCommands` on the result is
+    /// unaffected, but code naming the `ConnectionManager` type must switch.
+    pub fn connection(&self) -> CacheConnection {
         self.connection.clone()
     }
 
+    /// Whether locks use Redlock quorum across independent nodes.
+    pub fn uses_redlock(&self) -> bool {
+        self.redlock.is_some()
+    }
+
+    /// Codec used for newly written values.
+    pub fn codec(&self) -> CacheCodec {
+        self.codec
+    }
+
     // =========================================================================
     // Generic Cache Operations
     // =========================================================================
 
     /// Get a value from cache.
+    ///
+    /// A stored value that cannot be decoded (e.g. after a type change) is
+    /// treated as a miss so callers fall back to the source of truth.
     pub async fn get<T: DeserializeOwned>(&self, key: &str) -> AppResult<Option<T>> {
         let mut conn = self.connection.clone();
-        let value: Option<String> = conn.get(key).await.map_err(cache_error)?;
+        let value: Option<Vec<u8>> = conn.get(key).await.map_err(cache_error)?;
 
         match value {
-            Some(json) => {
-                let parsed = serde_json::from_str(&json).map_err(|e| {
-                    AppError::internal(format!("Cache deserialization error: {}", e))
-                })?;
-                Ok(Some(parsed))
-            }
+            Some(bytes) => match CacheCodec::decode(&bytes) {
+                Ok(parsed) => Ok(Some(parsed)),
+                Err(e) => {
+                    tracing::warn!(key = %key, error = %e, "Undecodable cache entry, treating as miss");
+                    Ok(None)
+                }
+            },
             None => Ok(None),
         }
     }
@@ -94,10 +248,12 @@ impl Cache {
         ttl_seconds: u64,
     ) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let json = serde_json::to_string(value)
+        let bytes = self
+            .codec
+            .encode(value)
             .map_err(|e| AppError::internal(format!("Cache serialization error: {}", e)))?;
 
-        conn.set_ex::<_, _, ()>(key, json, ttl_seconds)
+        conn.set_ex::<_, _, ()>(key, bytes, ttl_seconds)
             .await
             .map_err(cache_error)?;
 
@@ -150,10 +306,8 @@ impl Cache {
             .arg(&keys)
             .query_async(&mut conn)
             .await
-            .unwrap_or_else(|_| {
-                // Fallback: this branch runs sync but only on UNLINK failure
-                0
-            });
+            // Fallback: this branch runs sync but only on UNLINK failure
+            .unwrap_or(0);
 
         // If UNLINK failed (returned 0 but we had keys), try batch DEL
         if deleted == 0 && !keys.is_empty() {
@@ -185,6 +339,12 @@ impl Cache {
         self.delete(&key).await
     }
 
+    /// Invalidate every cached user, e.g. after the database was reset.
+    pub async fn invalidate_all_users(&self) -> AppResult<u64> {
+        self.delete_pattern(&format!("{}*", CACHE_PREFIX_USER))
+            .await
+    }
+
     // =========================================================================
     // Session Cache Operations
     // =========================================================================
@@ -212,6 +372,38 @@ impl Cache {
         self.delete(&key).await
     }
 
+    /// Remove session entries that have no expiry set.
+    /// Sessions are always written with a TTL, so these are leftovers.
+    /// Uses SCAN to avoid blocking Redis on large keyspaces.
+    pub async fn cleanup_sessions(&self) -> AppResult<u64> {
+        let pattern = format!("{}*", CACHE_PREFIX_SESSION);
+        let mut conn = self.connection.clone();
+
+        let keys: Vec<String> = {
+            let mut iter = conn
+                .scan_match::<_, String>(&pattern)
+                .await
+                .map_err(cache_error)?;
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+
+        let mut removed = 0;
+        for key in keys {
+            // TTL returns -1 when the key exists without an expiry
+            let ttl: i64 = conn.ttl(&key).await.map_err(cache_error)?;
+            if ttl == -1 {
+                let _: () = conn.del(&key).await.map_err(cache_error)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
     // =========================================================================
     // Rate Limiting Operations
     // =========================================================================
@@ -287,20 +479,9 @@ impl Cache {
     ) -> AppResult<LockGuard> {
         let key = format!("{}{}", CACHE_PREFIX_LOCK, resource);
         let lock_id = Uuid::new_v4().to_string();
-        let mut conn = self.connection.clone();
 
         for attempt in 0..=max_retries {
-            // Try to acquire lock using SET NX (set if not exists)
-            let acquired: bool = redis::cmd("SET")
-                .arg(&key)
-                .arg(&lock_id)
-                .arg("NX")
-                .arg("EX")
-                .arg(ttl_seconds)
-                .query_async(&mut conn)
-                .await
-                .map(|r: Option<String>| r.is_some())
-                .unwrap_or(false);
+            let acquired = self.lock_once(&key, &lock_id, ttl_seconds).await;
 
             if acquired {
                 tracing::debug!(resource = %resource, lock_id = %lock_id, "Lock acquired");
@@ -327,20 +508,21 @@ impl Cache {
     /// Try to acquire lock without retrying.
     /// Returns None if lock is already held.
     pub async fn try_acquire_lock(&self, resource: &str) -> AppResult<Option<LockGuard>> {
+        self.try_acquire_lock_with_ttl(resource, DEFAULT_LOCK_TTL_SECONDS)
+            .await
+    }
+
+    /// Try to acquire lock with a custom TTL without retrying.
+    /// Returns None if lock is already held.
+    pub async fn try_acquire_lock_with_ttl(
+        &self,
+        resource: &str,
+        ttl_seconds: u64,
+    ) -> AppResult<Option<LockGuard>> {
         let key = format!("{}{}", CACHE_PREFIX_LOCK, resource);
         let lock_id = Uuid::new_v4().to_string();
-        let mut conn = self.connection.clone();
 
-        let acquired: bool = redis::cmd("SET")
-            .arg(&key)
-            .arg(&lock_id)
-            .arg("NX")
-            .arg("EX")
-            .arg(DEFAULT_LOCK_TTL_SECONDS)
-            .query_async(&mut conn)
-            .await
-            .map(|r: Option<String>| r.is_some())
-            .unwrap_or(false);
+        let acquired = self.lock_once(&key, &lock_id, ttl_seconds).await;
 
         if acquired {
             tracing::debug!(resource = %resource, lock_id = %lock_id, "Lock acquired");
@@ -358,25 +540,47 @@ impl Cache {
     /// Check if a resource is currently locked.
     pub async fn is_locked(&self, resource: &str) -> AppResult<bool> {
         let key = format!("{}{}", CACHE_PREFIX_LOCK, resource);
-        self.exists(&key).await
+        match &self.redlock {
+            Some(redlock) => Ok(redlock.is_locked(&key).await),
+            None => self.exists(&key).await,
+        }
+    }
+
+    /// Make a single lock attempt on the configured lock backend.
+    async fn lock_once(&self, key: &str, lock_id: &str, ttl_seconds: u64) -> bool {
+        if let Some(redlock) = &self.redlock {
+            return redlock
+                .try_acquire(key, lock_id, ttl_seconds * 1000)
+                .await
+                .is_some();
+        }
+
+        // Try to acquire lock using SET NX (set if not exists)
+        let mut conn = self.connection.clone();
+        redis::cmd("SET")
+            .arg(key)
+            .arg(lock_id)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_seconds)
+            .query_async(&mut conn)
+            .await
+            .map(|r: Option<String>| r.is_some())
+            .unwrap_or(false)
     }
 
     /// Release a lock (internal use - prefer using LockGuard).
     async fn release_lock(&self, key: &str, lock_id: &str) -> AppResult<bool> {
+        if let Some(redlock) = &self.redlock {
+            return Ok(redlock.release(key, lock_id).await > 0);
+        }
+
         let mut conn = self.connection.clone();
 
         // Use Lua script to atomically check and delete
         // Only delete if the lock_id matches (we own the lock)
-        let script = r#"
-            if redis.call("GET", KEYS[1]) == ARGV[1] then
-                return redis.call("DEL", KEYS[1])
-            else
-                return 0
-            end
-        "#;
-
         let released: i32 = redis::cmd("EVAL")
-            .arg(script)
+            .arg(RELEASE_LOCK_SCRIPT)
             .arg(1)
             .arg(key)
             .arg(lock_id)
@@ -387,6 +591,28 @@ impl Cache {
         Ok(released == 1)
     }
 
+    /// Extend a lock TTL (internal use - prefer using LockGuard).
+    async fn extend_lock(&self, key: &str, lock_id: &str, ttl_seconds: u64) -> AppResult<bool> {
+        if let Some(redlock) = &self.redlock {
+            return Ok(redlock.extend(key, lock_id, ttl_seconds).await);
+        }
+
+        let mut conn = self.connection.clone();
+
+        // Only extend if we still own the lock
+        let extended: i32 = redis::cmd("EVAL")
+            .arg(EXTEND_LOCK_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(lock_id)
+            .arg(ttl_seconds)
+            .query_async(&mut conn)
+            .await
+            .map_err(cache_error)?;
+
+        Ok(extended == 1)
+    }
+
     // =========================================================================
     // Semaphore Operations
     // =========================================================================
@@ -458,7 +684,7 @@ impl Cache {
 
                 return Ok(SemaphorePermit {
                     cache: Arc::new(self.clone()),
-                    key,
+                    kind: PermitKind::Set { key },
                     permit_id,
                     released: false,
                 });
@@ -513,7 +739,7 @@ impl Cache {
         if acquired == 1 {
             Ok(Some(SemaphorePermit {
                 cache: Arc::new(self.clone()),
-                key,
+                kind: PermitKind::Set { key },
                 permit_id,
                 released: false,
             }))
@@ -531,11 +757,203 @@ impl Cache {
     }
 
     /// Release a semaphore permit (internal use - prefer using SemaphorePermit).
-    async fn release_semaphore(&self, key: &str, permit_id: &str) -> AppResult<bool> {
+    async fn release_semaphore(&self, kind: &PermitKind, permit_id: &str) -> AppResult<bool> {
         let mut conn = self.connection.clone();
-        let removed: i64 = conn.srem(key, permit_id).await.map_err(cache_error)?;
+        let removed: i64 = match kind {
+            PermitKind::Set { key } => conn.srem(key, permit_id).await.map_err(cache_error)?,
+            PermitKind::Fair { keys, max_permits } => {
+                self.fair_release(keys, permit_id, *max_permits).await?
+            }
+        };
         Ok(removed == 1)
     }
+
+    /// Extend a semaphore permit (internal use - prefer using SemaphorePermit).
+    async fn extend_semaphore(
+        &self,
+        kind: &PermitKind,
+        permit_id: &str,
+        ttl_seconds: u64,
+    ) -> AppResult<bool> {
+        let mut conn = self.connection.clone();
+        match kind {
+            PermitKind::Set { key } => {
+                let held: bool = conn.sismember(key, permit_id).await.map_err(cache_error)?;
+                if held {
+                    let _: () = conn
+                        .expire(key, ttl_seconds as i64)
+                        .await
+                        .map_err(cache_error)?;
+                }
+                Ok(held)
+            }
+            PermitKind::Fair { keys, .. } => {
+                let extended: i64 = redis::cmd("EVAL")
+                    .arg(FAIR_EXTEND_SCRIPT)
+                    .arg(1)
+                    .arg(&keys.holders)
+                    .arg(permit_id)
+                    .arg(ttl_seconds * 1000)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(cache_error)?;
+                Ok(extended == 1)
+            }
+        }
+    }
+
+    // =========================================================================
+    // Fair Semaphore Operations
+    // =========================================================================
+
+    /// Acquire a fair semaphore permit, waiting in FIFO order until `deadline`.
+    ///
+    /// Unlike [`Cache::acquire_semaphore`], waiters are served in arrival
+    /// order and block on a wake-up list instead of polling. Permits of
+    /// holders that crash expire after `ttl_seconds`.
+    pub async fn acquire_fair_semaphore(
+        &self,
+        resource: &str,
+        max_permits: u64,
+        ttl_seconds: u64,
+        deadline: Instant,
+    ) -> AppResult<SemaphorePermit> {
+        self.join_semaphore_queue(resource, max_permits, ttl_seconds)
+            .await?
+            .wait(deadline)
+            .await
+    }
+
+    /// Join the queue of a fair semaphore without waiting yet.
+    ///
+    /// The returned ticket reports its queue position while it waits.
+    pub async fn join_semaphore_queue(
+        &self,
+        resource: &str,
+        max_permits: u64,
+        ttl_seconds: u64,
+    ) -> AppResult<SemaphoreTicket> {
+        let keys = FairSemaphoreKeys::new(resource);
+        let ticket = Uuid::new_v4().to_string();
+        let position = self.fair_enqueue(&keys, &ticket, ttl_seconds).await?;
+
+        tracing::debug!(resource = %resource, ticket = %ticket, position, "Joined semaphore queue");
+
+        Ok(SemaphoreTicket {
+            cache: Arc::new(self.clone()),
+            resource: resource.to_string(),
+            keys,
+            ticket,
+            max_permits,
+            ttl_seconds,
+            position: watch::channel(position).0,
+            done: false,
+        })
+    }
+
+    /// Number of waiters queued on a fair semaphore.
+    pub async fn semaphore_queue_len(&self, resource: &str) -> AppResult<u64> {
+        let keys = FairSemaphoreKeys::new(resource);
+        let mut conn = self.connection.clone();
+        let len: i64 = conn.zcard(&keys.queue).await.map_err(cache_error)?;
+        Ok(len as u64)
+    }
+
+    async fn fair_enqueue(
+        &self,
+        keys: &FairSemaphoreKeys,
+        ticket: &str,
+        ttl_seconds: u64,
+    ) -> AppResult<u64> {
+        let mut conn = self.connection.clone();
+        let position: i64 = redis::cmd("EVAL")
+            .arg(FAIR_ENQUEUE_SCRIPT)
+            .arg(3)
+            .arg(&keys.queue)
+            .arg(&keys.waiters)
+            .arg(&keys.seq)
+            .arg(ticket)
+            .arg(SEMAPHORE_WAITER_TTL_MS)
+            .arg(fair_key_ttl_ms(ttl_seconds))
+            .query_async(&mut conn)
+            .await
+            .map_err(cache_error)?;
+        Ok(position as u64)
+    }
+
+    async fn fair_try_acquire(
+        &self,
+        keys: &FairSemaphoreKeys,
+        ticket: &str,
+        max_permits: u64,
+        ttl_seconds: u64,
+    ) -> AppResult<i64> {
+        let mut conn = self.connection.clone();
+        let (result, wake): (i64, Vec<String>) = redis::cmd("EVAL")
+            .arg(FAIR_ACQUIRE_SCRIPT)
+            .arg(4)
+            .arg(&keys.holders)
+            .arg(&keys.queue)
+            .arg(&keys.waiters)
+            .arg(keys.wake(ticket))
+            .arg(ticket)
+            .arg(max_permits)
+            .arg(ttl_seconds * 1000)
+            .arg(SEMAPHORE_WAITER_TTL_MS)
+            .arg(fair_key_ttl_ms(ttl_seconds))
+            .query_async(&mut conn)
+            .await
+            .map_err(cache_error)?;
+        self.fair_wake(keys, &wake).await?;
+        Ok(result)
+    }
+
+    async fn fair_release(
+        &self,
+        keys: &FairSemaphoreKeys,
+        ticket: &str,
+        max_permits: u64,
+    ) -> AppResult<i64> {
+        let mut conn = self.connection.clone();
+        let (removed, wake): (i64, Vec<String>) = redis::cmd("EVAL")
+            .arg(FAIR_RELEASE_SCRIPT)
+            .arg(3)
+            .arg(&keys.holders)
+            .arg(&keys.queue)
+            .arg(&keys.waiters)
+            .arg(ticket)
+            .arg(max_permits)
+            .query_async(&mut conn)
+            .await
+            .map_err(cache_error)?;
+        self.fair_wake(keys, &wake).await?;
+        Ok(removed)
+    }
+
+    /// Push to the wake-up lists of `tickets`.
+    ///
+    /// Done outside the scripts because the lists are only known once they
+    /// run; a lost wake-up just delays the waiter until its next poll.
+    async fn fair_wake(&self, keys: &FairSemaphoreKeys, tickets: &[String]) -> AppResult<()> {
+        if tickets.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for ticket in tickets {
+            let wake = keys.wake(ticket);
+            pipe.rpush(&wake, 1)
+                .ignore()
+                .pexpire(&wake, SEMAPHORE_WAKE_TTL_MS as i64)
+                .ignore();
+        }
+        let mut conn = self.connection.clone();
+        pipe.query_async::<()>(&mut conn).await.map_err(cache_error)
+    }
+}
+
+/// Idle expiry for fair semaphore keys, long enough to outlive any holder or waiter.
+fn fair_key_ttl_ms(ttl_seconds: u64) -> u64 {
+    (ttl_seconds * 1000).max(SEMAPHORE_WAITER_TTL_MS) * 2
 }
 
 // =============================================================================
@@ -559,28 +977,9 @@ impl LockGuard {
 
     /// Extend the lock TTL.
     pub async fn extend(&self, ttl_seconds: u64) -> AppResult<bool> {
-        let mut conn = self.cache.connection.clone();
-
-        // Only extend if we still own the lock
-        let script = r#"
-            if redis.call("GET", KEYS[1]) == ARGV[1] then
-                return redis.call("EXPIRE", KEYS[1], ARGV[2])
-            else
-                return 0
-            end
-        "#;
-
-        let extended: i32 = redis::cmd("EVAL")
-            .arg(script)
-            .arg(1)
-            .arg(&self.key)
-            .arg(&self.lock_id)
-            .arg(ttl_seconds)
-            .query_async(&mut conn)
+        self.cache
+            .extend_lock(&self.key, &self.lock_id, ttl_seconds)
             .await
-            .map_err(cache_error)?;
-
-        Ok(extended == 1)
     }
 
     async fn do_release(&mut self) -> AppResult<()> {
@@ -622,23 +1021,54 @@ impl Drop for LockGuard {
 /// Automatically releases the permit when dropped.
 pub struct SemaphorePermit {
     cache: Arc<Cache>,
-    key: String,
+    kind: PermitKind,
     permit_id: String,
     released: bool,
 }
 
+/// Storage behind a permit.
+#[derive(Clone)]
+enum PermitKind {
+    /// Member of a plain Redis set (`acquire_semaphore`)
+    Set { key: String },
+    /// Holder in a fair semaphore (`acquire_fair_semaphore`)
+    Fair {
+        keys: FairSemaphoreKeys,
+        max_permits: u64,
+    },
+}
+
+impl PermitKind {
+    fn key(&self) -> &str {
+        match self {
+            Self::Set { key } => key,
+            Self::Fair { keys, .. } => &keys.holders,
+        }
+    }
+}
+
 impl SemaphorePermit {
     /// Manually release the permit early.
     pub async fn release(mut self) -> AppResult<()> {
         self.do_release().await
     }
 
+    /// Extend the permit TTL. Returns false if the permit already expired.
+    pub async fn extend(&self, ttl_seconds: u64) -> AppResult<bool> {
+        self.cache
+            .extend_semaphore(&self.kind, &self.permit_id, ttl_seconds)
+            .await
+    }
+
     async fn do_release(&mut self) -> AppResult<()> {
         if !self.released {
             self.released = true;
-            let released = self.cache.release_semaphore(&self.key, &self.permit_id).await?;
+            let released = self
+                .cache
+                .release_semaphore(&self.kind, &self.permit_id)
+                .await?;
             if released {
-                tracing::debug!(key = %self.key, "Semaphore permit released");
+                tracing::debug!(key = %self.kind.key(), "Semaphore permit released");
             }
         }
         Ok(())
@@ -649,11 +1079,12 @@ impl Drop for SemaphorePermit {
     fn drop(&mut self) {
         if !self.released {
             let cache = self.cache.clone();
-            let key = self.key.clone();
+            let kind = self.kind.clone();
             let permit_id = self.permit_id.clone();
 
             tokio::spawn(async move {
-                if let Err(e) = cache.release_semaphore(&key, &permit_id).await {
+                let key = kind.key();
+                if let Err(e) = cache.release_semaphore(&kind, &permit_id).await {
                     tracing::error!(key = %key, error = %e, "Failed to release semaphore on drop");
                 } else {
                     tracing::debug!(key = %key, "Semaphore permit released on drop");
@@ -663,6 +1094,188 @@ impl Drop for SemaphorePermit {
     }
 }
 
+// =============================================================================
+// Fair Semaphore Ticket
+// =============================================================================
+
+/// Redis keys backing a fair semaphore.
+///
+/// The resource is wrapped in a hash tag so every key lands in the same
+/// cluster slot and the scripts can touch them together.
+#[derive(Debug, Clone)]
+struct FairSemaphoreKeys {
+    /// Sorted set of permit holders, scored by expiry (ms)
+    holders: String,
+    /// Sorted set of waiting tickets, scored by arrival sequence
+    queue: String,
+    /// Sorted set of waiting tickets, scored by heartbeat expiry (ms)
+    waiters: String,
+    /// Arrival sequence counter
+    seq: String,
+    /// Prefix of the per-ticket wake-up lists
+    wake_prefix: String,
+}
+
+impl FairSemaphoreKeys {
+    fn new(resource: &str) -> Self {
+        let base = format!("{}fair:{{{}}}", CACHE_PREFIX_SEMAPHORE, resource);
+        Self {
+            holders: format!("{}:holders", base),
+            queue: format!("{}:queue", base),
+            waiters: format!("{}:waiters", base),
+            seq: format!("{}:seq", base),
+            wake_prefix: format!("{}:wake:", base),
+        }
+    }
+
+    fn wake(&self, ticket: &str) -> String {
+        format!("{}{}", self.wake_prefix, ticket)
+    }
+}
+
+/// A place in a fair semaphore queue.
+///
+/// Leaves the queue when dropped without being turned into a permit.
+pub struct SemaphoreTicket {
+    cache: Arc<Cache>,
+    resource: String,
+    keys: FairSemaphoreKeys,
+    ticket: String,
+    max_permits: u64,
+    ttl_seconds: u64,
+    position: watch::Sender<u64>,
+    done: bool,
+}
+
+impl SemaphoreTicket {
+    /// Ticket identifier (also the permit id once acquired).
+    pub fn id(&self) -> &str {
+        &self.ticket
+    }
+
+    /// Last known number of waiters ahead of this ticket.
+    pub fn position(&self) -> u64 {
+        *self.position.borrow()
+    }
+
+    /// Subscribe to queue position updates while waiting.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.position.subscribe()
+    }
+
+    /// Wait for a permit until `deadline`.
+    ///
+    /// Blocks on the ticket's wake-up list between attempts, waking at least
+    /// every `SEMAPHORE_WAIT_SLICE_MS` to heartbeat and refresh the position.
+    /// Falls back to polling where blocking commands are unavailable (Cluster)
+    /// or every pooled blocking connection is in use.
+    pub async fn wait(mut self, deadline: Instant) -> AppResult<SemaphorePermit> {
+        let wake_key = self.keys.wake(&self.ticket);
+        let mut blocking = self.cache.blocking.get().await.unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "No dedicated Redis connection, polling semaphore instead");
+            None
+        });
+
+        loop {
+            let result = self
+                .cache
+                .fair_try_acquire(&self.keys, &self.ticket, self.max_permits, self.ttl_seconds)
+                .await?;
+
+            match result {
+                -1 => {
+                    self.done = true;
+                    tracing::debug!(resource = %self.resource, ticket = %self.ticket, "Fair semaphore permit acquired");
+                    return Ok(SemaphorePermit {
+                        cache: self.cache.clone(),
+                        kind: PermitKind::Fair {
+                            keys: self.keys.clone(),
+                            max_permits: self.max_permits,
+                        },
+                        permit_id: self.ticket.clone(),
+                        released: false,
+                    });
+                }
+                -2 => {
+                    // Our heartbeat lapsed and the ticket was purged - rejoin at the back
+                    tracing::warn!(resource = %self.resource, ticket = %self.ticket, "Semaphore ticket expired, rejoining queue");
+                    let position = self
+                        .cache
+                        .fair_enqueue(&self.keys, &self.ticket, self.ttl_seconds)
+                        .await?;
+                    self.position.send_replace(position);
+                }
+                position => {
+                    self.position.send_if_modified(|current| {
+                        let changed = *current != position as u64;
+                        *current = position as u64;
+                        changed
+                    });
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let slice = (deadline - now).min(Duration::from_millis(SEMAPHORE_WAIT_SLICE_MS));
+
+            match blocking.as_mut() {
+                // BLPOP treats 0 as "forever", so never pass less than 10ms
+                Some(conn) => {
+                    let timeout = slice.max(Duration::from_millis(10)).as_secs_f64();
+                    let popped: Result<Option<(String, String)>, _> =
+                        conn.blpop(&wake_key, timeout).await;
+                    if let Err(e) = popped {
+                        tracing::warn!(error = %e, "Blocking semaphore wait failed, polling instead");
+                        if let Some(conn) = blocking.take() {
+                            conn.discard();
+                        }
+                    }
+                }
+                None => sleep(slice.min(Duration::from_millis(DEFAULT_LOCK_RETRY_DELAY_MS))).await,
+            }
+        }
+
+        self.done = true;
+        self.cache
+            .fair_release(&self.keys, &self.ticket, self.max_permits)
+            .await?;
+
+        tracing::warn!(resource = %self.resource, "Timed out waiting for semaphore permit");
+        Err(AppError::internal(format!(
+            "Timed out waiting for semaphore permit for resource: {}",
+            self.resource
+        )))
+    }
+
+    /// Leave the queue without waiting.
+    pub async fn cancel(mut self) -> AppResult<()> {
+        self.done = true;
+        self.cache
+            .fair_release(&self.keys, &self.ticket, self.max_permits)
+            .await?;
+        Ok(())
+    }
+}
+
+impl Drop for SemaphoreTicket {
+    fn drop(&mut self) {
+        if !self.done {
+            let cache = self.cache.clone();
+            let keys = self.keys.clone();
+            let ticket = self.ticket.clone();
+            let max_permits = self.max_permits;
+
+            tokio::spawn(async move {
+                if let Err(e) = cache.fair_release(&keys, &ticket, max_permits).await {
+                    tracing::error!(key = %keys.queue, error = %e, "Failed to leave semaphore queue on drop");
+                }
+            });
+        }
+    }
+}
+
 /// Convert Redis error to AppError.
 fn cache_error(e: RedisError) -> AppError {
     tracing::error!("Redis error: {}", e);
@@ -688,4 +1301,19 @@ mod tests {
         assert_eq!(DEFAULT_LOCK_RETRIES, 10);
         assert_eq!(DEFAULT_LOCK_RETRY_DELAY_MS, 100);
     }
+
+    #[test]
+    fn test_fair_semaphore_keys_share_hash_slot() {
+        let keys = FairSemaphoreKeys::new("reports");
+        for key in [&keys.holders, &keys.queue, &keys.waiters, &keys.seq] {
+            assert!(key.starts_with("semaphore:fair:{reports}:"));
+        }
+        assert_eq!(keys.wake("t1"), "semaphore:fair:{reports}:wake:t1");
+    }
+
+    #[test]
+    fn test_fair_key_ttl_outlives_waiters() {
+        assert_eq!(fair_key_ttl_ms(30), 60_000);
+        assert_eq!(fair_key_ttl_ms(1), SEMAPHORE_WAITER_TTL_MS * 2);
+    }
 }
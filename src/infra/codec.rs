@@ -0,0 +1,214 @@
+//! Serialization codecs for cached values.
+//!
+//! Every encoded value starts with a header byte naming its format and
+//! whether it is zstd-compressed, so entries written under one codec stay
+//! readable after the configured codec changes. Values without a known
+//! header are treated as legacy plain JSON written before codecs existed.
+//!
+//! MessagePack is used as the compact binary format rather than bincode:
+//! it is self-describing, so `#[serde(skip_serializing_if)]` and added
+//! fields keep working across deploys.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::config::{CACHE_CODEC_JSON, CACHE_CODEC_MSGPACK, CACHE_ZSTD_LEVEL};
+
+/// Header byte: JSON payload
+const HEADER_JSON: u8 = 0x01;
+
+/// Header byte: MessagePack payload
+const HEADER_MSGPACK: u8 = 0x02;
+
+/// Header flag: payload is zstd-compressed
+const FLAG_ZSTD: u8 = 0x80;
+
+/// Errors from encoding or decoding cached values.
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("MessagePack encode error: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+
+    #[error("MessagePack decode error: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+
+    #[error("Compression error: {0}")]
+    Compression(#[from] std::io::Error),
+
+    #[error("Empty cache value")]
+    Empty,
+}
+
+/// Serialization format for cached values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheFormat {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl CacheFormat {
+    fn header(self) -> u8 {
+        match self {
+            Self::Json => HEADER_JSON,
+            Self::MessagePack => HEADER_MSGPACK,
+        }
+    }
+}
+
+impl FromStr for CacheFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            CACHE_CODEC_JSON => Ok(Self::Json),
+            CACHE_CODEC_MSGPACK | "messagepack" => Ok(Self::MessagePack),
+            other => Err(format!("unknown cache codec '{}'", other)),
+        }
+    }
+}
+
+/// Encodes values for storage and decodes any supported stored format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheCodec {
+    format: CacheFormat,
+    /// Compress payloads larger than this many bytes (None = never)
+    compress_above: Option<usize>,
+}
+
+impl CacheCodec {
+    /// Create a codec writing the given format without compression.
+    pub fn new(format: CacheFormat) -> Self {
+        Self {
+            format,
+            compress_above: None,
+        }
+    }
+
+    /// Compress payloads larger than `threshold` bytes with zstd.
+    pub fn with_compression(mut self, threshold: usize) -> Self {
+        self.compress_above = Some(threshold);
+        self
+    }
+
+    /// Format used for newly written values.
+    pub fn format(&self) -> CacheFormat {
+        self.format
+    }
+
+    /// Serialize a value with a format header, compressing if large.
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        let payload = match self.format {
+            CacheFormat::Json => serde_json::to_vec(value)?,
+            CacheFormat::MessagePack => rmp_serde::to_vec_named(value)?,
+        };
+
+        let mut header = self.format.header();
+        let payload = match self.compress_above {
+            Some(threshold) if payload.len() > threshold => {
+                header |= FLAG_ZSTD;
+                zstd::encode_all(payload.as_slice(), CACHE_ZSTD_LEVEL)?
+            }
+            _ => payload,
+        };
+
+        let mut bytes = Vec::with_capacity(payload.len() + 1);
+        bytes.push(header);
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Deserialize a stored value, whichever codec wrote it.
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        let (&header, payload) = bytes.split_first().ok_or(CodecError::Empty)?;
+
+        let format = match header & !FLAG_ZSTD {
+            HEADER_JSON => CacheFormat::Json,
+            HEADER_MSGPACK => CacheFormat::MessagePack,
+            // Legacy entry: plain JSON text never starts with a header byte
+            _ => return Ok(serde_json::from_slice(bytes)?),
+        };
+
+        let decompressed;
+        let payload = if header & FLAG_ZSTD != 0 {
+            decompressed = zstd::decode_all(payload)?;
+            decompressed.as_slice()
+        } else {
+            payload
+        };
+
+        Ok(match format {
+            CacheFormat::Json => serde_json::from_slice(payload)?,
+            CacheFormat::MessagePack => rmp_serde::from_slice(payload)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Sample {
+        id: u32,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        note: Option<String>,
+    }
+
+    fn sample() -> Sample {
+        Sample {
+            id: 7,
+            name: "x".repeat(200),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_each_format() {
+        for format in [CacheFormat::Json, CacheFormat::MessagePack] {
+            let codec = CacheCodec::new(format);
+            let bytes = codec.encode(&sample()).unwrap();
+            assert_eq!(bytes[0], format.header());
+            assert_eq!(CacheCodec::decode::<Sample>(&bytes).unwrap(), sample());
+        }
+    }
+
+    #[test]
+    fn test_compresses_above_threshold() {
+        let codec = CacheCodec::new(CacheFormat::MessagePack).with_compression(64);
+        let bytes = codec.encode(&sample()).unwrap();
+        assert_eq!(bytes[0], HEADER_MSGPACK | FLAG_ZSTD);
+        assert_eq!(CacheCodec::decode::<Sample>(&bytes).unwrap(), sample());
+
+        let small = CacheCodec::new(CacheFormat::Json).with_compression(4096);
+        assert_eq!(small.encode(&sample()).unwrap()[0], HEADER_JSON);
+    }
+
+    #[test]
+    fn test_decodes_legacy_json() {
+        let legacy = serde_json::to_vec(&sample()).unwrap();
+        assert_eq!(CacheCodec::decode::<Sample>(&legacy).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(CacheCodec::decode::<Sample>(b"").is_err());
+        assert!(CacheCodec::decode::<Sample>(&[HEADER_MSGPACK, 0xc1]).is_err());
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("json".parse::<CacheFormat>(), Ok(CacheFormat::Json));
+        assert_eq!(
+            "MsgPack".parse::<CacheFormat>(),
+            Ok(CacheFormat::MessagePack)
+        );
+        assert!("bincode".parse::<CacheFormat>().is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Migration SQL capture and drift detection.
+//!
+//! [`migration_sql`] runs a migration against a recording connection, so
+//! the statements it would execute can be printed without touching the
+//! database. A SHA-256 of those statements is stored per applied migration
+//! in `migration_checksums`; when the code of an applied migration changes,
+//! so does its checksum and `migrate status` reports the drift.
+
+use std::sync::{Arc, Mutex};
+
+use sea_orm::{
+    ConnectionTrait, Database as SeaDatabase, DatabaseBackend, DbErr, ProxyDatabaseTrait,
+    ProxyExecResult, ProxyRow, Statement,
+};
+use sea_orm_migration::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// Which way to run a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+/// Connection that records statements instead of running them.
+#[derive(Debug, Clone, Default)]
+struct Recorder {
+    statements: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl ProxyDatabaseTrait for Recorder {
+    async fn query(&self, statement: Statement) -> Result<Vec<ProxyRow>, DbErr> {
+        self.record(statement);
+        Ok(Vec::new())
+    }
+
+    async fn execute(&self, statement: Statement) -> Result<ProxyExecResult, DbErr> {
+        self.record(statement);
+        Ok(ProxyExecResult::default())
+    }
+}
+
+impl Recorder {
+    fn record(&self, statement: Statement) {
+        if let Ok(mut statements) = self.statements.lock() {
+            statements.push(statement.to_string());
+        }
+    }
+}
+
+/// SQL a migration runs on `backend`, without executing it.
+///
+/// Schema checks such as `has_table` see an empty database.
+pub async fn migration_sql(
+    backend: DatabaseBackend,
+    migration: &dyn MigrationTrait,
+    direction: Direction,
+) -> Result<Vec<String>, DbErr> {
+    let recorder = Recorder::default();
+    let connection =
+        SeaDatabase::connect_proxy(backend, Arc::new(Box::new(recorder.clone()))).await?;
+
+    let manager = SchemaManager::new(&connection);
+    match direction {
+        Direction::Up => migration.up(&manager).await?,
+        Direction::Down => migration.down(&manager).await?,
+    }
+
+    let statements = recorder
+        .statements
+        .lock()
+        .map(|statements| statements.clone())
+        .unwrap_or_default();
+    Ok(statements)
+}
+
+/// Hex SHA-256 of the statements of a migration.
+pub fn checksum(statements: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for statement in statements {
+        hasher.update(statement.as_bytes());
+        hasher.update(b";\n");
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Checksum of the `up` SQL of a migration on `backend`.
+pub async fn migration_checksum(
+    backend: DatabaseBackend,
+    migration: &dyn MigrationTrait,
+) -> Result<String, DbErr> {
+    Ok(checksum(
+        &migration_sql(backend, migration, Direction::Up).await?,
+    ))
+}
+
+/// Recorded checksums of applied migrations.
+pub struct ChecksumStore<'c, C: ConnectionTrait> {
+    db: &'c C,
+}
+
+impl<'c, C: ConnectionTrait> ChecksumStore<'c, C> {
+    pub fn new(db: &'c C) -> Self {
+        Self { db }
+    }
+
+    /// Create the checksum table if it does not exist yet.
+    pub async fn ensure_table(&self) -> Result<(), DbErr> {
+        let backend = self.db.get_database_backend();
+        let table = Table::create()
+            .table(MigrationChecksums::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(MigrationChecksums::Version)
+                    .string()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(
+                ColumnDef::new(MigrationChecksums::Checksum)
+                    .string()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(MigrationChecksums::RecordedAt)
+                    .timestamp_with_time_zone()
+                    .not_null(),
+            )
+            .to_owned();
+        self.db.execute(backend.build(&table)).await?;
+        Ok(())
+    }
+
+    /// All recorded `(version, checksum)` pairs.
+    pub async fn all(&self) -> Result<Vec<(String, String)>, DbErr> {
+        let backend = self.db.get_database_backend();
+        let query = Query::select()
+            .columns([MigrationChecksums::Version, MigrationChecksums::Checksum])
+            .from(MigrationChecksums::Table)
+            .to_owned();
+        self.db
+            .query_all(backend.build(&query))
+            .await?
+            .iter()
+            .map(|row| Ok((row.try_get("", "version")?, row.try_get("", "checksum")?)))
+            .collect()
+    }
+
+    /// Record the checksum of a migration unless one is already stored.
+    ///
+    /// Existing checksums are kept so later changes show up as drift.
+    pub async fn record(&self, version: &str, checksum: &str) -> Result<(), DbErr> {
+        let backend = self.db.get_database_backend();
+        let insert = Query::insert()
+            .into_table(MigrationChecksums::Table)
+            .columns([
+                MigrationChecksums::Version,
+                MigrationChecksums::Checksum,
+                MigrationChecksums::RecordedAt,
+            ])
+            .values_panic([version.into(), checksum.into(), chrono::Utc::now().into()])
+            .on_conflict(
+                OnConflict::column(MigrationChecksums::Version)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .to_owned();
+        self.db.execute(backend.build(&insert)).await?;
+        Ok(())
+    }
+
+    /// Forget the checksums of rolled back migrations, or of all of them.
+    pub async fn remove(&self, versions: Option<&[String]>) -> Result<(), DbErr> {
+        let backend = self.db.get_database_backend();
+        let mut delete = Query::delete()
+            .from_table(MigrationChecksums::Table)
+            .to_owned();
+        if let Some(versions) = versions {
+            delete
+                .and_where(Expr::col(MigrationChecksums::Version).is_in(versions.iter().cloned()));
+        }
+        self.db.execute(backend.build(&delete)).await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum MigrationChecksums {
+    Table,
+    Version,
+    Checksum,
+    RecordedAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::Migrator;
+
+    #[tokio::test]
+    async fn test_migration_sql_is_recorded_not_run() {
+        let migration = Migrator::migrations().remove(0);
+
+        let up = migration_sql(DatabaseBackend::Postgres, migration.as_ref(), Direction::Up)
+            .await
+            .unwrap();
+        assert!(up[0].starts_with("CREATE TABLE IF NOT EXISTS \"users\""));
+
+        let down = migration_sql(
+            DatabaseBackend::Postgres,
+            migration.as_ref(),
+            Direction::Down,
+        )
+        .await
+        .unwrap();
+        assert_eq!(down, vec!["DROP TABLE \"users\"".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_checksum_changes_with_sql() {
+        let migration = Migrator::migrations().remove(0);
+        let first = migration_checksum(DatabaseBackend::Postgres, migration.as_ref())
+            .await
+            .unwrap();
+        let again = migration_checksum(DatabaseBackend::Postgres, migration.as_ref())
+            .await
+            .unwrap();
+
+        assert_eq!(first, again);
+        assert_eq!(first.len(), 64);
+        assert_ne!(first, checksum(&["CREATE TABLE other ()".to_string()]));
+    }
+}
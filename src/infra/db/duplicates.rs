@@ -0,0 +1,162 @@
+//! Users whose emails differ only by case.
+//!
+//! They block the unique `lower(email)` index; `db duplicates` reports them
+//! so they can be merged or renamed before migrating.
+//!
+//! `EMAIL_LOCAL_PART` only shapes emails as they are written, so switching
+//! it (e.g. to `strip_tag`) leaves existing users as they were stored.
+//! `db normalize-emails` rewrites them, refusing while two users would end
+//! up with one address.
+
+use std::collections::BTreeMap;
+
+use sea_orm::sea_query::{Asterisk, Expr, Func, Query};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::domain::{EmailAddress, LocalPartPolicy, User};
+use crate::infra::repositories::entities::user::{self, Entity as UserEntity};
+
+/// Users sharing one email, ignoring case.
+#[derive(Debug, Clone)]
+pub struct DuplicateEmail {
+    /// The lowercased email
+    pub email: String,
+    /// Oldest first, soft-deleted included
+    pub users: Vec<User>,
+}
+
+/// Every email used by more than one user, ignoring case.
+pub async fn duplicate_emails<C: ConnectionTrait>(db: &C) -> Result<Vec<DuplicateEmail>, DbErr> {
+    let lower_email = || Func::lower(Expr::col(user::Column::Email));
+    let shared = Query::select()
+        .expr(lower_email())
+        .from(UserEntity)
+        .add_group_by([lower_email().into()])
+        .and_having(Expr::expr(Func::count(Expr::col(Asterisk))).gt(1))
+        .to_owned();
+
+    let models = UserEntity::find()
+        .filter(Expr::expr(lower_email()).in_subquery(shared))
+        .order_by_asc(Expr::expr(lower_email()))
+        .order_by_asc(user::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut duplicates: Vec<DuplicateEmail> = Vec::new();
+    for model in models {
+        let email = model.email.to_lowercase();
+        match duplicates.last_mut() {
+            Some(last) if last.email == email => last.users.push(model.into()),
+            _ => duplicates.push(DuplicateEmail {
+                email,
+                users: vec![model.into()],
+            }),
+        }
+    }
+    Ok(duplicates)
+}
+
+/// A stored email that differs from its normalized form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailRewrite {
+    pub user_id: Uuid,
+    /// As stored
+    pub from: String,
+    /// Under the policy
+    pub to: String,
+}
+
+/// Normalized form of a stored email; unparseable ones are left as stored.
+fn normalized(email: &str, policy: LocalPartPolicy) -> String {
+    EmailAddress::parse(email, policy)
+        .map(EmailAddress::into_string)
+        .unwrap_or_else(|_| email.to_string())
+}
+
+/// Every address several users would share once their emails are
+/// normalized under `policy`, ignoring case.
+///
+/// Under `strip_tag`, `bob+news@x.com` and `bob@x.com` collide.
+pub async fn email_collisions<C: ConnectionTrait>(
+    db: &C,
+    policy: LocalPartPolicy,
+) -> Result<Vec<DuplicateEmail>, DbErr> {
+    let models = UserEntity::find()
+        .order_by_asc(user::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut by_email: BTreeMap<String, Vec<User>> = BTreeMap::new();
+    for model in models {
+        let email = normalized(&model.email, policy).to_lowercase();
+        by_email.entry(email).or_default().push(model.into());
+    }
+    Ok(by_email
+        .into_iter()
+        .filter(|(_, users)| users.len() > 1)
+        .map(|(email, users)| DuplicateEmail { email, users })
+        .collect())
+}
+
+/// Stored emails that differ from their form under `policy`.
+pub async fn email_rewrites<C: ConnectionTrait>(
+    db: &C,
+    policy: LocalPartPolicy,
+) -> Result<Vec<EmailRewrite>, DbErr> {
+    let models = UserEntity::find()
+        .order_by_asc(user::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(models
+        .into_iter()
+        .filter_map(|model| {
+            let to = normalized(&model.email, policy);
+            (to != model.email).then_some(EmailRewrite {
+                user_id: model.id,
+                from: model.email,
+                to,
+            })
+        })
+        .collect())
+}
+
+/// Rewrite stored emails under `policy`, bumping each user's version.
+///
+/// Check [`email_collisions`] first: rewrites that make two users share an
+/// address fail on the unique index. Run it in a transaction so a failure
+/// leaves every email as it was.
+pub async fn normalize_emails<C: ConnectionTrait>(
+    db: &C,
+    policy: LocalPartPolicy,
+) -> Result<Vec<EmailRewrite>, DbErr> {
+    let models = UserEntity::find()
+        .order_by_asc(user::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut rewrites = Vec::new();
+    for model in models {
+        let to = normalized(&model.email, policy);
+        if to == model.email {
+            continue;
+        }
+        rewrites.push(EmailRewrite {
+            user_id: model.id,
+            from: model.email.clone(),
+            to: to.clone(),
+        });
+
+        let version = model.version;
+        let mut active = model.into_active_model();
+        active.email = Set(to);
+        active.version = Set(version + 1);
+        active.updated_at = Set(chrono::Utc::now());
+        active.update(db).await?;
+    }
+    Ok(rewrites)
+}
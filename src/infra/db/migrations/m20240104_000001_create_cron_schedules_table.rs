@@ -0,0 +1,66 @@
+//! Migration: Create cron_schedules table tracking recurring job runs.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CronSchedules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CronSchedules::Name)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CronSchedules::LastScheduledAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(CronSchedules::LastStartedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(CronSchedules::LastFinishedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(CronSchedules::LastStatus).string().null())
+                    .col(ColumnDef::new(CronSchedules::LastError).text().null())
+                    .col(
+                        ColumnDef::new(CronSchedules::LockedUntil)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CronSchedules::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum CronSchedules {
+    Table,
+    Name,
+    LastScheduledAt,
+    LastStartedAt,
+    LastFinishedAt,
+    LastStatus,
+    LastError,
+    LockedUntil,
+}
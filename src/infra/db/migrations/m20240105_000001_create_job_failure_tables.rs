@@ -0,0 +1,134 @@
+//! Migration: Create job_attempts and dead_letter_jobs tables for job retries.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(JobAttempts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(JobAttempts::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JobAttempts::JobId).string().not_null())
+                    .col(ColumnDef::new(JobAttempts::JobType).string().not_null())
+                    .col(ColumnDef::new(JobAttempts::Attempt).integer().not_null())
+                    .col(ColumnDef::new(JobAttempts::ErrorKind).string().not_null())
+                    .col(ColumnDef::new(JobAttempts::Error).text().not_null())
+                    .col(
+                        ColumnDef::new(JobAttempts::RetryAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(JobAttempts::FailedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One row per failed attempt of a job
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_job_attempts_job_attempt")
+                    .table(JobAttempts::Table)
+                    .col(JobAttempts::JobId)
+                    .col(JobAttempts::Attempt)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeadLetterJobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeadLetterJobs::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetterJobs::JobId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(DeadLetterJobs::JobType).string().not_null())
+                    .col(
+                        ColumnDef::new(DeadLetterJobs::Payload)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetterJobs::Attempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetterJobs::ErrorKind).string().null())
+                    .col(ColumnDef::new(DeadLetterJobs::LastError).text().null())
+                    .col(
+                        ColumnDef::new(DeadLetterJobs::History)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetterJobs::FailedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeadLetterJobs::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(JobAttempts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum JobAttempts {
+    Table,
+    Id,
+    JobId,
+    JobType,
+    Attempt,
+    ErrorKind,
+    Error,
+    RetryAt,
+    FailedAt,
+}
+
+#[derive(Iden)]
+enum DeadLetterJobs {
+    Table,
+    Id,
+    JobId,
+    JobType,
+    Payload,
+    Attempts,
+    ErrorKind,
+    LastError,
+    History,
+    FailedAt,
+}
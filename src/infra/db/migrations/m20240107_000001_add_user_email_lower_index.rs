@@ -0,0 +1,60 @@
+//! Migration: Make user emails unique regardless of case.
+//!
+//! Fails while emails differing only by case exist; `db duplicates` lists
+//! them so they can be merged or renamed first.
+
+use sea_orm_migration::prelude::*;
+
+const INDEX_NAME: &str = "idx_users_email_lower";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let duplicates = Query::select()
+            .expr(Func::lower(Expr::col(Users::Email)))
+            .from(Users::Table)
+            .add_group_by([Func::lower(Expr::col(Users::Email)).into()])
+            .and_having(Expr::expr(Func::count(Expr::col(Asterisk))).gt(1))
+            .to_owned();
+        let duplicates = db
+            .query_all(manager.get_database_backend().build(&duplicates))
+            .await?;
+        if !duplicates.is_empty() {
+            return Err(DbErr::Migration(format!(
+                "{} emails are used by several users in different casing; \
+                 run `db duplicates` and resolve them first",
+                duplicates.len()
+            )));
+        }
+
+        // Expression indexes are not expressible with the index builder
+        db.execute_unprepared(&format!(
+            "CREATE UNIQUE INDEX {} ON users (lower(email))",
+            INDEX_NAME
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(INDEX_NAME)
+                    .table(Users::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Email,
+}
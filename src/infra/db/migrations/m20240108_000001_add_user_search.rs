@@ -0,0 +1,51 @@
+//! Migration: Full-text and trigram indexes for user search (PostgreSQL).
+//!
+//! Adds a generated `search_vector` column over name and email with a GIN
+//! index, and a `pg_trgm` index for similarity matching. Other backends
+//! search without indexes, so this is a no-op there.
+
+use sea_orm::DatabaseBackend;
+use sea_orm_migration::prelude::*;
+
+const UP: &[&str] = &[
+    "CREATE EXTENSION IF NOT EXISTS pg_trgm",
+    "ALTER TABLE users ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (\
+     setweight(to_tsvector('simple', coalesce(name, '')), 'A') || \
+     setweight(to_tsvector('simple', coalesce(email, '')), 'B')) STORED",
+    "CREATE INDEX idx_users_search_vector ON users USING GIN (search_vector)",
+    "CREATE INDEX idx_users_search_trgm ON users USING GIN ((name || ' ' || email) gin_trgm_ops)",
+];
+
+// The extension stays: other objects may depend on it
+const DOWN: &[&str] = &[
+    "DROP INDEX IF EXISTS idx_users_search_trgm",
+    "DROP INDEX IF EXISTS idx_users_search_vector",
+    "ALTER TABLE users DROP COLUMN IF EXISTS search_vector",
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        run(manager, UP).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        run(manager, DOWN).await
+    }
+}
+
+async fn run(manager: &SchemaManager<'_>, statements: &[&str]) -> Result<(), DbErr> {
+    if manager.get_database_backend() != DatabaseBackend::Postgres {
+        return Ok(());
+    }
+    for statement in statements {
+        manager
+            .get_connection()
+            .execute_unprepared(statement)
+            .await?;
+    }
+    Ok(())
+}
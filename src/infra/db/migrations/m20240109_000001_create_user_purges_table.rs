@@ -0,0 +1,64 @@
+//! Migration: Create user_purges table recording permanently deleted users.
+//!
+//! Only ids and timestamps are kept; the purge exists to erase the rest.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserPurges::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserPurges::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserPurges::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(UserPurges::DeletedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserPurges::PurgedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_purges_user_id")
+                    .table(UserPurges::Table)
+                    .col(UserPurges::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserPurges::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum UserPurges {
+    Table,
+    Id,
+    UserId,
+    DeletedAt,
+    PurgedAt,
+}
@@ -7,6 +7,13 @@ use sea_orm_migration::prelude::*;
 
 mod m20240101_000001_create_users_table;
 mod m20240102_000001_add_soft_delete;
+mod m20240103_000001_create_outbox_table;
+mod m20240104_000001_create_cron_schedules_table;
+mod m20240105_000001_create_job_failure_tables;
+mod m20240106_000001_add_user_version;
+mod m20240107_000001_add_user_email_lower_index;
+mod m20240108_000001_add_user_search;
+mod m20240109_000001_create_user_purges_table;
 
 pub struct Migrator;
 
@@ -16,6 +23,13 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20240101_000001_create_users_table::Migration),
             Box::new(m20240102_000001_add_soft_delete::Migration),
+            Box::new(m20240103_000001_create_outbox_table::Migration),
+            Box::new(m20240104_000001_create_cron_schedules_table::Migration),
+            Box::new(m20240105_000001_create_job_failure_tables::Migration),
+            Box::new(m20240106_000001_add_user_version::Migration),
+            Box::new(m20240107_000001_add_user_email_lower_index::Migration),
+            Box::new(m20240108_000001_add_user_search::Migration),
+            Box::new(m20240109_000001_create_user_purges_table::Migration),
         ]
     }
 }
@@ -1,45 +1,217 @@
 //! Database connection and initialization.
+//!
+//! The `DATABASE_URL` scheme picks the backend: `postgres://` or, with the
+//! `sqlite` feature, `sqlite://app.db` / `sqlite::memory:`. Users, the unit
+//! of work and migrations run on both; the job queue needs PostgreSQL.
+//!
+//! With `DATABASE_REPLICA_URL` set, repository lists, counts and searches
+//! outside a transaction go to the replica; writes, the lookups they depend
+//! on, transactions and migrations use the primary.
 
-use sea_orm::{ConnectionTrait, Database as SeaDatabase, DatabaseConnection, DbErr, Statement};
-use sea_orm_migration::MigratorTrait;
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database as SeaDatabase, DatabaseBackend, DatabaseConnection,
+    DbErr, Statement, TransactionTrait,
+};
+use sea_orm_migration::{IntoSchemaManagerConnection, MigratorTrait};
 
-use crate::config::Config;
+use std::str::FromStr;
+use std::time::Duration;
 
+use crate::config::{
+    Config, MIGRATIONS_ON_START_AUTO, MIGRATIONS_ON_START_CHECK, MIGRATIONS_ON_START_OFF,
+    MIGRATION_ADVISORY_LOCK_KEY,
+};
+use crate::errors::{AppError, AppResult};
+
+pub mod checksums;
+pub mod duplicates;
 pub mod migrations;
+pub mod seeds;
 
+pub use checksums::Direction;
+pub use duplicates::{
+    duplicate_emails, email_collisions, email_rewrites, normalize_emails, DuplicateEmail,
+    EmailRewrite,
+};
 pub use migrations::Migrator;
+pub use seeds::{SeedReport, SeedSet, Seeder};
+
+use checksums::{migration_checksum, migration_sql, ChecksumStore};
+
+/// How far `migrate down` rolls back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rollback {
+    /// The last `n` applied migrations
+    Steps(u32),
+    /// Every migration applied after the named one, which stays applied
+    To(String),
+}
+
+/// What `serve` and the job worker do with pending migrations at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartupMigrations {
+    /// Apply them; on PostgreSQL one instance at a time
+    #[default]
+    Auto,
+    /// Refuse to start until `migrate up` has run
+    Check,
+    /// Start anyway; `/health` reports them without failing
+    Off,
+}
+
+impl FromStr for StartupMigrations {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            MIGRATIONS_ON_START_AUTO => Ok(Self::Auto),
+            MIGRATIONS_ON_START_CHECK => Ok(Self::Check),
+            MIGRATIONS_ON_START_OFF => Ok(Self::Off),
+            other => Err(format!("unknown MIGRATIONS_ON_START mode '{}'", other)),
+        }
+    }
+}
+
+impl StartupMigrations {
+    /// The mode set by `MIGRATIONS_ON_START`, `auto` when unset.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        config
+            .migrations_on_start
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+}
+
+/// An applied migration whose code no longer matches the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationDrift {
+    /// Its SQL changed after it was applied
+    Changed(String),
+    /// It is recorded as applied but no longer exists in the code
+    Missing(String),
+}
 
 /// Database wrapper for connection management
 #[derive(Clone)]
 pub struct Database {
     connection: DatabaseConnection,
+    /// Read replica; None when reads go to the primary
+    replica: Option<DatabaseConnection>,
 }
 
 impl Database {
-    /// Initialize database connection and run migrations.
+    /// Initialize database connection and handle pending migrations as
+    /// `MIGRATIONS_ON_START` says.
     ///
     /// # Panics
-    /// Panics if database connection fails.
+    /// Panics if database connection fails, the mode is unknown, or
+    /// migrations fail or (in `check` mode) are pending.
     pub async fn connect(config: &Config) -> Self {
-        let connection = SeaDatabase::connect(&config.database_url)
+        let db = Self::connect_without_migrations(config)
             .await
             .expect("Failed to connect to database");
 
-        // Run pending migrations
-        if let Err(e) = Migrator::up(&connection, None).await {
+        let mode = StartupMigrations::from_config(config)
+            .unwrap_or_else(|e| panic!("Invalid MIGRATIONS_ON_START: {}", e));
+        if let Err(e) = db.startup_migrations(mode).await {
             tracing::error!("Failed to run migrations: {}", e);
             panic!("Failed to run migrations: {}", e);
         }
 
-        tracing::info!("Database connected and migrations applied");
+        tracing::info!("Database connected ({:?} migrations on start)", mode);
+
+        db
+    }
+
+    /// Handle pending migrations at startup.
+    pub async fn startup_migrations(&self, mode: StartupMigrations) -> Result<(), DbErr> {
+        match mode {
+            StartupMigrations::Auto => self.migrate_locked().await,
+            StartupMigrations::Check => match self.pending_migrations().await?.as_slice() {
+                [] => Ok(()),
+                pending => Err(DbErr::Custom(format!(
+                    "{} pending migration(s): {}; run `migrate up` first",
+                    pending.len(),
+                    pending.join(", ")
+                ))),
+            },
+            StartupMigrations::Off => {
+                let pending = self.pending_migrations().await?;
+                if !pending.is_empty() {
+                    tracing::warn!(
+                        "{} migration(s) pending: {}",
+                        pending.len(),
+                        pending.join(", ")
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply pending migrations while holding a Postgres advisory lock, so
+    /// instances starting together apply them once while the rest wait.
+    async fn migrate_locked(&self) -> Result<(), DbErr> {
+        if self.backend() != DatabaseBackend::Postgres {
+            return self.run_migrations().await;
+        }
+
+        // The transaction-scoped lock is released on commit, or when its
+        // connection drops. Migrations run on that same connection, so a
+        // pool of one cannot deadlock, and without a statement timeout, so
+        // neither the wait nor a long migration is cut short.
+        let lock = self.connection.begin().await?;
+        lock.execute_unprepared("SET LOCAL statement_timeout = 0")
+            .await?;
+        tracing::info!("Waiting for the migration lock...");
+        lock.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT pg_advisory_xact_lock($1)",
+            [MIGRATION_ADVISORY_LOCK_KEY.into()],
+        ))
+        .await?;
+
+        let applied = MigrationRunner::new(&lock).up(None).await?;
+        lock.commit().await?;
+        tracing::info!("Applied {} migration(s)", applied.len());
+        Ok(())
+    }
 
-        Self { connection }
+    /// Migrations not applied yet.
+    pub async fn pending_migrations(&self) -> Result<Vec<String>, DbErr> {
+        self.plan_up(None).await
     }
 
     /// Connect without running migrations (for CLI commands).
     pub async fn connect_without_migrations(config: &Config) -> Result<Self, DbErr> {
-        let connection = SeaDatabase::connect(&config.database_url).await?;
-        Ok(Self { connection })
+        let connection = open(&config.database_url, config).await?;
+        let replica = match &config.database_replica_url {
+            Some(url) => Some(open(url, config).await?),
+            None => None,
+        };
+        Ok(Self {
+            connection,
+            replica,
+        })
+    }
+
+    /// Backend chosen by the database URL.
+    pub fn backend(&self) -> DatabaseBackend {
+        self.connection.get_database_backend()
+    }
+
+    /// Fail unless connected to PostgreSQL, for features built on
+    /// Postgres-only SQL such as the job queue.
+    pub fn require_postgres(&self, feature: &str) -> AppResult<()> {
+        match self.backend() {
+            DatabaseBackend::Postgres => Ok(()),
+            backend => Err(AppError::BadRequest(format!(
+                "{} requires PostgreSQL, but DATABASE_URL points to {:?}",
+                feature, backend
+            ))),
+        }
     }
 
     /// Get a reference to the database connection.
@@ -52,25 +224,235 @@ impl Database {
         self.connection.clone()
     }
 
+    /// Connection for reads that may lag behind writes: the replica if
+    /// one is configured, otherwise the primary.
+    pub fn read_connection(&self) -> &DatabaseConnection {
+        self.replica.as_ref().unwrap_or(&self.connection)
+    }
+
+    /// Get a clone of the read connection.
+    pub fn get_read_connection(&self) -> DatabaseConnection {
+        self.read_connection().clone()
+    }
+
     /// Run pending migrations.
     pub async fn run_migrations(&self) -> Result<(), DbErr> {
-        Migrator::up(&self.connection, None).await
+        self.migrate_up(None).await.map(|_| ())
     }
 
     /// Rollback the last migration.
     pub async fn rollback_migration(&self) -> Result<(), DbErr> {
-        Migrator::down(&self.connection, Some(1)).await
+        self.migrate_down(&Rollback::Steps(1)).await.map(|_| ())
+    }
+
+    /// Apply pending migrations up to and including `to`, or all of them.
+    /// Returns the names of the applied migrations.
+    pub async fn migrate_up(&self, to: Option<&str>) -> Result<Vec<String>, DbErr> {
+        MigrationRunner::new(&self.connection).up(to).await
+    }
+
+    /// Roll back applied migrations, newest first.
+    /// Returns the names of the rolled back migrations.
+    pub async fn migrate_down(&self, rollback: &Rollback) -> Result<Vec<String>, DbErr> {
+        let targets = self.plan_down(rollback).await?;
+        if !targets.is_empty() {
+            Migrator::down(&self.connection, Some(targets.len() as u32)).await?;
+            let checksums = ChecksumStore::new(&self.connection);
+            checksums.ensure_table().await?;
+            checksums.remove(Some(&targets)).await?;
+        }
+        Ok(targets)
+    }
+
+    /// Pending migrations `migrate_up(to)` would apply, in order.
+    pub async fn plan_up(&self, to: Option<&str>) -> Result<Vec<String>, DbErr> {
+        MigrationRunner::new(&self.connection).plan_up(to).await
+    }
+
+    /// Applied migrations `migrate_down(rollback)` would revert, newest first.
+    pub async fn plan_down(&self, rollback: &Rollback) -> Result<Vec<String>, DbErr> {
+        let status = self.migration_status().await?;
+        let applied: Vec<String> = status
+            .iter()
+            .rev()
+            .filter(|(_, applied)| *applied)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        match rollback {
+            Rollback::Steps(steps) => Ok(applied.into_iter().take(*steps as usize).collect()),
+            Rollback::To(to) => match applied.iter().position(|name| name == to) {
+                Some(index) => Ok(applied[..index].to_vec()),
+                None if status.iter().any(|(name, _)| name == to) => Err(DbErr::Custom(
+                    format!("Migration '{}' is not applied", to),
+                )),
+                None => Err(unknown_migration(to)),
+            },
+        }
+    }
+
+    /// SQL of each pending migration up to `to`, without running it.
+    pub async fn dry_run_up(&self, to: Option<&str>) -> Result<Vec<(String, Vec<String>)>, DbErr> {
+        let names = self.plan_up(to).await?;
+        self.migration_sql(&names, Direction::Up).await
+    }
+
+    /// SQL of each migration `rollback` would revert, without running it.
+    pub async fn dry_run_down(
+        &self,
+        rollback: &Rollback,
+    ) -> Result<Vec<(String, Vec<String>)>, DbErr> {
+        let names = self.plan_down(rollback).await?;
+        self.migration_sql(&names, Direction::Down).await
+    }
+
+    async fn migration_sql(
+        &self,
+        names: &[String],
+        direction: Direction,
+    ) -> Result<Vec<(String, Vec<String>)>, DbErr> {
+        let migrations = Migrator::migrations();
+        let mut plan = Vec::with_capacity(names.len());
+        for name in names {
+            let migration = migrations
+                .iter()
+                .find(|m| m.name() == name)
+                .ok_or_else(|| unknown_migration(name))?;
+            let sql = migration_sql(self.backend(), migration.as_ref(), direction).await?;
+            plan.push((name.clone(), sql));
+        }
+        Ok(plan)
+    }
+
+    /// Applied migrations that changed or disappeared since they ran.
+    pub async fn migration_drift(&self) -> Result<Vec<MigrationDrift>, DbErr> {
+        let checksums = ChecksumStore::new(&self.connection);
+        checksums.ensure_table().await?;
+        let recorded = checksums.all().await?;
+
+        let migrations = Migrator::migrations();
+        let mut drift = Vec::new();
+        for (version, stored) in recorded {
+            match migrations.iter().find(|m| m.name() == version) {
+                Some(migration) => {
+                    if migration_checksum(self.backend(), migration.as_ref()).await? != stored {
+                        drift.push(MigrationDrift::Changed(version));
+                    }
+                }
+                None => drift.push(MigrationDrift::Missing(version)),
+            }
+        }
+        Ok(drift)
     }
 
     /// Get migration status (list all migrations with applied status).
     pub async fn migration_status(&self) -> Result<Vec<(String, bool)>, DbErr> {
+        MigrationRunner::new(&self.connection).status().await
+    }
+
+    /// Reset database and run all migrations fresh.
+    pub async fn fresh_migrations(&self) -> Result<(), DbErr> {
+        Migrator::fresh(&self.connection).await?;
+        let checksums = ChecksumStore::new(&self.connection);
+        checksums.ensure_table().await?;
+        checksums.remove(None).await?;
+        MigrationRunner::new(&self.connection)
+            .record_checksums()
+            .await
+    }
+
+    /// Check database connectivity by executing a simple query on the
+    /// primary and the replica.
+    pub async fn ping(&self) -> Result<(), DbErr> {
+        for connection in std::iter::once(&self.connection).chain(&self.replica) {
+            connection
+                .execute(Statement::from_string(
+                    connection.get_database_backend(),
+                    "SELECT 1".to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Migration steps against any connection, so `migrate_locked` can apply
+/// them on the transaction holding the advisory lock.
+struct MigrationRunner<'c, C> {
+    db: &'c C,
+}
+
+impl<'c, C> MigrationRunner<'c, C>
+where
+    C: ConnectionTrait,
+    &'c C: IntoSchemaManagerConnection<'c>,
+{
+    fn new(db: &'c C) -> Self {
+        Self { db }
+    }
+
+    /// Apply pending migrations up to `to` and record their checksums.
+    async fn up(&self, to: Option<&str>) -> Result<Vec<String>, DbErr> {
+        let pending = self.plan_up(to).await?;
+        if !pending.is_empty() {
+            Migrator::up(self.db, Some(pending.len() as u32)).await?;
+        }
+        self.record_checksums().await?;
+        Ok(pending)
+    }
+
+    /// Pending migrations `up(to)` would apply, in order.
+    async fn plan_up(&self, to: Option<&str>) -> Result<Vec<String>, DbErr> {
+        let status = self.status().await?;
+        let pending: Vec<String> = status
+            .iter()
+            .filter(|(_, applied)| !applied)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let Some(to) = to else {
+            return Ok(pending);
+        };
+        match pending.iter().position(|name| name == to) {
+            Some(index) => Ok(pending[..=index].to_vec()),
+            None if status.iter().any(|(name, _)| name == to) => Err(DbErr::Custom(format!(
+                "Migration '{}' is already applied",
+                to
+            ))),
+            None => Err(unknown_migration(to)),
+        }
+    }
+
+    /// Record checksums of applied migrations that have none yet.
+    async fn record_checksums(&self) -> Result<(), DbErr> {
+        let checksums = ChecksumStore::new(self.db);
+        checksums.ensure_table().await?;
+
+        let migrations = Migrator::migrations();
+        for (name, applied) in self.status().await? {
+            if !applied {
+                continue;
+            }
+            if let Some(migration) = migrations.iter().find(|m| m.name() == name) {
+                let checksum =
+                    migration_checksum(self.db.get_database_backend(), migration.as_ref()).await?;
+                checksums.record(&name, &checksum).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get migration status (list all migrations with applied status).
+    async fn status(&self) -> Result<Vec<(String, bool)>, DbErr> {
         use sea_orm::{EntityTrait, QueryOrder};
         use sea_orm_migration::seaql_migrations;
 
+        Migrator::install(self.db).await?;
+
         // Get applied migrations from database
         let applied: std::collections::HashSet<String> = seaql_migrations::Entity::find()
             .order_by_asc(seaql_migrations::Column::Version)
-            .all(&self.connection)
+            .all(self.db)
             .await?
             .into_iter()
             .map(|m| m.version)
@@ -88,20 +470,62 @@ impl Database {
 
         Ok(migrations)
     }
+}
 
-    /// Reset database and run all migrations fresh.
-    pub async fn fresh_migrations(&self) -> Result<(), DbErr> {
-        Migrator::fresh(&self.connection).await
+fn unknown_migration(name: &str) -> DbErr {
+    DbErr::Custom(format!("Unknown migration '{}'", name))
+}
+
+/// Connect to `url` with the configured pool settings.
+async fn open(url: &str, config: &Config) -> Result<DatabaseConnection, DbErr> {
+    let mut options = ConnectOptions::new(url);
+    options
+        .max_connections(config.database_max_connections.max(1))
+        .min_connections(
+            config
+                .database_min_connections
+                .min(config.database_max_connections),
+        )
+        .acquire_timeout(Duration::from_secs(
+            config.database_acquire_timeout_seconds,
+        ))
+        .idle_timeout(Duration::from_secs(config.database_idle_timeout_seconds));
+
+    if url.starts_with("sqlite:") {
+        if config.database_statement_timeout_ms.is_some() {
+            tracing::warn!("DATABASE_STATEMENT_TIMEOUT_MS is ignored on SQLite");
+        }
+        sqlite_options(&mut options, url)?;
+    } else if let Some(timeout) = config.database_statement_timeout_ms {
+        options.map_sqlx_postgres_opts(move |opts| {
+            opts.options([("statement_timeout", format!("{}ms", timeout))])
+        });
     }
+    SeaDatabase::connect(options).await
+}
 
-    /// Check database connectivity by executing a simple query.
-    pub async fn ping(&self) -> Result<(), DbErr> {
-        self.connection
-            .execute(Statement::from_string(
-                self.connection.get_database_backend(),
-                "SELECT 1".to_string(),
-            ))
-            .await?;
-        Ok(())
+#[cfg(feature = "sqlite")]
+fn sqlite_options(options: &mut ConnectOptions, url: &str) -> Result<(), DbErr> {
+    use crate::config::SQLITE_MEMORY_CONNECTION_LIFETIME_SECONDS;
+
+    options.map_sqlx_sqlite_opts(|opts| opts.create_if_missing(true));
+
+    // Every connection to `:memory:` opens a new, empty database, so the
+    // pool keeps a single connection open for as long as it lives
+    if url.contains(":memory:") || url.contains("mode=memory") {
+        let lifetime = Duration::from_secs(SQLITE_MEMORY_CONNECTION_LIFETIME_SECONDS);
+        options
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(lifetime)
+            .max_lifetime(lifetime);
     }
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_options(_options: &mut ConnectOptions, _url: &str) -> Result<(), DbErr> {
+    Err(DbErr::Custom(
+        "SQLite support is not enabled; build with `--features sqlite`".to_string(),
+    ))
 }
@@ -0,0 +1,297 @@
+//! Seed data for development and staging databases.
+//!
+//! A [`SeedSet`] lists users to create and how many fake users to generate.
+//! Sets are built in ([`SEED_SETS`]) or loaded from YAML/JSON files:
+//!
+//! ```yaml
+//! name: demo
+//! generate: 50
+//! users:
+//!   - email: owner@example.com
+//!     name: Owner
+//!     password: Sup3r-secret
+//!     role: admin
+//!   - email: gone@example.com
+//!     name: Gone
+//!     deleted: true
+//! ```
+//!
+//! Seeding is idempotent: users whose email already exists, in any casing
+//! and deleted or not, are left alone.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::config::{
+    DEFAULT_SEED_SET, ROLE_ADMIN, ROLE_USER, SEED_ADMIN_EMAIL, SEED_DEFAULT_PASSWORD,
+    SEED_DELETED_EVERY, SEED_STAGING_USERS, VALID_ROLES,
+};
+use crate::domain::{EmailAddress, LocalPartPolicy, Password};
+use crate::errors::{AppError, AppResult};
+use crate::infra::{UnitOfWork, UserRepository};
+use crate::with_transaction;
+
+/// Names of the built-in seed sets.
+pub const SEED_SETS: [&str; 2] = [DEFAULT_SEED_SET, "staging"];
+
+const FIRST_NAMES: [&str; 12] = [
+    "Ada",
+    "Alan",
+    "Barbara",
+    "Dennis",
+    "Edsger",
+    "Frances",
+    "Grace",
+    "John",
+    "Katherine",
+    "Linus",
+    "Margaret",
+    "Niklaus",
+];
+
+const LAST_NAMES: [&str; 10] = [
+    "Allen", "Hopper", "Johnson", "Knuth", "Lovelace", "Liskov", "Ritchie", "Thompson", "Turing",
+    "Wirth",
+];
+
+/// A user to seed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SeedUser {
+    pub email: String,
+    pub name: String,
+    /// Defaults to [`SEED_DEFAULT_PASSWORD`]
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Defaults to the `user` role
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Soft-delete the user after creating it
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+impl SeedUser {
+    fn new(email: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            email: email.into(),
+            name: name.into(),
+            password: None,
+            role: None,
+            deleted: false,
+        }
+    }
+}
+
+/// A named set of users to seed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SeedSet {
+    pub name: String,
+    #[serde(default)]
+    pub users: Vec<SeedUser>,
+    /// Number of fake users to generate
+    #[serde(default)]
+    pub generate: usize,
+}
+
+impl SeedSet {
+    /// Built-in seed set by name.
+    pub fn builtin(name: &str) -> AppResult<Self> {
+        let mut admin = SeedUser::new(SEED_ADMIN_EMAIL, "Admin");
+        admin.role = Some(ROLE_ADMIN.to_string());
+
+        match name.trim().to_lowercase().as_str() {
+            DEFAULT_SEED_SET => {
+                let mut former = SeedUser::new("former@example.com", "Former User");
+                former.deleted = true;
+                Ok(Self {
+                    name: DEFAULT_SEED_SET.to_string(),
+                    users: vec![
+                        admin,
+                        SeedUser::new("alice@example.com", "Alice Example"),
+                        SeedUser::new("bob@example.com", "Bob Example"),
+                        former,
+                    ],
+                    generate: 20,
+                })
+            }
+            "staging" => Ok(Self {
+                name: "staging".to_string(),
+                users: vec![admin],
+                generate: SEED_STAGING_USERS,
+            }),
+            _ => Err(AppError::validation(format!(
+                "Unknown seed set '{}', expected one of: {}",
+                name,
+                SEED_SETS.join(", ")
+            ))),
+        }
+    }
+
+    /// Load a seed set from a `.yaml`, `.yml` or `.json` file.
+    pub fn from_file(path: &Path) -> AppResult<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::validation(format!("Cannot read {}: {}", path.display(), e)))?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let parsed = match extension.as_str() {
+            "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+            "json" => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            _ => Err("expected a .yaml, .yml or .json file".to_string()),
+        };
+        parsed.map_err(|e| {
+            AppError::validation(format!("Invalid seed file {}: {}", path.display(), e))
+        })
+    }
+
+    /// Generate `count` fake users instead of the set's default.
+    pub fn with_generated(mut self, count: usize) -> Self {
+        self.generate = count;
+        self
+    }
+
+    /// Listed users followed by the generated ones.
+    ///
+    /// Generated users are numbered, so the same count always yields the
+    /// same users; every [`SEED_DELETED_EVERY`]th one is soft-deleted.
+    pub fn users(&self) -> Vec<SeedUser> {
+        let generated = (1..=self.generate).map(|n| {
+            let first = FIRST_NAMES[n % FIRST_NAMES.len()];
+            let last = LAST_NAMES[(n / FIRST_NAMES.len()) % LAST_NAMES.len()];
+            let mut user = SeedUser::new(
+                format!("user{:04}@example.com", n),
+                format!("{} {}", first, last),
+            );
+            user.deleted = n % SEED_DELETED_EVERY == 0;
+            user
+        });
+        self.users.iter().cloned().chain(generated).collect()
+    }
+}
+
+/// Outcome of seeding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    /// Users created
+    pub created: usize,
+    /// Users skipped because their email exists
+    pub existing: usize,
+    /// Created users that were soft-deleted
+    pub deleted: usize,
+}
+
+/// Creates seed users through a [`UnitOfWork`], one transaction per user.
+pub struct Seeder<U: UnitOfWork> {
+    uow: Arc<U>,
+    email_policy: LocalPartPolicy,
+}
+
+impl<U: UnitOfWork> Seeder<U> {
+    pub fn new(uow: Arc<U>) -> Self {
+        Self {
+            uow,
+            email_policy: LocalPartPolicy::default(),
+        }
+    }
+
+    /// Normalize seed emails like registration does.
+    pub fn with_email_policy(mut self, policy: LocalPartPolicy) -> Self {
+        self.email_policy = policy;
+        self
+    }
+
+    /// Create the users of `set` that do not exist yet.
+    pub async fn run(&self, set: &SeedSet) -> AppResult<SeedReport> {
+        let mut report = SeedReport::default();
+        // Hashing is slow and generated users share a password
+        let mut hashes: HashMap<String, String> = HashMap::new();
+
+        for seed in set.users() {
+            if let Some(role) = seed.role.as_deref().filter(|r| !VALID_ROLES.contains(r)) {
+                return Err(AppError::validation(format!(
+                    "Seed user {} has unknown role '{}'",
+                    seed.email, role
+                )));
+            }
+            let email = EmailAddress::parse(&seed.email, self.email_policy)?.into_string();
+            if self
+                .uow
+                .users()
+                .find_by_email_with_deleted(&email)
+                .await?
+                .is_some()
+            {
+                report.existing += 1;
+                continue;
+            }
+
+            let password = seed.password.as_deref().unwrap_or(SEED_DEFAULT_PASSWORD);
+            let hash = match hashes.get(password) {
+                Some(hash) => hash.clone(),
+                None => {
+                    let hash = Password::new(password)?.into_string();
+                    hashes.insert(password.to_string(), hash.clone());
+                    hash
+                }
+            };
+
+            // A failed role update or delete must not leave a half-seeded user
+            let role = seed.role.filter(|role| role != ROLE_USER);
+            let deleted = seed.deleted;
+            with_transaction!(self.uow, |ctx| {
+                let users = ctx.users();
+                let user = users.create(email, hash, seed.name).await?;
+                if role.is_some() {
+                    users.update(user.id, None, role, None).await?;
+                }
+                if deleted {
+                    users.delete(user.id).await?;
+                }
+                Ok(())
+            })?;
+
+            if deleted {
+                report.deleted += 1;
+            }
+            report.created += 1;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_sets() {
+        let development = SeedSet::builtin("development").unwrap();
+        assert!(development
+            .users()
+            .iter()
+            .any(|u| u.email == SEED_ADMIN_EMAIL && u.role.as_deref() == Some(ROLE_ADMIN)));
+
+        let staging = SeedSet::builtin("Staging").unwrap();
+        let users = staging.users();
+        assert_eq!(users.len(), SEED_STAGING_USERS + 1);
+        assert_eq!(
+            users.iter().filter(|u| u.deleted).count(),
+            SEED_STAGING_USERS / SEED_DELETED_EVERY
+        );
+        assert!(SeedSet::builtin("production").is_err());
+    }
+
+    #[test]
+    fn test_generated_users_are_stable() {
+        let set = SeedSet::builtin("staging").unwrap().with_generated(5);
+        assert_eq!(set.users(), set.clone().users());
+        assert_eq!(set.users()[1].email, "user0001@example.com");
+    }
+}
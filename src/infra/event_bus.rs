@@ -0,0 +1,303 @@
+//! Redis Streams event bus.
+//!
+//! Events are appended to a capped stream with `XADD`. Consumers read
+//! through consumer groups, so each event is delivered to one consumer per
+//! group and stays pending until acknowledged. A consumer first re-reads
+//! its own unacknowledged entries, which makes delivery at-least-once
+//! across restarts. Groups can be created or rewound at any offset to
+//! replay history.
+
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::streams::{
+    StreamId, StreamMaxlen, StreamRangeReply, StreamReadOptions, StreamReadReply,
+};
+use redis::{AsyncCommands, RedisError};
+use tokio::time::{sleep, Duration};
+
+use super::redis_client::CacheConnection;
+use crate::config::{DEFAULT_EVENT_STREAM, EVENT_STREAM_MAX_LEN};
+use crate::domain::{DomainEvent, EventBus};
+use crate::errors::{AppError, AppResult};
+
+/// Stream field holding the event name
+const FIELD_NAME: &str = "name";
+
+/// Stream field holding the JSON-encoded event
+const FIELD_EVENT: &str = "event";
+
+/// Where a consumer group starts reading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartFrom {
+    /// Replay the whole retained stream
+    Beginning,
+    /// Only events published from now on
+    Latest,
+    /// Events after the given stream offset
+    Offset(String),
+}
+
+impl StartFrom {
+    fn id(&self) -> &str {
+        match self {
+            Self::Beginning => "0",
+            Self::Latest => "$",
+            Self::Offset(id) => id,
+        }
+    }
+}
+
+/// An event read from the stream together with its offset.
+#[derive(Debug, Clone)]
+pub struct ReceivedEvent {
+    /// Stream entry ID, used to acknowledge or resume
+    pub offset: String,
+    pub event: DomainEvent,
+}
+
+/// Event bus publishing to a Redis stream.
+#[derive(Clone)]
+pub struct RedisEventBus {
+    connection: CacheConnection,
+    stream: String,
+    max_len: usize,
+}
+
+impl RedisEventBus {
+    /// Create a bus on the default stream.
+    pub fn new(connection: CacheConnection) -> Self {
+        Self {
+            connection,
+            stream: DEFAULT_EVENT_STREAM.to_string(),
+            max_len: EVENT_STREAM_MAX_LEN,
+        }
+    }
+
+    /// Use a different stream key.
+    pub fn with_stream(mut self, stream: impl Into<String>) -> Self {
+        self.stream = stream.into();
+        self
+    }
+
+    /// Approximate number of events retained in the stream.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Stream key events are published to.
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    /// Create a consumer group if it does not exist yet.
+    pub async fn create_group(&self, group: &str, start: StartFrom) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let created: Result<(), RedisError> = conn
+            .xgroup_create_mkstream(&self.stream, group, start.id())
+            .await;
+
+        match created {
+            Ok(()) => {
+                tracing::info!(stream = %self.stream, group = %group, "Consumer group created");
+                Ok(())
+            }
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            Err(e) => Err(event_error(e)),
+        }
+    }
+
+    /// Move an existing consumer group to a new offset to replay or skip events.
+    pub async fn reset_group(&self, group: &str, start: StartFrom) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let _: () = conn
+            .xgroup_setid(&self.stream, group, start.id())
+            .await
+            .map_err(event_error)?;
+        tracing::info!(stream = %self.stream, group = %group, offset = %start.id(), "Consumer group reset");
+        Ok(())
+    }
+
+    /// Read up to `count` events starting at `offset` (inclusive), outside any group.
+    pub async fn read_range(&self, offset: &str, count: usize) -> AppResult<Vec<ReceivedEvent>> {
+        let mut conn = self.connection.clone();
+        let reply: StreamRangeReply = conn
+            .xrange_count(&self.stream, offset, "+", count)
+            .await
+            .map_err(event_error)?;
+
+        Ok(reply.ids.iter().filter_map(parse_entry).collect())
+    }
+
+    /// Join a consumer group under the given consumer name.
+    ///
+    /// The group must exist (see [`RedisEventBus::create_group`]).
+    pub async fn consumer(&self, group: &str, name: &str) -> AppResult<EventConsumer> {
+        let blocking = self.connection.dedicated().await.map_err(event_error)?;
+
+        Ok(EventConsumer {
+            connection: self.connection.clone(),
+            blocking,
+            stream: self.stream.clone(),
+            group: group.to_string(),
+            name: name.to_string(),
+            backlog_drained: false,
+        })
+    }
+}
+
+#[async_trait]
+impl EventBus for RedisEventBus {
+    async fn publish(&self, event: DomainEvent) -> AppResult<()> {
+        let payload = serde_json::to_string(&event)
+            .map_err(|e| AppError::internal(format!("Event serialization error: {}", e)))?;
+
+        let mut conn = self.connection.clone();
+        let offset: String = conn
+            .xadd_maxlen(
+                &self.stream,
+                StreamMaxlen::Approx(self.max_len),
+                "*",
+                &[(FIELD_NAME, event.name()), (FIELD_EVENT, payload.as_str())],
+            )
+            .await
+            .map_err(event_error)?;
+
+        tracing::debug!(stream = %self.stream, offset = %offset, event = event.name(), "Event published");
+        Ok(())
+    }
+}
+
+/// A member of a consumer group.
+pub struct EventConsumer {
+    connection: CacheConnection,
+    /// Dedicated connection for blocking reads (None on Cluster)
+    blocking: Option<MultiplexedConnection>,
+    stream: String,
+    group: String,
+    name: String,
+    /// Whether this consumer's unacknowledged entries have been re-read
+    backlog_drained: bool,
+}
+
+impl EventConsumer {
+    /// Read the next batch of events, waiting up to `block` for new ones.
+    ///
+    /// Entries delivered earlier but never acknowledged are returned first.
+    /// Events that cannot be decoded are acknowledged and skipped.
+    pub async fn next_batch(
+        &mut self,
+        count: usize,
+        block: Duration,
+    ) -> AppResult<Vec<ReceivedEvent>> {
+        if !self.backlog_drained {
+            let pending = self.read("0", count, None).await?;
+            if !pending.is_empty() {
+                return Ok(pending);
+            }
+            self.backlog_drained = true;
+        }
+
+        self.read(">", count, Some(block)).await
+    }
+
+    /// Acknowledge processed events. Returns how many were still pending.
+    pub async fn ack(&self, offsets: &[String]) -> AppResult<u64> {
+        if offsets.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.connection.clone();
+        let acked: u64 = conn
+            .xack(&self.stream, &self.group, offsets)
+            .await
+            .map_err(event_error)?;
+        Ok(acked)
+    }
+
+    async fn read(
+        &mut self,
+        id: &str,
+        count: usize,
+        block: Option<Duration>,
+    ) -> AppResult<Vec<ReceivedEvent>> {
+        let options = StreamReadOptions::default()
+            .group(&self.group, &self.name)
+            .count(count);
+
+        let reply: Option<StreamReadReply> = match (block, self.blocking.as_mut()) {
+            (Some(block), Some(conn)) => conn
+                .xread_options(
+                    &[&self.stream],
+                    &[id],
+                    &options.block(block.as_millis() as usize),
+                )
+                .await
+                .map_err(event_error)?,
+            (block, _) => {
+                let mut conn = self.connection.clone();
+                let reply: Option<StreamReadReply> = conn
+                    .xread_options(&[&self.stream], &[id], &options)
+                    .await
+                    .map_err(event_error)?;
+                // No blocking reads available: wait out the block time when idle
+                if let Some(block) =
+                    block.filter(|_| reply.as_ref().is_none_or(|r| r.keys.is_empty()))
+                {
+                    sleep(block).await;
+                }
+                reply
+            }
+        };
+
+        let entries: Vec<StreamId> = reply
+            .map(|r| r.keys.into_iter().flat_map(|k| k.ids).collect())
+            .unwrap_or_default();
+
+        let mut events = Vec::with_capacity(entries.len());
+        let mut poison = Vec::new();
+        for entry in &entries {
+            match parse_entry(entry) {
+                Some(event) => events.push(event),
+                None => poison.push(entry.id.clone()),
+            }
+        }
+        if !poison.is_empty() {
+            self.ack(&poison).await?;
+        }
+
+        Ok(events)
+    }
+}
+
+/// Decode a stream entry, logging entries that are not valid events.
+fn parse_entry(entry: &StreamId) -> Option<ReceivedEvent> {
+    let payload: String = entry.get(FIELD_EVENT)?;
+    match serde_json::from_str(&payload) {
+        Ok(event) => Some(ReceivedEvent {
+            offset: entry.id.clone(),
+            event,
+        }),
+        Err(e) => {
+            tracing::error!(offset = %entry.id, error = %e, "Skipping undecodable event");
+            None
+        }
+    }
+}
+
+/// Convert Redis error to AppError.
+fn event_error(e: RedisError) -> AppError {
+    tracing::error!("Redis stream error: {}", e);
+    AppError::internal(format!("Event bus error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_from_ids() {
+        assert_eq!(StartFrom::Beginning.id(), "0");
+        assert_eq!(StartFrom::Latest.id(), "$");
+        assert_eq!(StartFrom::Offset("17-0".into()).id(), "17-0");
+    }
+}
@@ -0,0 +1,140 @@
+//! Leader election built on distributed locks.
+//!
+//! One instance holds a lock on the election resource and keeps renewing it.
+//! If renewal fails, leadership is dropped immediately so two instances never
+//! believe they lead at the same time. On shutdown the lock is released so
+//! another instance can take over without waiting for the TTL to expire.
+
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+use super::cache::{Cache, LockGuard};
+use crate::config::DEFAULT_LEADER_TTL_SECONDS;
+
+/// Leader election for a named resource.
+pub struct LeaderElection {
+    cache: Arc<Cache>,
+    resource: String,
+    ttl_seconds: u64,
+}
+
+impl LeaderElection {
+    /// Create an election for the given resource with the default TTL.
+    pub fn new(cache: Arc<Cache>, resource: impl Into<String>) -> Self {
+        Self {
+            cache,
+            resource: resource.into(),
+            ttl_seconds: DEFAULT_LEADER_TTL_SECONDS,
+        }
+    }
+
+    /// Set the leadership TTL. Renewal happens every third of the TTL.
+    pub fn with_ttl(mut self, ttl_seconds: u64) -> Self {
+        self.ttl_seconds = ttl_seconds.max(1);
+        self
+    }
+
+    /// Start campaigning in the background.
+    ///
+    /// Leadership is handed off when `shutdown` changes or its sender is dropped.
+    pub fn spawn(self, mut shutdown: watch::Receiver<bool>) -> Leadership {
+        let (state_tx, state_rx) = watch::channel(false);
+
+        let task = tokio::spawn(async move {
+            let renew_every = Duration::from_millis(self.ttl_seconds * 1000 / 3);
+            let mut guard: Option<LockGuard> = None;
+
+            loop {
+                guard = self.campaign(guard, &state_tx).await;
+
+                tokio::select! {
+                    _ = sleep(renew_every) => {}
+                    _ = shutdown.changed() => break,
+                }
+            }
+
+            // Hand off: step down first, then free the lock for the next leader
+            state_tx.send_replace(false);
+            if let Some(guard) = guard {
+                match guard.release().await {
+                    Ok(()) => tracing::info!(resource = %self.resource, "Leadership released"),
+                    Err(e) => {
+                        tracing::warn!(resource = %self.resource, error = %e, "Failed to release leadership")
+                    }
+                }
+            }
+        });
+
+        Leadership {
+            state: state_rx,
+            task,
+        }
+    }
+
+    /// Acquire or renew leadership, returning the guard if still leader.
+    async fn campaign(
+        &self,
+        guard: Option<LockGuard>,
+        state: &watch::Sender<bool>,
+    ) -> Option<LockGuard> {
+        match guard {
+            Some(guard) => match guard.extend(self.ttl_seconds).await {
+                Ok(true) => Some(guard),
+                Ok(false) => {
+                    tracing::warn!(resource = %self.resource, "Leadership lost to another instance");
+                    state.send_replace(false);
+                    None
+                }
+                Err(e) => {
+                    // Cannot prove we still hold the lock - step down to avoid split brain
+                    tracing::warn!(resource = %self.resource, error = %e, "Leadership renewal failed");
+                    state.send_replace(false);
+                    None
+                }
+            },
+            None => match self
+                .cache
+                .try_acquire_lock_with_ttl(&self.resource, self.ttl_seconds)
+                .await
+            {
+                Ok(Some(guard)) => {
+                    tracing::info!(resource = %self.resource, "Leadership acquired");
+                    state.send_replace(true);
+                    Some(guard)
+                }
+                Ok(None) => None,
+                Err(e) => {
+                    tracing::warn!(resource = %self.resource, error = %e, "Leader election attempt failed");
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Handle to a running election.
+pub struct Leadership {
+    state: watch::Receiver<bool>,
+    task: JoinHandle<()>,
+}
+
+impl Leadership {
+    /// Whether this instance currently leads.
+    pub fn is_leader(&self) -> bool {
+        *self.state.borrow()
+    }
+
+    /// Subscribe to leadership changes.
+    pub fn watch(&self) -> watch::Receiver<bool> {
+        self.state.clone()
+    }
+
+    /// Wait until the election loop has stopped and leadership is released.
+    pub async fn stopped(self) {
+        if let Err(e) = self.task.await {
+            tracing::error!(error = %e, "Leader election task panicked");
+        }
+    }
+}
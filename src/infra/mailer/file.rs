@@ -0,0 +1,73 @@
+//! File-drop transport for development and CI.
+//!
+//! Writes each message as an RFC 5322 `.eml` file. In maildir layout the
+//! file is written to `tmp/` and then renamed into `new/`, so readers
+//! never see a partially written message.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::{build_message, Email, EmailError, EmailTransport};
+
+/// Writes emails to a directory.
+pub struct FileTransport {
+    dir: PathBuf,
+    maildir: bool,
+}
+
+impl FileTransport {
+    /// Create a transport writing into `dir`.
+    pub fn new(dir: impl AsRef<Path>, maildir: bool) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            maildir,
+        }
+    }
+
+    async fn write(&self, name: &str, contents: &[u8]) -> std::io::Result<PathBuf> {
+        if !self.maildir {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let path = self.dir.join(format!("{}.eml", name));
+            tokio::fs::write(&path, contents).await?;
+            return Ok(path);
+        }
+
+        let tmp = self.dir.join("tmp");
+        let new = self.dir.join("new");
+        tokio::fs::create_dir_all(&tmp).await?;
+        tokio::fs::create_dir_all(&new).await?;
+        tokio::fs::create_dir_all(self.dir.join("cur")).await?;
+
+        let staged = tmp.join(name);
+        tokio::fs::write(&staged, contents).await?;
+        let path = new.join(name);
+        tokio::fs::rename(&staged, &path).await?;
+        Ok(path)
+    }
+}
+
+#[async_trait]
+impl EmailTransport for FileTransport {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn send(&self, email: &Email) -> Result<(), EmailError> {
+        let message = build_message(email)?;
+        let name = format!(
+            "{}-{}",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            Uuid::new_v4()
+        );
+
+        let path = self
+            .write(&name, &message.formatted())
+            .await
+            .map_err(|e| EmailError::Transient(format!("Failed to write email file: {}", e)))?;
+
+        tracing::info!(to = %email.to, path = %path.display(), "Email written to file");
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+//! Generic HTTP email provider transport.
+//!
+//! POSTs a JSON document to a provider endpoint:
+//! `{"from": "...", "to": "...", "subject": "...", "text": "...", "html": "..."}`
+//! (`html` only when the email has an HTML part)
+//! with an optional bearer token. 2xx is success; 408, 429 and 5xx are
+//! retried; any other status is a permanent rejection.
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use std::time::Duration;
+
+use super::{Email, EmailError, EmailTransport};
+use crate::config::EMAIL_HTTP_TIMEOUT_SECONDS;
+
+/// Request body sent to the provider
+#[derive(Serialize)]
+struct Payload<'a> {
+    from: &'a str,
+    to: &'a str,
+    subject: &'a str,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    html: Option<&'a str>,
+}
+
+/// Sends through an HTTP JSON API.
+pub struct HttpTransport {
+    client: Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl HttpTransport {
+    /// Create a transport posting to `url`.
+    pub fn new(url: impl Into<String>, token: Option<String>) -> Result<Self, EmailError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(EMAIL_HTTP_TIMEOUT_SECONDS))
+            .build()
+            .map_err(|e| EmailError::Permanent(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            url: url.into(),
+            token,
+        })
+    }
+}
+
+/// Whether a provider response status is worth retrying.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+#[async_trait]
+impl EmailTransport for HttpTransport {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn send(&self, email: &Email) -> Result<(), EmailError> {
+        let payload = Payload {
+            from: &email.from,
+            to: &email.to,
+            subject: &email.subject,
+            text: &email.text,
+            html: email.html.as_deref(),
+        };
+
+        let mut request = self.client.post(&self.url).json(&payload);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| EmailError::Transient(format!("Email provider unreachable: {}", e)))?;
+
+        let status = response.status();
+        if status.is_success() {
+            tracing::debug!(to = %email.to, status = %status, "Email provider accepted email");
+            return Ok(());
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let message = format!("Email provider returned {}: {}", status, body);
+        if is_retryable(status) {
+            Err(EmailError::Transient(message))
+        } else {
+            Err(EmailError::Permanent(message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
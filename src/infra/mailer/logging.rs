@@ -0,0 +1,31 @@
+//! Log-only transport for development.
+
+use async_trait::async_trait;
+
+use super::{Email, EmailError, EmailTransport};
+
+/// Logs emails instead of sending them.
+pub struct LogTransport;
+
+#[async_trait]
+impl EmailTransport for LogTransport {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    async fn send(&self, email: &Email) -> Result<(), EmailError> {
+        tracing::info!(
+            "=== EMAIL (not sent) ===\n\
+             From: {}\n\
+             To: {}\n\
+             Subject: {}\n\
+             Body:\n{}\n\
+             ========================",
+            email.from,
+            email.to,
+            email.subject,
+            email.text
+        );
+        Ok(())
+    }
+}
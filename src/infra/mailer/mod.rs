@@ -0,0 +1,256 @@
+//! Outgoing email delivery.
+//!
+//! [`EmailTransport`] abstracts how a message leaves the application:
+//! - `smtp` - an SMTP relay (STARTTLS, implicit TLS or plain)
+//! - `file` - `.eml` files in a directory, optionally in maildir layout
+//! - `http` - JSON POST to an HTTP email provider
+//! - `log` - log the message only
+//!
+//! The transport is selected by `EMAIL_TRANSPORT` (see [`EmailConfig`]).
+//! Failures are classified as permanent or transient so the job queue can
+//! decide whether to retry.
+
+mod file;
+mod http;
+mod logging;
+mod smtp;
+mod templates;
+
+pub use file::FileTransport;
+pub use http::HttpTransport;
+pub use logging::LogTransport;
+pub use smtp::{SmtpTls, SmtpTransport};
+pub use templates::{EmailTemplates, RenderedEmail};
+
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::Message;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::config::{
+    DEFAULT_EMAIL_FILE_DIR, DEFAULT_SMTP_FROM, DEFAULT_SMTP_PORT, EMAIL_TRANSPORT_FILE,
+    EMAIL_TRANSPORT_HTTP, EMAIL_TRANSPORT_LOG, EMAIL_TRANSPORT_SMTP,
+};
+
+/// A fully addressed email ready for delivery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    /// Plain text body
+    pub text: String,
+    /// Optional HTML alternative to `text`
+    pub html: Option<String>,
+}
+
+/// Email delivery failure.
+#[derive(Debug, Error)]
+pub enum EmailError {
+    /// Retrying will not help (bad recipient, rejected message, bad config)
+    #[error("Permanent email failure: {0}")]
+    Permanent(String),
+
+    /// Temporary problem (network, server busy); the job should be retried
+    #[error("Transient email failure: {0}")]
+    Transient(String),
+}
+
+impl EmailError {
+    /// Whether the failure should not be retried.
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, Self::Permanent(_))
+    }
+}
+
+/// Delivers emails.
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    /// Transport name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Deliver a single email
+    async fn send(&self, email: &Email) -> Result<(), EmailError>;
+}
+
+/// Available transports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    Smtp,
+    File,
+    Http,
+    Log,
+}
+
+impl std::str::FromStr for TransportKind {
+    type Err = EmailError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            EMAIL_TRANSPORT_SMTP => Ok(Self::Smtp),
+            EMAIL_TRANSPORT_FILE => Ok(Self::File),
+            EMAIL_TRANSPORT_HTTP => Ok(Self::Http),
+            EMAIL_TRANSPORT_LOG => Ok(Self::Log),
+            other => Err(EmailError::Permanent(format!(
+                "Unknown EMAIL_TRANSPORT '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Email configuration from environment.
+#[derive(Debug, Clone)]
+pub struct EmailConfig {
+    pub transport: TransportKind,
+    /// Default sender address
+    pub from: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_user: Option<String>,
+    pub smtp_pass: Option<String>,
+    pub smtp_tls: SmtpTls,
+    /// Output directory for the file transport
+    pub file_dir: PathBuf,
+    /// Write maildir (`tmp/` + `new/`) instead of flat `.eml` files
+    pub file_maildir: bool,
+    /// Endpoint for the HTTP transport
+    pub http_url: Option<String>,
+    /// Bearer token for the HTTP transport
+    pub http_token: Option<String>,
+}
+
+impl EmailConfig {
+    /// Load email settings from the environment.
+    ///
+    /// Without `EMAIL_TRANSPORT`, SMTP is used when `SMTP_HOST` is set and
+    /// emails are only logged otherwise.
+    pub fn from_env() -> Result<Self, EmailError> {
+        let smtp_host = env::var("SMTP_HOST").ok();
+        let smtp_port = env::var("SMTP_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_SMTP_PORT);
+
+        let transport = match env::var("EMAIL_TRANSPORT") {
+            Ok(kind) => kind.parse()?,
+            Err(_) if smtp_host.is_some() => TransportKind::Smtp,
+            Err(_) => TransportKind::Log,
+        };
+
+        Ok(Self {
+            transport,
+            from: env::var("EMAIL_FROM")
+                .or_else(|_| env::var("SMTP_FROM"))
+                .unwrap_or_else(|_| DEFAULT_SMTP_FROM.to_string()),
+            smtp_host,
+            smtp_port,
+            smtp_user: env::var("SMTP_USER").ok(),
+            smtp_pass: env::var("SMTP_PASS").ok(),
+            smtp_tls: SmtpTls::parse(env::var("SMTP_TLS").ok().as_deref(), smtp_port),
+            file_dir: env::var("EMAIL_FILE_DIR")
+                .unwrap_or_else(|_| DEFAULT_EMAIL_FILE_DIR.to_string())
+                .into(),
+            file_maildir: env::var("EMAIL_FILE_MAILDIR")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            http_url: env::var("EMAIL_HTTP_URL").ok(),
+            http_token: env::var("EMAIL_HTTP_TOKEN").ok(),
+        })
+    }
+
+    /// Build the configured transport.
+    pub fn build_transport(&self) -> Result<Arc<dyn EmailTransport>, EmailError> {
+        Ok(match self.transport {
+            TransportKind::Smtp => Arc::new(SmtpTransport::from_config(self)?),
+            TransportKind::File => Arc::new(FileTransport::new(&self.file_dir, self.file_maildir)),
+            TransportKind::Http => {
+                let url = self.http_url.as_deref().ok_or_else(|| {
+                    EmailError::Permanent(
+                        "EMAIL_HTTP_URL must be set for the http transport".into(),
+                    )
+                })?;
+                Arc::new(HttpTransport::new(url, self.http_token.clone())?)
+            }
+            TransportKind::Log => Arc::new(LogTransport),
+        })
+    }
+}
+
+/// Build a MIME message (shared by SMTP and file transports).
+///
+/// Emails with an HTML part become `multipart/alternative` with the plain
+/// text first, so clients without HTML support still show the text.
+pub(crate) fn build_message(email: &Email) -> Result<Message, EmailError> {
+    let from: Mailbox = email
+        .from
+        .parse()
+        .map_err(|e| EmailError::Permanent(format!("Invalid sender '{}': {}", email.from, e)))?;
+    let to: Mailbox = email
+        .to
+        .parse()
+        .map_err(|e| EmailError::Permanent(format!("Invalid recipient '{}': {}", email.to, e)))?;
+
+    let builder = Message::builder().from(from).to(to).subject(&email.subject);
+    let message = match &email.html {
+        Some(html) => builder.multipart(MultiPart::alternative_plain_html(
+            email.text.clone(),
+            html.clone(),
+        )),
+        None => builder
+            .header(lettre::message::header::ContentType::TEXT_PLAIN)
+            .body(email.text.clone()),
+    };
+    message.map_err(|e| EmailError::Permanent(format!("Invalid message: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_kind_parsing() {
+        assert_eq!(
+            "SMTP".parse::<TransportKind>().unwrap(),
+            TransportKind::Smtp
+        );
+        assert_eq!(
+            "file".parse::<TransportKind>().unwrap(),
+            TransportKind::File
+        );
+        assert!("pigeon"
+            .parse::<TransportKind>()
+            .unwrap_err()
+            .is_permanent());
+    }
+
+    #[test]
+    fn test_invalid_recipient_is_permanent() {
+        let email = Email {
+            from: DEFAULT_SMTP_FROM.to_string(),
+            to: "not an address".to_string(),
+            subject: "Hi".to_string(),
+            text: "Body".to_string(),
+            html: None,
+        };
+        assert!(build_message(&email).unwrap_err().is_permanent());
+    }
+
+    #[test]
+    fn test_html_email_is_multipart_alternative() {
+        let email = Email {
+            from: DEFAULT_SMTP_FROM.to_string(),
+            to: "user@example.com".to_string(),
+            subject: "Hi".to_string(),
+            text: "Plain body".to_string(),
+            html: Some("<p>Html body</p>".to_string()),
+        };
+        let formatted = String::from_utf8(build_message(&email).unwrap().formatted()).unwrap();
+        assert!(formatted.contains("multipart/alternative"));
+        assert!(formatted.contains("Plain body"));
+        assert!(formatted.contains("<p>Html body</p>"));
+    }
+}
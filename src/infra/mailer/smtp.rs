@@ -0,0 +1,113 @@
+//! SMTP relay transport.
+
+use async_trait::async_trait;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::Error as SmtpError;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use std::time::Duration;
+
+use super::{build_message, Email, EmailConfig, EmailError, EmailTransport};
+use crate::config::{SMTP_IMPLICIT_TLS_PORT, SMTP_TIMEOUT_SECONDS};
+
+/// SMTP connection security.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain connection (local relays and test sinks only)
+    None,
+    /// Upgrade with STARTTLS (port 587)
+    StartTls,
+    /// TLS from the first byte (port 465)
+    Implicit,
+}
+
+impl SmtpTls {
+    /// Parse `SMTP_TLS`, defaulting by port when unset or unrecognised.
+    pub(super) fn parse(value: Option<&str>, port: u16) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("false" | "0" | "none" | "off") => Self::None,
+            Some("implicit" | "smtps" | "tls") => Self::Implicit,
+            Some("starttls") => Self::StartTls,
+            _ if port == SMTP_IMPLICIT_TLS_PORT => Self::Implicit,
+            _ => Self::StartTls,
+        }
+    }
+}
+
+impl From<SmtpError> for EmailError {
+    fn from(e: SmtpError) -> Self {
+        // 5xx replies are permanent; everything else (4xx, I/O, TLS, timeouts) may recover
+        if e.is_permanent() {
+            Self::Permanent(e.to_string())
+        } else {
+            Self::Transient(e.to_string())
+        }
+    }
+}
+
+/// Sends through an SMTP relay with a pooled connection.
+pub struct SmtpTransport {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    /// Build a transport from the SMTP settings.
+    pub fn from_config(config: &EmailConfig) -> Result<Self, EmailError> {
+        let host = config.smtp_host.as_deref().ok_or_else(|| {
+            EmailError::Permanent("SMTP_HOST must be set for the smtp transport".into())
+        })?;
+
+        let builder = match config.smtp_tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(|e| EmailError::Permanent(format!("Invalid SMTP host: {}", e)))?,
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+                .map_err(|e| EmailError::Permanent(format!("Invalid SMTP host: {}", e)))?,
+        };
+
+        let builder = builder
+            .port(config.smtp_port)
+            .timeout(Some(Duration::from_secs(SMTP_TIMEOUT_SECONDS)));
+
+        let builder = match &config.smtp_user {
+            Some(user) => builder.credentials(Credentials::new(
+                user.clone(),
+                config.smtp_pass.clone().unwrap_or_default(),
+            )),
+            None => builder,
+        };
+
+        Ok(Self {
+            mailer: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SmtpTransport {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn send(&self, email: &Email) -> Result<(), EmailError> {
+        let message = build_message(email)?;
+        let response = self.mailer.send(message).await?;
+
+        tracing::debug!(to = %email.to, code = %response.code(), "SMTP server accepted email");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_mode_parsing() {
+        assert_eq!(SmtpTls::parse(None, 587), SmtpTls::StartTls);
+        assert_eq!(SmtpTls::parse(None, 465), SmtpTls::Implicit);
+        assert_eq!(SmtpTls::parse(Some("false"), 465), SmtpTls::None);
+        assert_eq!(SmtpTls::parse(Some("implicit"), 2525), SmtpTls::Implicit);
+        // Legacy boolean form keeps meaning STARTTLS
+        assert_eq!(SmtpTls::parse(Some("true"), 587), SmtpTls::StartTls);
+    }
+}
@@ -0,0 +1,241 @@
+//! Transactional email templates.
+//!
+//! Templates are Jinja files named `{locale}/{name}.subject.txt`,
+//! `{locale}/{name}.txt` and optionally `{locale}/{name}.html`. Bodies
+//! usually extend a layout from `layouts/`. The built-in set under
+//! `templates/email` is embedded in the binary; files in
+//! `EMAIL_TEMPLATE_DIR` take precedence, so a deployment can override or
+//! add templates without rebuilding.
+//!
+//! Locales fall back from the requested one (`de-AT`) to its language
+//! (`de`) and finally to [`DEFAULT_EMAIL_LOCALE`].
+
+use minijinja::{context, Environment, ErrorKind, UndefinedBehavior, Value};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::EmailError;
+use crate::config::{DEFAULT_EMAIL_APP_NAME, DEFAULT_EMAIL_LOCALE};
+
+macro_rules! embedded {
+    ($($path:literal),* $(,)?) => {
+        &[$(($path, include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/", $path)))),*]
+    };
+}
+
+/// Templates compiled into the binary: (path, source)
+static EMBEDDED: &[(&str, &str)] = embedded![
+    "layouts/base.txt",
+    "layouts/base.html",
+    "en/welcome.subject.txt",
+    "en/welcome.txt",
+    "en/welcome.html",
+    "en/verify_email.subject.txt",
+    "en/verify_email.txt",
+    "en/verify_email.html",
+    "en/password_reset.subject.txt",
+    "en/password_reset.txt",
+    "en/password_reset.html",
+    "en/account_locked.subject.txt",
+    "en/account_locked.txt",
+    "en/account_locked.html",
+    "en/admin_digest.subject.txt",
+    "en/admin_digest.txt",
+    "en/admin_digest.html",
+    "de/welcome.subject.txt",
+    "de/welcome.txt",
+    "de/welcome.html",
+    "de/verify_email.subject.txt",
+    "de/verify_email.txt",
+    "de/verify_email.html",
+    "de/password_reset.subject.txt",
+    "de/password_reset.txt",
+    "de/password_reset.html",
+    "de/account_locked.subject.txt",
+    "de/account_locked.txt",
+    "de/account_locked.html",
+    "de/admin_digest.subject.txt",
+    "de/admin_digest.txt",
+    "de/admin_digest.html",
+];
+
+static GLOBAL: Lazy<EmailTemplates> = Lazy::new(EmailTemplates::from_env);
+
+/// Output of a rendered template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    /// Locale the template was actually rendered in
+    pub locale: String,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+/// Template registry and renderer.
+pub struct EmailTemplates {
+    env: Environment<'static>,
+}
+
+impl EmailTemplates {
+    /// Templates from an optional override directory, falling back to the
+    /// embedded set.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        env.add_global("app_name", DEFAULT_EMAIL_APP_NAME);
+        env.set_loader(move |name| {
+            if name.split('/').any(|part| part == "..") {
+                return Ok(None);
+            }
+            if let Some(dir) = &dir {
+                let path = dir.join(name);
+                if path.is_file() {
+                    return std::fs::read_to_string(&path).map(Some).map_err(|e| {
+                        minijinja::Error::new(
+                            ErrorKind::InvalidOperation,
+                            format!("Failed to read template {}: {}", path.display(), e),
+                        )
+                    });
+                }
+            }
+            Ok(EMBEDDED
+                .iter()
+                .find(|(path, _)| *path == name)
+                .map(|(_, source)| source.to_string()))
+        });
+        Self { env }
+    }
+
+    /// Embedded templates only.
+    pub fn embedded() -> Self {
+        Self::new(None)
+    }
+
+    /// Templates configured by `EMAIL_TEMPLATE_DIR`.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("EMAIL_TEMPLATE_DIR").ok().map(PathBuf::from))
+    }
+
+    /// Process-wide instance used by `EmailJob::from_template`.
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    /// Render `name` in `locale` (or its fallback) with `vars`.
+    ///
+    /// Missing templates and variables are permanent errors.
+    pub fn render(
+        &self,
+        name: &str,
+        locale: &str,
+        vars: impl Serialize,
+    ) -> Result<RenderedEmail, EmailError> {
+        if !is_identifier(name) {
+            return Err(EmailError::Permanent(format!(
+                "Invalid email template name '{}'",
+                name
+            )));
+        }
+
+        let locale = locale_candidates(locale)
+            .into_iter()
+            .find(|candidate| self.exists(&format!("{}/{}.txt", candidate, name)))
+            .ok_or_else(|| EmailError::Permanent(format!("Unknown email template '{}'", name)))?;
+
+        let ctx = context! { locale => locale.clone(), ..Value::from_serialize(&vars) };
+        let subject = self.render_file(&format!("{}/{}.subject.txt", locale, name), &ctx)?;
+        let text = self.render_file(&format!("{}/{}.txt", locale, name), &ctx)?;
+        let html_path = format!("{}/{}.html", locale, name);
+        let html = if self.exists(&html_path) {
+            Some(self.render_file(&html_path, &ctx)?)
+        } else {
+            None
+        };
+
+        Ok(RenderedEmail {
+            locale,
+            subject: subject
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            text: text.trim().to_string(),
+            html: html.map(|h| h.trim().to_string()),
+        })
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.env.get_template(path).is_ok()
+    }
+
+    fn render_file(&self, path: &str, ctx: &Value) -> Result<String, EmailError> {
+        self.env
+            .get_template(path)
+            .and_then(|t| t.render(ctx))
+            .map_err(|e| EmailError::Permanent(format!("Failed to render {}: {:#}", path, e)))
+    }
+}
+
+/// Template names are restricted to `[a-z0-9_-]`.
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Locales to try, most specific first: `de_AT` -> [`de-at`, `de`, `en`].
+fn locale_candidates(locale: &str) -> Vec<String> {
+    let normalized = locale.trim().to_lowercase().replace('_', "-");
+    let mut candidates = Vec::new();
+    if is_identifier(&normalized) {
+        candidates.push(normalized.clone());
+        if let Some((language, _)) = normalized.split_once('-') {
+            candidates.push(language.to_string());
+        }
+    }
+    candidates.push(DEFAULT_EMAIL_LOCALE.to_string());
+    candidates.dedup();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EMAIL_TEMPLATES;
+
+    #[test]
+    fn test_locale_candidates() {
+        assert_eq!(locale_candidates("de_AT"), vec!["de-at", "de", "en"]);
+        assert_eq!(locale_candidates("en"), vec!["en"]);
+        assert_eq!(locale_candidates("../etc"), vec!["en"]);
+    }
+
+    #[test]
+    fn test_builtin_templates_render_in_every_locale() {
+        let templates = EmailTemplates::embedded();
+        let vars = context! {
+            name => "Ada",
+            verify_url => "https://example.com/verify",
+            reset_url => "https://example.com/reset",
+            expires_in_hours => 24,
+            expires_in_minutes => 30,
+            unlock_at => "12:00 UTC",
+            since => "2024-01-01",
+            new_users => 3,
+            deleted_users => 1,
+            active_users => 42,
+        };
+
+        for name in EMAIL_TEMPLATES {
+            for locale in ["en", "de"] {
+                let rendered = templates.render(name, locale, &vars).unwrap();
+                assert_eq!(rendered.locale, locale);
+                assert!(!rendered.subject.is_empty());
+                assert!(rendered.text.contains("Ada"), "{}/{}", locale, name);
+                assert!(rendered.html.unwrap().contains("<html"));
+            }
+        }
+    }
+}
@@ -6,15 +6,37 @@
 //! - Caching systems (Redis)
 //! - Message queues
 //! - Unit of Work for transaction management
+//! - Transactional outbox for jobs and events
+//! - Record of purged users
 
 pub mod cache;
+pub mod codec;
 pub mod db;
+pub mod event_bus;
+pub mod leader;
+pub mod mailer;
+pub mod outbox;
+pub mod purge_log;
+pub mod redis_client;
+pub mod redlock;
 pub mod repositories;
 pub mod unit_of_work;
 
-pub use cache::{Cache, LockGuard, SemaphorePermit};
-pub use db::{Database, Migrator};
-pub use repositories::{UserRepository, UserStore};
+pub use cache::{Cache, LockGuard, SemaphorePermit, SemaphoreTicket};
+pub use codec::{CacheCodec, CacheFormat, CodecError};
+pub use db::{
+    duplicate_emails, email_collisions, email_rewrites, normalize_emails, Database,
+    DuplicateEmail, EmailRewrite, MigrationDrift, Migrator, Rollback, SeedReport, SeedSet, Seeder,
+    StartupMigrations,
+};
+pub use event_bus::{EventConsumer, ReceivedEvent, RedisEventBus, StartFrom};
+pub use leader::{LeaderElection, Leadership};
+pub use mailer::{Email, EmailConfig, EmailError, EmailTemplates, EmailTransport};
+pub use outbox::{OutboxMessage, TxOutbox};
+pub use purge_log::TxPurgeLog;
+pub use redis_client::{BlockingPool, CacheConnection, PooledConnection, RedisTopology};
+pub use redlock::Redlock;
+pub use repositories::{ConnectionRef, UserRepository, UserStore};
 pub use unit_of_work::{TransactionContext, TxUserRepository, UnitOfWork, Persistence};
 
 #[cfg(any(test, feature = "test-utils"))]
@@ -0,0 +1,153 @@
+//! Transactional outbox.
+//!
+//! Jobs and domain events that must only leave the system if a database
+//! change commits are written to the `outbox` table inside the same
+//! transaction (see [`TransactionContext::outbox`]). The outbox relay
+//! (`jobs::OutboxRelay`) later hands them to the job queue or event bus.
+//!
+//! Delivery is at-least-once. Every entry carries a deduplication key:
+//! writing a second entry with the same key is a no-op, relayed jobs reuse
+//! the entry id as their job id, and relayed events keep their event id so
+//! consumers can drop repeats.
+//!
+//! [`TransactionContext::outbox`]: super::TransactionContext::outbox
+
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{DatabaseTransaction, EntityTrait, Set};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::repositories::entities::outbox::{self, ActiveModel, Entity as OutboxEntity};
+use crate::domain::DomainEvent;
+use crate::errors::{AppError, AppResult};
+use crate::jobs::Job;
+
+/// A message waiting in the outbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutboxMessage {
+    /// Background job, optionally delayed until `run_at`
+    Job {
+        job: Job,
+        run_at: Option<DateTime<Utc>>,
+    },
+    /// Domain event for the event bus
+    Event { event: DomainEvent },
+}
+
+/// Outbox writer bound to a transaction.
+pub struct TxOutbox<'a> {
+    txn: &'a DatabaseTransaction,
+}
+
+impl<'a> TxOutbox<'a> {
+    pub(crate) fn new(txn: &'a DatabaseTransaction) -> Self {
+        Self { txn }
+    }
+
+    /// Enqueue a job once the transaction commits.
+    pub async fn enqueue(&self, job: Job) -> AppResult<Uuid> {
+        let id = Uuid::new_v4();
+        self.insert(id, id.to_string(), OutboxMessage::Job { job, run_at: None })
+            .await?;
+        Ok(id)
+    }
+
+    /// Enqueue a job to run at `at` once the transaction commits.
+    pub async fn enqueue_at(&self, job: Job, at: DateTime<Utc>) -> AppResult<Uuid> {
+        let id = Uuid::new_v4();
+        let message = OutboxMessage::Job {
+            job,
+            run_at: Some(at),
+        };
+        self.insert(id, id.to_string(), message).await?;
+        Ok(id)
+    }
+
+    /// Publish an event once the transaction commits.
+    ///
+    /// The event id is the deduplication key.
+    pub async fn publish(&self, event: DomainEvent) -> AppResult<Uuid> {
+        let id = Uuid::new_v4();
+        let key = format!("event:{}", event.id);
+        self.insert(id, key, OutboxMessage::Event { event }).await?;
+        Ok(id)
+    }
+
+    /// Add a message under an explicit deduplication key.
+    ///
+    /// Returns `None` if an entry with the same key already exists, e.g.
+    /// `welcome-email:{user_id}` written by a retried request.
+    pub async fn push(&self, message: OutboxMessage, dedup_key: &str) -> AppResult<Option<Uuid>> {
+        let id = Uuid::new_v4();
+        let inserted = self.insert(id, dedup_key.to_string(), message).await?;
+        Ok(inserted.then_some(id))
+    }
+
+    async fn insert(&self, id: Uuid, dedup_key: String, message: OutboxMessage) -> AppResult<bool> {
+        let payload = serde_json::to_value(&message)
+            .map_err(|e| AppError::internal(format!("Failed to serialize outbox entry: {}", e)))?;
+        let now = Utc::now();
+
+        let entry = ActiveModel {
+            id: Set(id),
+            dedup_key: Set(dedup_key),
+            payload: Set(payload),
+            attempts: Set(0),
+            last_error: Set(None),
+            available_at: Set(now),
+            created_at: Set(now),
+            dispatched_at: Set(None),
+        };
+
+        let rows = OutboxEntity::insert(entry)
+            .on_conflict(
+                OnConflict::column(outbox::Column::DedupKey)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(self.txn)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(rows > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::UserEvent;
+    use crate::jobs::EmailJob;
+
+    #[test]
+    fn test_message_round_trip() {
+        let event = DomainEvent::from(UserEvent::Deleted {
+            user_id: Uuid::new_v4(),
+        });
+        let messages = [
+            OutboxMessage::Job {
+                job: EmailJob::new("a@example.com", "Hi", "Body").into(),
+                run_at: Some(Utc::now()),
+            },
+            OutboxMessage::Event {
+                event: event.clone(),
+            },
+        ];
+
+        for message in messages {
+            let value = serde_json::to_value(&message).unwrap();
+            let back: OutboxMessage = serde_json::from_value(value).unwrap();
+            match (message, back) {
+                (OutboxMessage::Job { run_at: a, .. }, OutboxMessage::Job { run_at: b, .. }) => {
+                    assert_eq!(a, b)
+                }
+                (OutboxMessage::Event { event: a }, OutboxMessage::Event { event: b }) => {
+                    assert_eq!(a, b)
+                }
+                _ => panic!("message kind changed"),
+            }
+        }
+    }
+}
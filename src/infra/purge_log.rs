@@ -0,0 +1,50 @@
+//! Record of permanently deleted users.
+//!
+//! Purging erases a user's row; the `user_purges` table keeps the id and
+//! when the user was deleted and purged, written in the purge transaction
+//! (see [`TransactionContext::purge_log`]).
+//!
+//! [`TransactionContext::purge_log`]: super::TransactionContext::purge_log
+
+use chrono::{DateTime, Utc};
+use sea_orm::{DatabaseTransaction, EntityTrait, Set};
+use uuid::Uuid;
+
+use super::repositories::entities::user_purge::{ActiveModel, Entity as UserPurgeEntity};
+use crate::domain::User;
+use crate::errors::{AppError, AppResult};
+
+/// Purge record writer bound to a transaction.
+pub struct TxPurgeLog<'a> {
+    txn: &'a DatabaseTransaction,
+}
+
+impl<'a> TxPurgeLog<'a> {
+    pub(crate) fn new(txn: &'a DatabaseTransaction) -> Self {
+        Self { txn }
+    }
+
+    /// Record that soft-deleted `users` were purged at `purged_at`.
+    pub async fn record(&self, users: &[User], purged_at: DateTime<Utc>) -> AppResult<()> {
+        let records: Vec<ActiveModel> = users
+            .iter()
+            .filter_map(|user| {
+                Some(ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    user_id: Set(user.id),
+                    deleted_at: Set(user.deleted_at?),
+                    purged_at: Set(purged_at),
+                })
+            })
+            .collect();
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        UserPurgeEntity::insert_many(records)
+            .exec_without_returning(self.txn)
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
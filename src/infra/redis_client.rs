@@ -0,0 +1,647 @@
+//! Redis topology resolution and connection handling.
+//!
+//! Supports three deployment shapes, selected by URL scheme or `REDIS_MODE`:
+//! - `redis://` / `rediss://` - a single standalone server
+//! - `redis+sentinel://host1:26379,host2:26379/mymaster[/db]` - Sentinel-managed master
+//! - `redis+cluster://host1:6379,host2:6379` - Redis Cluster
+//!
+//! Sentinel and Cluster URLs take `user:pass@` once for all hosts, and the
+//! `rediss+sentinel://` / `rediss+cluster://` schemes enable TLS.
+//!
+//! All shapes are exposed through [`CacheConnection`], which implements
+//! `ConnectionLike` so the regular `AsyncCommands` API works unchanged.
+
+use redis::aio::{ConnectionLike, ConnectionManager, MultiplexedConnection};
+use redis::cluster::ClusterClient;
+use redis::cluster_async::ClusterConnection;
+use redis::sentinel::{Sentinel, SentinelNodeConnectionInfo};
+use redis::{
+    Client, Cmd, ErrorKind, Pipeline, RedisConnectionInfo, RedisError, RedisFuture, RedisResult,
+    TlsMode, Value,
+};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::config::{REDIS_MODE_CLUSTER, REDIS_MODE_SENTINEL, REDIS_MODE_STANDALONE};
+
+/// URL scheme suffix selecting Sentinel discovery (`redis+sentinel`, `rediss+sentinel`)
+const SCHEME_SENTINEL: &str = "+sentinel";
+
+/// URL scheme suffix selecting Redis Cluster (`redis+cluster`, `rediss+cluster`)
+const SCHEME_CLUSTER: &str = "+cluster";
+
+/// Resolved Redis deployment topology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedisTopology {
+    /// Single standalone server
+    Standalone { url: String },
+    /// Master discovered through one or more Sentinels
+    Sentinel {
+        sentinels: Vec<String>,
+        master_name: String,
+        db: i64,
+        username: Option<String>,
+        password: Option<String>,
+        tls: bool,
+    },
+    /// Redis Cluster seeded from one or more nodes
+    Cluster { nodes: Vec<String> },
+}
+
+impl RedisTopology {
+    /// Resolve the topology from a Redis URL and an optional explicit mode.
+    ///
+    /// When `mode` is `None`, the URL scheme decides. An explicit mode lets
+    /// plain comma-separated `redis://` URLs be used for Sentinel or Cluster.
+    pub fn parse(url: &str, mode: Option<&str>) -> RedisResult<Self> {
+        let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+        let mode = match mode {
+            Some(mode) => mode.to_ascii_lowercase(),
+            None if scheme.is_some_and(|s| s.ends_with(SCHEME_SENTINEL)) => {
+                REDIS_MODE_SENTINEL.to_string()
+            }
+            None if scheme.is_some_and(|s| s.ends_with(SCHEME_CLUSTER)) => {
+                REDIS_MODE_CLUSTER.to_string()
+            }
+            None => REDIS_MODE_STANDALONE.to_string(),
+        };
+
+        match mode.as_str() {
+            REDIS_MODE_STANDALONE => Ok(Self::Standalone {
+                url: url.to_string(),
+            }),
+            REDIS_MODE_SENTINEL => Self::parse_sentinel(url),
+            REDIS_MODE_CLUSTER => Self::parse_cluster(url),
+            other => Err(topology_error(format!("Unknown REDIS_MODE: {}", other))),
+        }
+    }
+
+    /// Parse `redis[s]+sentinel://[user:pass@]h1:p1,h2:p2/master[/db]`.
+    ///
+    /// Credentials apply to the master; TLS applies to Sentinels and master.
+    fn parse_sentinel(url: &str) -> RedisResult<Self> {
+        let list = HostList::parse(url);
+        let mut segments = list.path.iter();
+
+        let master_name = segments
+            .next()
+            .ok_or_else(|| topology_error("Sentinel URL must include the master name"))?
+            .to_string();
+        let db = match segments.next() {
+            Some(db) => db
+                .parse()
+                .map_err(|_| topology_error(format!("Invalid Redis database: {}", db)))?,
+            None => 0,
+        };
+
+        let (username, password) = match list.auth {
+            Some(auth) => match auth.split_once(':') {
+                Some((user, pass)) => (non_empty(user), non_empty(pass)),
+                None => (non_empty(auth), None),
+            },
+            None => (None, None),
+        };
+
+        Ok(Self::Sentinel {
+            sentinels: list.hosts.iter().map(|h| list.node_url(h, None)).collect(),
+            master_name,
+            db,
+            username,
+            password,
+            tls: list.tls,
+        })
+    }
+
+    /// Parse `redis[s]+cluster://[user:pass@]h1:p1,h2:p2`.
+    ///
+    /// Credentials and TLS apply to every seed node. Redis Cluster only has
+    /// database 0, so any other `/db` is rejected.
+    fn parse_cluster(url: &str) -> RedisResult<Self> {
+        let list = HostList::parse(url);
+        match list.path.as_slice() {
+            [] | ["0"] => {}
+            path => {
+                return Err(topology_error(format!(
+                    "Redis Cluster only supports database 0, got /{}",
+                    path.join("/")
+                )))
+            }
+        }
+
+        Ok(Self::Cluster {
+            nodes: list
+                .hosts
+                .iter()
+                .map(|h| list.node_url(h, list.auth))
+                .collect(),
+        })
+    }
+}
+
+/// Comma-separated host list shared by Sentinel and Cluster URLs.
+///
+/// Credentials, TLS and path are parsed once for the whole list, wherever
+/// they appear: `rediss+cluster://user:pass@h1,h2` and
+/// `rediss://user:pass@h1,rediss://h2` resolve to the same nodes.
+#[derive(Debug, Default)]
+struct HostList<'a> {
+    hosts: Vec<&'a str>,
+    /// Raw `user:pass`, still percent-encoded
+    auth: Option<&'a str>,
+    tls: bool,
+    path: Vec<&'a str>,
+}
+
+impl<'a> HostList<'a> {
+    fn parse(url: &'a str) -> Self {
+        let mut list = Self::default();
+        for segment in url.split(',').map(str::trim) {
+            let rest = match segment.split_once("://") {
+                Some((scheme, rest)) => {
+                    list.tls |= scheme.split('+').next() == Some("rediss");
+                    rest
+                }
+                None => segment,
+            };
+            let rest = match rest.rsplit_once('@') {
+                Some((auth, rest)) => {
+                    list.auth.get_or_insert(auth);
+                    rest
+                }
+                None => rest,
+            };
+            let host = match rest.split_once('/') {
+                Some((host, path)) => {
+                    list.path = path.split('/').filter(|s| !s.is_empty()).collect();
+                    host
+                }
+                None => rest,
+            };
+            if !host.is_empty() {
+                list.hosts.push(host);
+            }
+        }
+        list
+    }
+
+    /// URL of a single node, with the list's TLS setting and `auth`.
+    fn node_url(&self, host: &str, auth: Option<&str>) -> String {
+        let scheme = if self.tls { "rediss" } else { "redis" };
+        match auth {
+            Some(auth) => format!("{}://{}@{}", scheme, auth, host),
+            None => format!("{}://{}", scheme, host),
+        }
+    }
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+fn topology_error(msg: impl Into<String>) -> RedisError {
+    RedisError::from((
+        ErrorKind::InvalidClientConfig,
+        "Invalid Redis topology",
+        msg.into(),
+    ))
+}
+
+// =============================================================================
+// Connection
+// =============================================================================
+
+/// Connection handle for any supported topology.
+///
+/// Cheap to clone; all clones share the underlying multiplexed connections.
+#[derive(Clone)]
+pub enum CacheConnection {
+    Standalone {
+        manager: ConnectionManager,
+        client: Arc<Client>,
+    },
+    Sentinel(SentinelConnection),
+    Cluster(ClusterConnection),
+}
+
+impl CacheConnection {
+    /// Open a connection for the given topology.
+    pub async fn open(topology: &RedisTopology) -> RedisResult<Self> {
+        match topology {
+            RedisTopology::Standalone { url } => {
+                let client = Client::open(url.as_str())?;
+                let manager = ConnectionManager::new(client.clone()).await?;
+                Ok(Self::Standalone {
+                    manager,
+                    client: Arc::new(client),
+                })
+            }
+            RedisTopology::Sentinel { .. } => {
+                Ok(Self::Sentinel(SentinelConnection::open(topology).await?))
+            }
+            RedisTopology::Cluster { nodes } => {
+                let client = ClusterClient::new(nodes.clone())?;
+                Ok(Self::Cluster(client.get_async_connection().await?))
+            }
+        }
+    }
+
+    /// Open a dedicated (non-shared) connection for blocking commands.
+    ///
+    /// Blocking commands such as `BLPOP` would stall every caller sharing a
+    /// multiplexed connection. Returns `None` for Cluster, where callers
+    /// should fall back to polling.
+    pub async fn dedicated(&self) -> RedisResult<Option<MultiplexedConnection>> {
+        let client = match self {
+            Self::Standalone { client, .. } => Client::clone(client),
+            Self::Sentinel(conn) => conn.client.read().await.clone(),
+            Self::Cluster(_) => return Ok(None),
+        };
+        client.get_multiplexed_tokio_connection().await.map(Some)
+    }
+}
+
+impl ConnectionLike for CacheConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Self::Standalone { manager, .. } => manager.req_packed_command(cmd),
+            Self::Sentinel(conn) => conn.req_packed_command(cmd),
+            Self::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Self::Standalone { manager, .. } => manager.req_packed_commands(cmd, offset, count),
+            Self::Sentinel(conn) => conn.req_packed_commands(cmd, offset, count),
+            Self::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Standalone { manager, .. } => manager.get_db(),
+            Self::Sentinel(conn) => conn.db,
+            Self::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+/// Connection to a Sentinel-managed master that follows failovers.
+///
+/// When the current master stops accepting writes (`READONLY`) or the
+/// connection drops, the master address is re-resolved through Sentinel
+/// and subsequent commands go to the new master.
+#[derive(Clone)]
+pub struct SentinelConnection {
+    sentinel: Arc<Mutex<Sentinel>>,
+    master_name: String,
+    node_info: SentinelNodeConnectionInfo,
+    db: i64,
+    client: Arc<RwLock<Client>>,
+    manager: Arc<RwLock<ConnectionManager>>,
+}
+
+impl SentinelConnection {
+    async fn open(topology: &RedisTopology) -> RedisResult<Self> {
+        let RedisTopology::Sentinel {
+            sentinels,
+            master_name,
+            db,
+            username,
+            password,
+            tls,
+        } = topology
+        else {
+            return Err(topology_error("Expected a Sentinel topology"));
+        };
+
+        let node_info = SentinelNodeConnectionInfo {
+            tls_mode: tls.then_some(TlsMode::Secure),
+            redis_connection_info: Some(RedisConnectionInfo {
+                db: *db,
+                username: username.clone(),
+                password: password.clone(),
+                ..Default::default()
+            }),
+        };
+
+        let mut sentinel = Sentinel::build(sentinels.clone())?;
+        let client = sentinel
+            .async_master_for(master_name, Some(&node_info))
+            .await?;
+        let manager = ConnectionManager::new(client.clone()).await?;
+
+        tracing::info!(master = %master_name, "Resolved Redis master via Sentinel");
+
+        Ok(Self {
+            sentinel: Arc::new(Mutex::new(sentinel)),
+            master_name: master_name.clone(),
+            node_info,
+            db: *db,
+            client: Arc::new(RwLock::new(client)),
+            manager: Arc::new(RwLock::new(manager)),
+        })
+    }
+
+    /// Re-resolve the master and swap the shared connection.
+    async fn failover(&self) {
+        let resolved = {
+            let mut sentinel = self.sentinel.lock().await;
+            sentinel
+                .async_master_for(&self.master_name, Some(&self.node_info))
+                .await
+        };
+
+        let client = match resolved {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!(master = %self.master_name, error = %e, "Sentinel master lookup failed");
+                return;
+            }
+        };
+
+        match ConnectionManager::new(client.clone()).await {
+            Ok(manager) => {
+                *self.manager.write().await = manager;
+                *self.client.write().await = client;
+                tracing::warn!(master = %self.master_name, "Switched to new Redis master");
+            }
+            Err(e) => {
+                tracing::error!(master = %self.master_name, error = %e, "Failed to connect to new master");
+            }
+        }
+    }
+
+    fn needs_failover(err: &RedisError) -> bool {
+        err.kind() == ErrorKind::ReadOnly || err.is_io_error() || err.is_connection_dropped()
+    }
+}
+
+impl ConnectionLike for SentinelConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let mut conn = self.manager.read().await.clone();
+            let result = conn.req_packed_command(cmd).await;
+            if let Err(e) = &result {
+                if Self::needs_failover(e) {
+                    self.failover().await;
+                }
+            }
+            result
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move {
+            let mut conn = self.manager.read().await.clone();
+            let result = conn.req_packed_commands(cmd, offset, count).await;
+            if let Err(e) = &result {
+                if Self::needs_failover(e) {
+                    self.failover().await;
+                }
+            }
+            result
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        self.db
+    }
+}
+
+// =============================================================================
+// Blocking Connections
+// =============================================================================
+
+/// Bounded pool of dedicated connections for blocking commands.
+///
+/// A blocked `BLPOP` holds its connection until it returns, so callers
+/// cannot share the multiplexed connection; they borrow one from here
+/// instead of each opening their own.
+pub struct BlockingPool {
+    connection: CacheConnection,
+    idle: std::sync::Mutex<Vec<MultiplexedConnection>>,
+    /// Connections open, idle or borrowed
+    open: AtomicUsize,
+    max_size: usize,
+}
+
+impl BlockingPool {
+    /// Create an empty pool opening at most `max_size` connections.
+    pub fn new(connection: CacheConnection, max_size: usize) -> Self {
+        Self {
+            connection,
+            idle: std::sync::Mutex::new(Vec::new()),
+            open: AtomicUsize::new(0),
+            max_size,
+        }
+    }
+
+    /// Borrow a connection, opening one if none is idle.
+    ///
+    /// Returns `None` when all connections are borrowed or the topology
+    /// has no blocking support (Cluster); callers should poll instead.
+    pub async fn get(self: &Arc<Self>) -> RedisResult<Option<PooledConnection>> {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        if let Some(conn) = idle {
+            return Ok(Some(self.lend(conn)));
+        }
+
+        // Reserve the slot before connecting so concurrent callers respect the cap
+        let reserved = self
+            .open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < self.max_size).then_some(open + 1)
+            });
+        if reserved.is_err() {
+            return Ok(None);
+        }
+        match self.connection.dedicated().await {
+            Ok(Some(conn)) => Ok(Some(self.lend(conn))),
+            result => {
+                self.open.fetch_sub(1, Ordering::SeqCst);
+                result.map(|_| None)
+            }
+        }
+    }
+
+    fn lend(self: &Arc<Self>, conn: MultiplexedConnection) -> PooledConnection {
+        PooledConnection {
+            pool: self.clone(),
+            conn: Some(conn),
+        }
+    }
+}
+
+/// Connection borrowed from a [`BlockingPool`], returned when dropped.
+pub struct PooledConnection {
+    pool: Arc<BlockingPool>,
+    conn: Option<MultiplexedConnection>,
+}
+
+impl PooledConnection {
+    /// Close the connection instead of returning it, e.g. after an error.
+    pub fn discard(mut self) {
+        self.conn = None;
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = MultiplexedConnection;
+
+    fn deref(&self) -> &MultiplexedConnection {
+        self.conn
+            .as_ref()
+            .expect("pooled connection already released")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut MultiplexedConnection {
+        self.conn
+            .as_mut()
+            .expect("pooled connection already released")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        match self.conn.take() {
+            Some(conn) => self
+                .pool
+                .idle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(conn),
+            None => {
+                self.pool.open.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_standalone() {
+        let topology = RedisTopology::parse("redis://127.0.0.1:6379", None).unwrap();
+        assert_eq!(
+            topology,
+            RedisTopology::Standalone {
+                url: "redis://127.0.0.1:6379".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_sentinel_url() {
+        let topology = RedisTopology::parse(
+            "redis+sentinel://:secret@s1:26379,s2:26379/mymaster/2",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            topology,
+            RedisTopology::Sentinel {
+                sentinels: vec![
+                    "redis://s1:26379".to_string(),
+                    "redis://s2:26379".to_string()
+                ],
+                master_name: "mymaster".to_string(),
+                db: 2,
+                username: None,
+                password: Some("secret".to_string()),
+                tls: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_sentinel_tls_with_auth() {
+        let topology = RedisTopology::parse(
+            "rediss+sentinel://app:secret@s1:26379,s2:26379/mymaster",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            topology,
+            RedisTopology::Sentinel {
+                sentinels: vec![
+                    "rediss://s1:26379".to_string(),
+                    "rediss://s2:26379".to_string()
+                ],
+                master_name: "mymaster".to_string(),
+                db: 0,
+                username: Some("app".to_string()),
+                password: Some("secret".to_string()),
+                tls: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_sentinel_requires_master() {
+        assert!(RedisTopology::parse("redis+sentinel://s1:26379", None).is_err());
+    }
+
+    #[test]
+    fn test_parse_cluster_by_mode() {
+        let topology =
+            RedisTopology::parse("redis://n1:6379,redis://n2:6379", Some("cluster")).unwrap();
+        assert_eq!(
+            topology,
+            RedisTopology::Cluster {
+                nodes: vec!["redis://n1:6379".to_string(), "redis://n2:6379".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cluster_applies_auth_to_every_node() {
+        let topology =
+            RedisTopology::parse("redis+cluster://user:pass@n1:6379,n2:6379", None).unwrap();
+        assert_eq!(
+            topology,
+            RedisTopology::Cluster {
+                nodes: vec![
+                    "redis://user:pass@n1:6379".to_string(),
+                    "redis://user:pass@n2:6379".to_string()
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cluster_tls() {
+        let topology =
+            RedisTopology::parse("rediss+cluster://:pass@n1:6379,n2:6379/0", None).unwrap();
+        assert_eq!(
+            topology,
+            RedisTopology::Cluster {
+                nodes: vec![
+                    "rediss://:pass@n1:6379".to_string(),
+                    "rediss://:pass@n2:6379".to_string()
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cluster_rejects_database() {
+        assert!(RedisTopology::parse("redis+cluster://n1:6379,n2:6379/3", None).is_err());
+    }
+}
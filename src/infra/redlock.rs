@@ -0,0 +1,246 @@
+//! Redlock - distributed locks across independent Redis nodes.
+//!
+//! A lock is held only when a quorum (N/2 + 1) of the nodes accepted it
+//! and the time spent acquiring it left some validity on the TTL.
+//! This removes the single point of failure of a lock stored on one server.
+//!
+//! See <https://redis.io/docs/latest/develop/use/patterns/distributed-locks/>.
+
+use futures::future::join_all;
+use redis::{aio::ConnectionManager, Client, ErrorKind, RedisError, RedisResult};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::OnceCell;
+use tokio::time::{timeout, Duration};
+
+use super::cache::{EXTEND_LOCK_SCRIPT, RELEASE_LOCK_SCRIPT};
+use crate::config::{REDLOCK_CLOCK_DRIFT_FACTOR, REDLOCK_NODE_TIMEOUT_MS};
+
+/// Lock manager spanning several independent Redis masters.
+#[derive(Clone)]
+pub struct Redlock {
+    nodes: Vec<Node>,
+}
+
+/// One lock node, connected on first use.
+#[derive(Clone)]
+struct Node {
+    client: Client,
+    conn: Arc<OnceCell<ConnectionManager>>,
+}
+
+impl Node {
+    /// Shared connection, retrying the connect until it first succeeds.
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        self.conn
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+}
+
+impl Redlock {
+    /// Connect to the nodes, requiring only a quorum to be reachable.
+    ///
+    /// Unreachable nodes are retried on each lock operation and vote "no"
+    /// until they come back.
+    pub async fn connect(urls: &[String]) -> RedisResult<Self> {
+        let nodes = urls
+            .iter()
+            .map(|url| {
+                Ok(Node {
+                    client: Client::open(url.as_str())?,
+                    conn: Arc::new(OnceCell::new()),
+                })
+            })
+            .collect::<RedisResult<Vec<_>>>()?;
+
+        let reachable = join_all(
+            nodes
+                .iter()
+                .map(|node| async { node_call(node.connection()).await.is_some() }),
+        )
+        .await
+        .into_iter()
+        .filter(|ok| *ok)
+        .count();
+
+        let quorum = quorum(nodes.len());
+        if reachable < quorum {
+            return Err(RedisError::from((
+                ErrorKind::IoError,
+                "Redlock quorum unreachable",
+                format!(
+                    "{} of {} lock nodes reachable, {} required",
+                    reachable,
+                    nodes.len(),
+                    quorum
+                ),
+            )));
+        }
+
+        tracing::info!(nodes = nodes.len(), reachable, quorum, "Redlock enabled");
+
+        Ok(Self { nodes })
+    }
+
+    /// Number of nodes participating in the lock.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Minimum number of nodes that must agree.
+    pub fn quorum(&self) -> usize {
+        quorum(self.nodes.len())
+    }
+
+    /// Try to acquire the lock on a quorum of nodes.
+    ///
+    /// Returns the remaining validity when acquired. On failure, any partial
+    /// acquisitions are rolled back so other clients are not blocked.
+    pub async fn try_acquire(&self, key: &str, lock_id: &str, ttl_ms: u64) -> Option<Duration> {
+        let start = Instant::now();
+
+        let results = join_all(self.nodes.iter().map(|node| async move {
+            let cmd = redis::cmd("SET")
+                .arg(key)
+                .arg(lock_id)
+                .arg("NX")
+                .arg("PX")
+                .arg(ttl_ms)
+                .to_owned();
+            node_query::<Option<String>>(node, &cmd)
+                .await
+                .flatten()
+                .is_some()
+        }))
+        .await;
+
+        let acquired = results.into_iter().filter(|ok| *ok).count();
+        let validity = validity(ttl_ms, start.elapsed());
+
+        match validity {
+            Some(validity) if acquired >= self.quorum() => Some(validity),
+            _ => {
+                tracing::debug!(key = %key, acquired, quorum = self.quorum(), "Redlock quorum not reached");
+                self.release(key, lock_id).await;
+                None
+            }
+        }
+    }
+
+    /// Release the lock on every node. Returns how many nodes held it.
+    pub async fn release(&self, key: &str, lock_id: &str) -> usize {
+        self.eval_all(RELEASE_LOCK_SCRIPT, key, lock_id, None).await
+    }
+
+    /// Extend the lock TTL. Succeeds only if a quorum still holds the lock.
+    pub async fn extend(&self, key: &str, lock_id: &str, ttl_seconds: u64) -> bool {
+        let extended = self
+            .eval_all(EXTEND_LOCK_SCRIPT, key, lock_id, Some(ttl_seconds))
+            .await;
+        extended >= self.quorum()
+    }
+
+    /// Check whether a quorum of nodes currently hold any lock for the key.
+    pub async fn is_locked(&self, key: &str) -> bool {
+        let results = join_all(self.nodes.iter().map(|node| async move {
+            let cmd = redis::cmd("EXISTS").arg(key).to_owned();
+            node_query::<bool>(node, &cmd).await.unwrap_or(false)
+        }))
+        .await;
+
+        results.into_iter().filter(|held| *held).count() >= self.quorum()
+    }
+
+    /// Run an ownership-checked script on every node, counting successes.
+    async fn eval_all(&self, script: &str, key: &str, lock_id: &str, ttl: Option<u64>) -> usize {
+        let results = join_all(self.nodes.iter().map(|node| async move {
+            let mut cmd = redis::cmd("EVAL");
+            cmd.arg(script).arg(1).arg(key).arg(lock_id);
+            if let Some(ttl) = ttl {
+                cmd.arg(ttl);
+            }
+            node_query::<i32>(node, &cmd).await.unwrap_or(0) == 1
+        }))
+        .await;
+
+        results.into_iter().filter(|ok| *ok).count()
+    }
+}
+
+/// Run a command on one node, connecting first if needed.
+async fn node_query<T: redis::FromRedisValue>(node: &Node, cmd: &redis::Cmd) -> Option<T> {
+    node_call(async {
+        let mut conn = node.connection().await?;
+        cmd.query_async::<T>(&mut conn).await
+    })
+    .await
+}
+
+/// Run a single node command with the per-node timeout.
+/// Unreachable or slow nodes count as a "no" vote instead of stalling the lock.
+async fn node_call<T>(fut: impl std::future::Future<Output = RedisResult<T>>) -> Option<T> {
+    match timeout(Duration::from_millis(REDLOCK_NODE_TIMEOUT_MS), fut).await {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "Redlock node error");
+            None
+        }
+        Err(_) => {
+            tracing::warn!("Redlock node timed out");
+            None
+        }
+    }
+}
+
+/// Majority of `n` nodes.
+fn quorum(n: usize) -> usize {
+    n / 2 + 1
+}
+
+/// Remaining lock validity after acquisition, accounting for clock drift.
+fn validity(ttl_ms: u64, elapsed: Duration) -> Option<Duration> {
+    let drift_ms = (ttl_ms as f64 * REDLOCK_CLOCK_DRIFT_FACTOR) as u64 + 2;
+    let remaining = ttl_ms
+        .checked_sub(elapsed.as_millis() as u64)?
+        .checked_sub(drift_ms)?;
+    (remaining > 0).then(|| Duration::from_millis(remaining))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quorum() {
+        assert_eq!(quorum(1), 1);
+        assert_eq!(quorum(3), 2);
+        assert_eq!(quorum(5), 3);
+        assert_eq!(quorum(4), 3);
+    }
+
+    #[tokio::test]
+    async fn test_connect_requires_reachable_quorum() {
+        let urls = vec![
+            "redis://127.0.0.1:1".to_string(),
+            "redis://127.0.0.1:2".to_string(),
+            "redis://127.0.0.1:3".to_string(),
+        ];
+        let err = Redlock::connect(&urls).await.err().unwrap();
+        assert!(err.to_string().contains("0 of 3 lock nodes reachable"));
+    }
+
+    #[test]
+    fn test_validity_accounts_for_drift() {
+        // 10s TTL: drift = 100ms + 2ms
+        let v = validity(10_000, Duration::from_millis(500)).unwrap();
+        assert_eq!(v, Duration::from_millis(10_000 - 500 - 102));
+    }
+
+    #[test]
+    fn test_validity_expired() {
+        assert!(validity(100, Duration::from_millis(200)).is_none());
+        assert!(validity(100, Duration::from_millis(97)).is_none());
+    }
+}
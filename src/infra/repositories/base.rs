@@ -5,23 +5,68 @@
 
 use async_trait::async_trait;
 use sea_orm::{
-    ActiveModelBehavior, ActiveModelTrait, DatabaseConnection, EntityTrait, FromQueryResult,
-    IntoActiveModel, PaginatorTrait, PrimaryKeyTrait,
+    ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, DatabaseConnection,
+    DatabaseTransaction, EntityTrait, FromQueryResult, IntoActiveModel, PaginatorTrait,
+    PrimaryKeyTrait,
 };
 use std::fmt::Debug;
 
 use crate::errors::AppResult;
 use crate::types::PaginationParams;
 
+/// Connection a repository runs its queries on.
+///
+/// Implemented for the pooled [`DatabaseConnection`] and for borrowed
+/// connections such as `&DatabaseTransaction`, so one repository
+/// implementation serves both plain calls and a unit of work.
+pub trait ConnectionRef: Send + Sync {
+    type Connection: ConnectionTrait;
+
+    fn connection(&self) -> &Self::Connection;
+}
+
+impl ConnectionRef for DatabaseConnection {
+    type Connection = Self;
+
+    fn connection(&self) -> &Self {
+        self
+    }
+}
+
+impl ConnectionRef for DatabaseTransaction {
+    type Connection = Self;
+
+    fn connection(&self) -> &Self {
+        self
+    }
+}
+
+impl<C: ConnectionRef> ConnectionRef for &C {
+    type Connection = C::Connection;
+
+    fn connection(&self) -> &C::Connection {
+        (**self).connection()
+    }
+}
+
 /// Read operations (Query) - Single Responsibility
+///
+/// Generic over the [`ConnectionRef`] so the same repository can run on
+/// the pool or inside a transaction.
 #[async_trait]
-pub trait ReadRepository<E, M>: Send + Sync
+pub trait ReadRepository<E, M, C = DatabaseConnection>: Send + Sync
 where
     E: EntityTrait<Model = M>,
     M: Send + Sync + FromQueryResult,
+    C: ConnectionRef,
 {
     /// Get database connection reference
-    fn db(&self) -> &DatabaseConnection;
+    fn db(&self) -> &C::Connection;
+
+    /// Connection used for reads; override to route them to a replica
+    fn read_db(&self) -> &C::Connection {
+        self.db()
+    }
 
     /// Find entity by primary key
     async fn find_by_id(&self, id: <E::PrimaryKey as PrimaryKeyTrait>::ValueType) -> AppResult<Option<M>>
@@ -29,7 +74,7 @@ where
         <E::PrimaryKey as PrimaryKeyTrait>::ValueType: Clone + Send,
     {
         E::find_by_id(id)
-            .one(self.db())
+            .one(self.read_db())
             .await
             .map_err(Into::into)
     }
@@ -37,23 +82,23 @@ where
     /// Find all entities
     async fn find_all(&self) -> AppResult<Vec<M>> {
         E::find()
-            .all(self.db())
+            .all(self.read_db())
             .await
             .map_err(Into::into)
     }
 
     /// Find entities with pagination
     async fn find_paginated(&self, params: &PaginationParams) -> AppResult<(Vec<M>, u64)> {
-        let paginator = E::find().paginate(self.db(), params.limit());
+        let paginator = E::find().paginate(self.read_db(), params.limit());
         let total = paginator.num_items().await?;
-        let data = paginator.fetch_page(params.page.saturating_sub(1)).await?;
+        let data = paginator.fetch_page(params.page() - 1).await?;
         Ok((data, total))
     }
 
     /// Count all entities
     async fn count(&self) -> AppResult<u64> {
         E::find()
-            .paginate(self.db(), 1)
+            .paginate(self.read_db(), 1)
             .num_items()
             .await
             .map_err(Into::into)
@@ -62,14 +107,15 @@ where
 
 /// Write operations (Command) - Single Responsibility
 #[async_trait]
-pub trait WriteRepository<E, M, A>: Send + Sync
+pub trait WriteRepository<E, M, A, C = DatabaseConnection>: Send + Sync
 where
     E: EntityTrait<Model = M>,
     M: Send + Sync + IntoActiveModel<A>,
     A: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send + 'static,
+    C: ConnectionRef,
 {
     /// Get database connection reference
-    fn db(&self) -> &DatabaseConnection;
+    fn db(&self) -> &C::Connection;
 
     /// Insert new entity
     async fn insert(&self, model: A) -> AppResult<M>
@@ -96,12 +142,13 @@ where
 
 /// Delete operations - Single Responsibility
 #[async_trait]
-pub trait DeleteRepository<E>: Send + Sync
+pub trait DeleteRepository<E, C = DatabaseConnection>: Send + Sync
 where
     E: EntityTrait,
+    C: ConnectionRef,
 {
     /// Get database connection reference
-    fn db(&self) -> &DatabaseConnection;
+    fn db(&self) -> &C::Connection;
 
     /// Delete entity by primary key
     async fn delete_by_id(&self, id: <E::PrimaryKey as PrimaryKeyTrait>::ValueType) -> AppResult<()>
@@ -117,21 +164,92 @@ where
 
 /// Full CRUD repository - Combines all operations
 /// Follows Open/Closed Principle: extend by implementing individual traits
-pub trait CrudRepository<E, M, A>:
-    ReadRepository<E, M> + WriteRepository<E, M, A> + DeleteRepository<E>
+pub trait CrudRepository<E, M, A, C = DatabaseConnection>:
+    ReadRepository<E, M, C> + WriteRepository<E, M, A, C> + DeleteRepository<E, C>
 where
     E: EntityTrait<Model = M>,
     M: Send + Sync + FromQueryResult + IntoActiveModel<A>,
     A: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send + 'static,
+    C: ConnectionRef,
 {
 }
 
 // Auto-implement CrudRepository for types implementing all traits
-impl<T, E, M, A> CrudRepository<E, M, A> for T
+impl<T, E, M, A, C> CrudRepository<E, M, A, C> for T
 where
-    T: ReadRepository<E, M> + WriteRepository<E, M, A> + DeleteRepository<E>,
+    T: ReadRepository<E, M, C> + WriteRepository<E, M, A, C> + DeleteRepository<E, C>,
     E: EntityTrait<Model = M>,
     M: Send + Sync + FromQueryResult + IntoActiveModel<A>,
     A: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send + 'static,
+    C: ConnectionRef,
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{Set, TransactionTrait};
+    use sea_orm_migration::MigratorTrait;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::infra::repositories::entities::user;
+    use crate::infra::Migrator;
+
+    struct Users<C>(C);
+
+    impl<C: ConnectionRef> ReadRepository<user::Entity, user::Model, C> for Users<C> {
+        fn db(&self) -> &C::Connection {
+            self.0.connection()
+        }
+    }
+
+    impl<C: ConnectionRef> WriteRepository<user::Entity, user::Model, user::ActiveModel, C>
+        for Users<C>
+    {
+        fn db(&self) -> &C::Connection {
+            self.0.connection()
+        }
+    }
+
+    impl<C: ConnectionRef> DeleteRepository<user::Entity, C> for Users<C> {
+        fn db(&self) -> &C::Connection {
+            self.0.connection()
+        }
+    }
+
+    fn assert_crud<C: ConnectionRef>(
+        _: &impl CrudRepository<user::Entity, user::Model, user::ActiveModel, C>,
+    ) {
+    }
+
+    #[tokio::test]
+    async fn test_crud_repository_runs_on_a_transaction() {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let txn = db.begin().await.unwrap();
+        let users = Users(&txn);
+        assert_crud(&users);
+        let now = chrono::Utc::now();
+        let created = users
+            .insert(user::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                email: Set("tx@example.com".to_string()),
+                password_hash: Set("hash".to_string()),
+                name: Set("Tx".to_string()),
+                role: Set("user".to_string()),
+                created_at: Set(now),
+                updated_at: Set(now),
+                deleted_at: Set(None),
+                version: Set(1),
+            })
+            .await
+            .unwrap();
+        assert_eq!(users.count().await.unwrap(), 1);
+        assert!(users.find_by_id(created.id).await.unwrap().is_some());
+        txn.rollback().await.unwrap();
+
+        // Rolled back, so the pool never sees it
+        assert_eq!(Users(db).count().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,24 @@
+//! Cron schedule state entity for SeaORM.
+
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "cron_schedules")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    /// Occurrence handled by the latest run (or skipped up to)
+    pub last_scheduled_at: Option<DateTimeUtc>,
+    pub last_started_at: Option<DateTimeUtc>,
+    pub last_finished_at: Option<DateTimeUtc>,
+    /// `succeeded`, `failed` or `skipped`
+    pub last_status: Option<String>,
+    pub last_error: Option<String>,
+    /// Lease held by the worker currently running the job
+    pub locked_until: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
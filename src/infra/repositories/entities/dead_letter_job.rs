@@ -0,0 +1,27 @@
+//! Dead-letter job entity for SeaORM.
+
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "dead_letter_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Id the job had in the queue
+    #[sea_orm(unique)]
+    pub job_id: String,
+    pub job_type: String,
+    /// Job arguments as stored in the queue
+    pub payload: Json,
+    pub attempts: i32,
+    pub error_kind: Option<String>,
+    pub last_error: Option<String>,
+    /// Failed attempts, oldest first: `[{attempt, kind, error, failed_at}]`
+    pub history: Json,
+    pub failed_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,26 @@
+//! Failed job attempt entity for SeaORM.
+
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "job_attempts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// apalis job id
+    pub job_id: String,
+    pub job_type: String,
+    /// 1-based attempt number
+    pub attempt: i32,
+    /// `FailureKind` of the error
+    pub error_kind: String,
+    pub error: String,
+    /// When the job is retried; `None` if it goes to the dead-letter table
+    pub retry_at: Option<DateTimeUtc>,
+    pub failed_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,7 +2,12 @@
 //!
 //! These are database-specific entities separate from domain models.
 
+pub mod cron_schedule;
+pub mod dead_letter_job;
+pub mod job_attempt;
+pub mod outbox;
 pub mod user;
+pub mod user_purge;
 
 // Re-exports for public API convenience
 #[allow(unused_imports)]
@@ -0,0 +1,27 @@
+//! Outbox database entity for SeaORM.
+
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    /// Unique key; a second entry with the same key is dropped
+    #[sea_orm(unique)]
+    pub dedup_key: String,
+    /// Serialized `OutboxMessage`
+    pub payload: Json,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// Earliest time the relay may (re)try the entry
+    pub available_at: DateTimeUtc,
+    pub created_at: DateTimeUtc,
+    /// Set once the entry was handed to the job queue or event bus
+    pub dispatched_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
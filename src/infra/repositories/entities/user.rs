@@ -18,6 +18,8 @@ pub struct Model {
     pub updated_at: DateTimeUtc,
     /// Soft delete timestamp (NULL = active, set = deleted)
     pub deleted_at: Option<DateTimeUtc>,
+    /// Incremented on every write (optimistic concurrency)
+    pub version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -37,6 +39,7 @@ impl From<Model> for User {
             created_at: model.created_at,
             updated_at: model.updated_at,
             deleted_at: model.deleted_at,
+            version: model.version,
         }
     }
 }
@@ -0,0 +1,20 @@
+//! User purge record entity for SeaORM.
+
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_purges")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// The removed user; no longer references a row
+    pub user_id: Uuid,
+    /// When the user was soft-deleted
+    pub deleted_at: DateTimeUtc,
+    pub purged_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
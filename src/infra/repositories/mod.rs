@@ -7,7 +7,7 @@ mod base;
 pub(crate) mod entities;
 mod user_repository;
 
-pub use base::{CrudRepository, DeleteRepository, ReadRepository, WriteRepository};
+pub use base::{ConnectionRef, CrudRepository, DeleteRepository, ReadRepository, WriteRepository};
 pub use user_repository::{UserRepository, UserStore};
 
 // Export mock for tests (both unit and integration)
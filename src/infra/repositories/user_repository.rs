@@ -1,12 +1,19 @@
 //! User repository implementation with soft delete support.
 
 use async_trait::async_trait;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Condition, Expr, Func, SimpleExpr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection,
+    EntityTrait, FromQueryResult, IdenStatic, Iterable, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait, Set, Statement,
+};
 use uuid::Uuid;
 
 use super::entities::user::{self, ActiveModel, Entity as UserEntity};
+use super::ConnectionRef;
 use crate::config::ROLE_USER;
-use crate::domain::User;
+use crate::domain::{User, UserSearch, UserSearchHit};
 use crate::errors::{AppError, AppResult};
 
 #[cfg(any(test, feature = "test-utils"))]
@@ -25,17 +32,26 @@ pub trait UserRepository: Send + Sync {
     /// Find user by ID including soft-deleted
     async fn find_by_id_with_deleted(&self, id: Uuid) -> AppResult<Option<User>>;
 
-    /// Find active user by email address (excludes soft-deleted)
+    /// Find active user by email address, ignoring case (excludes soft-deleted)
     async fn find_by_email(&self, email: &str) -> AppResult<Option<User>>;
 
-    /// Find user by email including soft-deleted
+    /// Find user by email, ignoring case, including soft-deleted
     async fn find_by_email_with_deleted(&self, email: &str) -> AppResult<Option<User>>;
 
     /// Create a new user
     async fn create(&self, email: String, password_hash: String, name: String) -> AppResult<User>;
 
-    /// Update user fields
-    async fn update(&self, id: Uuid, name: Option<String>, role: Option<String>) -> AppResult<User>;
+    /// Update user fields and increment the version.
+    ///
+    /// With `expected_version`, fails with `PreconditionFailed` when the
+    /// stored version differs.
+    async fn update(
+        &self,
+        id: Uuid,
+        name: Option<String>,
+        role: Option<String>,
+        expected_version: Option<i32>,
+    ) -> AppResult<User>;
 
     /// Soft delete user by ID (sets deleted_at timestamp)
     async fn delete(&self, id: Uuid) -> AppResult<()>;
@@ -54,26 +70,113 @@ pub trait UserRepository: Send + Sync {
 
     /// List only soft-deleted users
     async fn list_deleted(&self) -> AppResult<Vec<User>>;
+
+    /// Number of active users
+    async fn count(&self) -> AppResult<u64>;
+
+    /// Number of soft-deleted users
+    async fn count_deleted(&self) -> AppResult<u64>;
+
+    /// Up to `limit` users soft-deleted before `before`, oldest first
+    async fn list_deleted_before(&self, before: DateTime<Utc>, limit: u64) -> AppResult<Vec<User>>;
+
+    /// Number of users soft-deleted before `before`
+    async fn count_deleted_before(&self, before: DateTime<Utc>) -> AppResult<u64>;
+
+    /// Permanently delete those of `ids` still soft-deleted before `before`.
+    ///
+    /// Users restored (or deleted again) since they were listed are left
+    /// alone. Returns the deleted users.
+    async fn purge_deleted(&self, ids: &[Uuid], before: DateTime<Utc>) -> AppResult<Vec<User>>;
+
+    /// Active users matching `search`, best first, and the number of matches
+    async fn search(&self, search: &UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)>;
 }
 
-/// Concrete implementation of UserRepository with soft delete
-pub struct UserStore {
-    db: DatabaseConnection,
+/// Full-text prefix match or trigram word similarity ($1 text, $2 tsquery)
+const SEARCH_MATCHES: &str = "deleted_at IS NULL \
+     AND (search_vector @@ to_tsquery('simple', $2) OR $1 <% (name || ' ' || email))";
+
+/// Columns of [`user::Model`], for raw queries
+fn user_columns() -> String {
+    user::Column::iter()
+        .map(|column| column.as_str().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Ranked PostgreSQL search for a page of `search`, and the match count
+fn ranked_search_statements(search: &UserSearch) -> (Statement, Statement) {
+    let query = &search.query;
+
+    // Name terms weigh more than email terms (see the search migration)
+    let page = Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        format!(
+            "SELECT {}, (ts_rank(search_vector, to_tsquery('simple', $2)) \
+             + word_similarity($1, name || ' ' || email))::float8 AS rank \
+             FROM users WHERE {} ORDER BY rank DESC, id LIMIT $3 OFFSET $4",
+            user_columns(),
+            SEARCH_MATCHES
+        ),
+        [
+            query.text().into(),
+            query.tsquery().into(),
+            (search.limit as i64).into(),
+            (search.offset as i64).into(),
+        ],
+    );
+    let count = Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        format!(
+            "SELECT COUNT(*)::bigint AS count FROM users WHERE {}",
+            SEARCH_MATCHES
+        ),
+        [query.text().into(), query.tsquery().into()],
+    );
+    (page, count)
 }
 
-impl UserStore {
+/// `lower(email) = lower(?)`, served by the unique `lower(email)` index
+fn email_matches(email: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(user::Column::Email))).eq(Func::lower(Expr::val(email)))
+}
+
+/// Concrete implementation of UserRepository with soft delete.
+///
+/// Runs on any [`ConnectionRef`]: the pool by default, or a transaction
+/// (see [`TransactionContext::users`](crate::infra::TransactionContext::users)).
+/// Lists, counts and search read from `reader`, which may be a replica.
+/// Writes and single-user lookups use the primary, since those lookups back
+/// writes: duplicate checks, ETag versions, and logins right after
+/// registration.
+pub struct UserStore<C = DatabaseConnection> {
+    db: C,
+    reader: C,
+}
+
+impl<C: ConnectionRef + Clone> UserStore<C> {
     /// Create new repository instance
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(db: C) -> Self {
+        Self {
+            reader: db.clone(),
+            db,
+        }
+    }
+
+    /// Read from a replica instead of the primary
+    pub fn with_replica(mut self, reader: C) -> Self {
+        self.reader = reader;
+        self
     }
 }
 
 #[async_trait]
-impl UserRepository for UserStore {
+impl<C: ConnectionRef> UserRepository for UserStore<C> {
     async fn find_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
         let result = UserEntity::find_by_id(id)
             .filter(user::Column::DeletedAt.is_null())
-            .one(&self.db)
+            .one(self.db())
             .await
             .map_err(AppError::from)?;
 
@@ -82,7 +185,7 @@ impl UserRepository for UserStore {
 
     async fn find_by_id_with_deleted(&self, id: Uuid) -> AppResult<Option<User>> {
         let result = UserEntity::find_by_id(id)
-            .one(&self.db)
+            .one(self.db())
             .await
             .map_err(AppError::from)?;
 
@@ -91,9 +194,9 @@ impl UserRepository for UserStore {
 
     async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
         let result = UserEntity::find()
-            .filter(user::Column::Email.eq(email))
+            .filter(email_matches(email))
             .filter(user::Column::DeletedAt.is_null())
-            .one(&self.db)
+            .one(self.db())
             .await
             .map_err(AppError::from)?;
 
@@ -102,8 +205,8 @@ impl UserRepository for UserStore {
 
     async fn find_by_email_with_deleted(&self, email: &str) -> AppResult<Option<User>> {
         let result = UserEntity::find()
-            .filter(user::Column::Email.eq(email))
-            .one(&self.db)
+            .filter(email_matches(email))
+            .one(self.db())
             .await
             .map_err(AppError::from)?;
 
@@ -121,54 +224,84 @@ impl UserRepository for UserStore {
             created_at: Set(now),
             updated_at: Set(now),
             deleted_at: Set(None),
+            version: Set(1),
         };
 
-        let model = active_model.insert(&self.db).await.map_err(AppError::from)?;
+        let model = active_model
+            .insert(self.db())
+            .await
+            .map_err(AppError::from)?;
         Ok(User::from(model))
     }
 
-    async fn update(&self, id: Uuid, name: Option<String>, role: Option<String>) -> AppResult<User> {
-        // Only allow updating active (non-deleted) users
-        let user = UserEntity::find_by_id(id)
-            .filter(user::Column::DeletedAt.is_null())
-            .one(&self.db)
-            .await?
-            .ok_or(AppError::NotFound)?;
-
-        let mut active: ActiveModel = user.into();
-
+    async fn update(
+        &self,
+        id: Uuid,
+        name: Option<String>,
+        role: Option<String>,
+        expected_version: Option<i32>,
+    ) -> AppResult<User> {
+        // Compare-and-set in one statement so concurrent writers cannot both win
+        let mut query = UserEntity::update_many()
+            .col_expr(
+                user::Column::Version,
+                Expr::col(user::Column::Version).add(1),
+            )
+            .col_expr(user::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(user::Column::Id.eq(id))
+            // Only allow updating active (non-deleted) users
+            .filter(user::Column::DeletedAt.is_null());
         if let Some(name) = name {
-            active.name = Set(name);
+            query = query.col_expr(user::Column::Name, Expr::value(name));
         }
         if let Some(role) = role {
-            active.role = Set(role);
+            query = query.col_expr(user::Column::Role, Expr::value(role));
+        }
+        if let Some(version) = expected_version {
+            query = query.filter(user::Column::Version.eq(version));
         }
-        active.updated_at = Set(chrono::Utc::now());
 
-        let model = active.update(&self.db).await.map_err(AppError::from)?;
-        Ok(User::from(model))
+        let result = query
+            .exec(self.db())
+            .await
+            .map_err(AppError::from)?;
+
+        let user = UserEntity::find_by_id(id)
+            .filter(user::Column::DeletedAt.is_null())
+            .one(self.db())
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if result.rows_affected == 0 {
+            return Err(AppError::PreconditionFailed);
+        }
+        Ok(User::from(user))
     }
 
     async fn delete(&self, id: Uuid) -> AppResult<()> {
         // Soft delete: set deleted_at timestamp
         let user = UserEntity::find_by_id(id)
             .filter(user::Column::DeletedAt.is_null())
-            .one(&self.db)
+            .one(self.db())
             .await?
             .ok_or(AppError::NotFound)?;
 
+        let version = user.version;
         let mut active: ActiveModel = user.into();
         let now = chrono::Utc::now();
+        active.version = Set(version + 1);
         active.deleted_at = Set(Some(now));
         active.updated_at = Set(now);
 
-        active.update(&self.db).await.map_err(AppError::from)?;
+        active
+            .update(self.db())
+            .await
+            .map_err(AppError::from)?;
         Ok(())
     }
 
     async fn hard_delete(&self, id: Uuid) -> AppResult<()> {
         let result = UserEntity::delete_by_id(id)
-            .exec(&self.db)
+            .exec(self.db())
             .await
             .map_err(AppError::from)?;
 
@@ -183,22 +316,27 @@ impl UserRepository for UserStore {
         // Find the soft-deleted user
         let user = UserEntity::find_by_id(id)
             .filter(user::Column::DeletedAt.is_not_null())
-            .one(&self.db)
+            .one(self.db())
             .await?
             .ok_or_else(|| AppError::validation("User is not deleted or does not exist"))?;
 
+        let version = user.version;
         let mut active: ActiveModel = user.into();
+        active.version = Set(version + 1);
         active.deleted_at = Set(None);
         active.updated_at = Set(chrono::Utc::now());
 
-        let model = active.update(&self.db).await.map_err(AppError::from)?;
+        let model = active
+            .update(self.db())
+            .await
+            .map_err(AppError::from)?;
         Ok(User::from(model))
     }
 
     async fn list(&self) -> AppResult<Vec<User>> {
         let models = UserEntity::find()
             .filter(user::Column::DeletedAt.is_null())
-            .all(&self.db)
+            .all(self.read_db())
             .await
             .map_err(AppError::from)?;
 
@@ -207,7 +345,7 @@ impl UserRepository for UserStore {
 
     async fn list_with_deleted(&self) -> AppResult<Vec<User>> {
         let models = UserEntity::find()
-            .all(&self.db)
+            .all(self.read_db())
             .await
             .map_err(AppError::from)?;
 
@@ -217,10 +355,205 @@ impl UserRepository for UserStore {
     async fn list_deleted(&self) -> AppResult<Vec<User>> {
         let models = UserEntity::find()
             .filter(user::Column::DeletedAt.is_not_null())
-            .all(&self.db)
+            .all(self.read_db())
             .await
             .map_err(AppError::from)?;
 
         Ok(models.into_iter().map(User::from).collect())
     }
+
+    async fn count(&self) -> AppResult<u64> {
+        UserEntity::find()
+            .filter(user::Column::DeletedAt.is_null())
+            .count(self.read_db())
+            .await
+            .map_err(AppError::from)
+    }
+
+    async fn count_deleted(&self) -> AppResult<u64> {
+        UserEntity::find()
+            .filter(user::Column::DeletedAt.is_not_null())
+            .count(self.read_db())
+            .await
+            .map_err(AppError::from)
+    }
+
+    async fn list_deleted_before(&self, before: DateTime<Utc>, limit: u64) -> AppResult<Vec<User>> {
+        // Read from the primary: the caller is about to purge them
+        let models = UserEntity::find()
+            .filter(user::Column::DeletedAt.lt(before))
+            .order_by_asc(user::Column::DeletedAt)
+            .order_by_asc(user::Column::Id)
+            .limit(limit)
+            .all(self.db())
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(models.into_iter().map(User::from).collect())
+    }
+
+    async fn count_deleted_before(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        UserEntity::find()
+            .filter(user::Column::DeletedAt.lt(before))
+            .count(self.read_db())
+            .await
+            .map_err(AppError::from)
+    }
+
+    async fn purge_deleted(&self, ids: &[Uuid], before: DateTime<Utc>) -> AppResult<Vec<User>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        // DELETE ... RETURNING, so callers see exactly the rows removed
+        let models = UserEntity::delete_many()
+            .filter(user::Column::Id.is_in(ids.iter().copied()))
+            .filter(user::Column::DeletedAt.lt(before))
+            .exec_with_returning(self.db())
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(models.into_iter().map(User::from).collect())
+    }
+
+    async fn search(&self, search: &UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)> {
+        match self.read_db().get_database_backend() {
+            DatabaseBackend::Postgres => self.search_indexed(search).await,
+            _ => self.search_substrings(search).await,
+        }
+    }
+}
+
+impl<C: ConnectionRef> UserStore<C> {
+    /// Connection for writes and the lookups they depend on
+    fn db(&self) -> &C::Connection {
+        self.db.connection()
+    }
+
+    /// Connection for reads that may lag behind writes
+    fn read_db(&self) -> &C::Connection {
+        self.reader.connection()
+    }
+
+    /// Ranked search on the PostgreSQL full-text and trigram indexes
+    async fn search_indexed(&self, search: &UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)> {
+        let db = self.read_db();
+        let query = &search.query;
+        let (page, count) = ranked_search_statements(search);
+
+        let rows = db.query_all(page).await?;
+        let hits = rows
+            .iter()
+            .map(|row| {
+                let user = User::from(user::Model::from_query_result(row, "")?);
+                Ok(UserSearchHit {
+                    highlights: query.highlights(&user),
+                    rank: row.try_get("", "rank")?,
+                    user,
+                })
+            })
+            .collect::<Result<Vec<_>, sea_orm::DbErr>>()?;
+
+        let total = db
+            .query_one(count)
+            .await?
+            .and_then(|row| row.try_get::<i64>("", "count").ok())
+            .unwrap_or(0) as u64;
+
+        Ok((hits, total))
+    }
+
+    /// Unindexed search for other backends: every term must occur in the
+    /// name or email, ranked by the share of them the terms cover
+    async fn search_substrings(&self, search: &UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)> {
+        let db = self.read_db();
+        let query = &search.query;
+        let lower = |column: user::Column| Expr::expr(Func::lower(Expr::col(column)));
+
+        // Terms are alphanumeric, so they need no LIKE escaping
+        let mut condition = Condition::all();
+        let mut covered = SimpleExpr::from(0);
+        for term in query.terms() {
+            let pattern = format!("%{}%", term);
+            let in_name = lower(user::Column::Name).like(&pattern);
+            let in_email = lower(user::Column::Email).like(&pattern);
+            let length = term.chars().count() as i64;
+            covered = covered
+                .add(Expr::case(in_name.clone(), length).finally(0))
+                .add(Expr::case(in_email.clone(), length).finally(0));
+            condition = condition.add(Condition::any().add(in_name).add(in_email));
+        }
+        let length = Expr::expr(Func::char_length(Expr::col(user::Column::Name)))
+            .add(Func::char_length(Expr::col(user::Column::Email)));
+        let rank = Expr::val(1.0).mul(covered).div(length);
+
+        let matches = UserEntity::find()
+            .filter(user::Column::DeletedAt.is_null())
+            .filter(condition);
+        let total = matches.clone().count(db).await?;
+
+        let page = matches
+            .expr_as(rank, "rank")
+            .order_by_desc(Expr::cust("rank"))
+            .order_by_asc(user::Column::Id)
+            .limit(search.limit)
+            .offset(search.offset)
+            .build(db.get_database_backend());
+        let hits = db
+            .query_all(page)
+            .await?
+            .iter()
+            .map(|row| {
+                let user = User::from(user::Model::from_query_result(row, "")?);
+                Ok(UserSearchHit {
+                    highlights: query.highlights(&user),
+                    rank: row.try_get("", "rank")?,
+                    user,
+                })
+            })
+            .collect::<Result<Vec<_>, sea_orm::DbErr>>()?;
+
+        Ok((hits, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SearchQuery;
+
+    #[test]
+    fn test_ranked_search_statements() {
+        let search = UserSearch {
+            query: SearchQuery::parse("Ada LOVE").unwrap(),
+            limit: 20,
+            offset: 40,
+        };
+        let (page, count) = ranked_search_statements(&search);
+
+        // Every entity column is selected, so rows load as user::Model
+        let projection = page.sql.split(", (ts_rank").next().unwrap();
+        let columns: Vec<&str> = projection
+            .trim_start_matches("SELECT ")
+            .split(", ")
+            .collect();
+        let expected: Vec<String> = user::Column::iter()
+            .map(|column| column.as_str().to_string())
+            .collect();
+        assert_eq!(columns, expected);
+
+        assert!(page
+            .sql
+            .ends_with("ORDER BY rank DESC, id LIMIT $3 OFFSET $4"));
+        assert_eq!(
+            page.values.unwrap().0,
+            vec![
+                "ada love".into(),
+                "ada:* & love:*".into(),
+                20i64.into(),
+                40i64.into()
+            ]
+        );
+        assert!(count.sql.contains(SEARCH_MATCHES));
+        assert_eq!(count.values.unwrap().0.len(), 2);
+    }
 }
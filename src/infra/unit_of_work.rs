@@ -11,10 +11,13 @@
 
 use async_trait::async_trait;
 use sea_orm::{
-    AccessMode, DatabaseConnection, DatabaseTransaction, IsolationLevel, TransactionTrait,
+    AccessMode, ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend,
+    IsolationLevel, TransactionTrait,
 };
 use std::sync::Arc;
 
+use super::outbox::TxOutbox;
+use super::purge_log::TxPurgeLog;
 use super::repositories::{UserRepository, UserStore};
 use crate::errors::{AppError, AppResult};
 
@@ -66,8 +69,20 @@ impl<'a> TransactionContext<'a> {
     }
 
     /// Get user repository for this transaction
-    pub fn users(&self) -> TxUserRepository<'_> {
-        TxUserRepository::new(self.txn)
+    pub fn users(&self) -> TxUserRepository<'a> {
+        UserStore::new(self.txn)
+    }
+
+    /// Get outbox writer for this transaction
+    ///
+    /// Jobs and events written here are relayed only if the transaction commits.
+    pub fn outbox(&self) -> TxOutbox<'_> {
+        TxOutbox::new(self.txn)
+    }
+
+    /// Get the purged-user record writer for this transaction
+    pub fn purge_log(&self) -> TxPurgeLog<'_> {
+        TxPurgeLog::new(self.txn)
     }
 }
 
@@ -84,6 +99,13 @@ impl Persistence {
         Self { db, user_repo }
     }
 
+    /// Serve repository lists, counts and searches from a replica; lookups
+    /// and transactions keep using the primary, so they see recent writes.
+    pub fn with_replica(mut self, replica: DatabaseConnection) -> Self {
+        self.user_repo = Arc::new(UserStore::new(self.db.clone()).with_replica(replica));
+        self
+    }
+
     /// Internal transaction execution with configurable isolation level
     async fn execute_transaction<F, T>(&self, isolation: IsolationLevel, f: F) -> AppResult<T>
     where
@@ -92,12 +114,17 @@ impl Persistence {
             > + Send,
         T: Send,
     {
-        // Begin transaction
-        let txn = self
-            .db
-            .begin_with_config(Some(isolation), Some(AccessMode::ReadWrite))
-            .await
-            .map_err(AppError::from)?;
+        // Begin transaction; SQLite has no per-transaction isolation level
+        // (its transactions are always serializable)
+        let txn = match self.db.get_database_backend() {
+            DbBackend::Sqlite => self.db.begin().await,
+            _ => {
+                self.db
+                    .begin_with_config(Some(isolation), Some(AccessMode::ReadWrite))
+                    .await
+            }
+        }
+        .map_err(AppError::from)?;
 
         // Create context with borrowed transaction
         let ctx = TransactionContext::new(&txn);
@@ -148,222 +175,11 @@ impl UnitOfWork for Persistence {
     }
 }
 
-/// Transaction-aware user repository with soft delete support.
+/// User repository running inside a transaction.
 ///
-/// Executes all operations within the provided transaction.
-/// Uses borrowed reference to ensure transaction outlives repository operations.
-/// By default, query methods exclude soft-deleted records.
-pub struct TxUserRepository<'a> {
-    txn: &'a DatabaseTransaction,
-}
-
-impl<'a> TxUserRepository<'a> {
-    /// Create new transaction-aware repository
-    fn new(txn: &'a DatabaseTransaction) -> Self {
-        Self { txn }
-    }
-
-    /// Find active user by ID (excludes soft-deleted)
-    pub async fn find_by_id(&self, id: uuid::Uuid) -> AppResult<Option<crate::domain::User>> {
-        use super::repositories::entities::user::{self, Entity as UserEntity};
-        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-
-        let result = UserEntity::find_by_id(id)
-            .filter(user::Column::DeletedAt.is_null())
-            .one(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(result.map(crate::domain::User::from))
-    }
-
-    /// Find user by ID including soft-deleted
-    pub async fn find_by_id_with_deleted(&self, id: uuid::Uuid) -> AppResult<Option<crate::domain::User>> {
-        use super::repositories::entities::user::Entity as UserEntity;
-        use sea_orm::EntityTrait;
-
-        let result = UserEntity::find_by_id(id)
-            .one(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(result.map(crate::domain::User::from))
-    }
-
-    /// Find active user by email (excludes soft-deleted)
-    pub async fn find_by_email(&self, email: &str) -> AppResult<Option<crate::domain::User>> {
-        use super::repositories::entities::user::{self, Entity as UserEntity};
-        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-
-        let result = UserEntity::find()
-            .filter(user::Column::Email.eq(email))
-            .filter(user::Column::DeletedAt.is_null())
-            .one(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(result.map(crate::domain::User::from))
-    }
-
-    /// Find user by email including soft-deleted
-    pub async fn find_by_email_with_deleted(&self, email: &str) -> AppResult<Option<crate::domain::User>> {
-        use super::repositories::entities::user::{self, Entity as UserEntity};
-        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-
-        let result = UserEntity::find()
-            .filter(user::Column::Email.eq(email))
-            .one(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(result.map(crate::domain::User::from))
-    }
-
-    /// Create a new user
-    pub async fn create(
-        &self,
-        email: String,
-        password_hash: String,
-        name: String,
-    ) -> AppResult<crate::domain::User> {
-        use super::repositories::entities::user::ActiveModel;
-        use crate::config::ROLE_USER;
-        use sea_orm::{ActiveModelTrait, Set};
-
-        let now = chrono::Utc::now();
-        let active_model = ActiveModel {
-            id: Set(uuid::Uuid::new_v4()),
-            email: Set(email),
-            password_hash: Set(password_hash),
-            name: Set(name),
-            role: Set(ROLE_USER.to_string()),
-            created_at: Set(now),
-            updated_at: Set(now),
-            deleted_at: Set(None),
-        };
-
-        let model = active_model
-            .insert(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(crate::domain::User::from(model))
-    }
-
-    /// Update user fields (only active users)
-    pub async fn update(
-        &self,
-        id: uuid::Uuid,
-        name: Option<String>,
-        role: Option<String>,
-    ) -> AppResult<crate::domain::User> {
-        use super::repositories::entities::user::{self, ActiveModel, Entity as UserEntity};
-        use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
-
-        let user = UserEntity::find_by_id(id)
-            .filter(user::Column::DeletedAt.is_null())
-            .one(self.txn)
-            .await?
-            .ok_or(AppError::NotFound)?;
-
-        let mut active: ActiveModel = user.into();
-
-        if let Some(name) = name {
-            active.name = Set(name);
-        }
-        if let Some(role) = role {
-            active.role = Set(role);
-        }
-        active.updated_at = Set(chrono::Utc::now());
-
-        let model = active.update(self.txn).await.map_err(AppError::from)?;
-
-        Ok(crate::domain::User::from(model))
-    }
-
-    /// Soft delete user by ID (sets deleted_at timestamp)
-    pub async fn delete(&self, id: uuid::Uuid) -> AppResult<()> {
-        use super::repositories::entities::user::{self, ActiveModel, Entity as UserEntity};
-        use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
-
-        let user = UserEntity::find_by_id(id)
-            .filter(user::Column::DeletedAt.is_null())
-            .one(self.txn)
-            .await?
-            .ok_or(AppError::NotFound)?;
-
-        let mut active: ActiveModel = user.into();
-        let now = chrono::Utc::now();
-        active.deleted_at = Set(Some(now));
-        active.updated_at = Set(now);
-
-        active.update(self.txn).await.map_err(AppError::from)?;
-        Ok(())
-    }
-
-    /// Permanently delete user from database (hard delete)
-    pub async fn hard_delete(&self, id: uuid::Uuid) -> AppResult<()> {
-        use super::repositories::entities::user::Entity as UserEntity;
-        use sea_orm::EntityTrait;
-
-        let result = UserEntity::delete_by_id(id)
-            .exec(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        if result.rows_affected == 0 {
-            return Err(AppError::NotFound);
-        }
-
-        Ok(())
-    }
-
-    /// Restore a soft-deleted user
-    pub async fn restore(&self, id: uuid::Uuid) -> AppResult<crate::domain::User> {
-        use super::repositories::entities::user::{self, ActiveModel, Entity as UserEntity};
-        use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
-
-        let user = UserEntity::find_by_id(id)
-            .filter(user::Column::DeletedAt.is_not_null())
-            .one(self.txn)
-            .await?
-            .ok_or_else(|| AppError::validation("User is not deleted or does not exist"))?;
-
-        let mut active: ActiveModel = user.into();
-        active.deleted_at = Set(None);
-        active.updated_at = Set(chrono::Utc::now());
-
-        let model = active.update(self.txn).await.map_err(AppError::from)?;
-        Ok(crate::domain::User::from(model))
-    }
-
-    /// List active users (excludes soft-deleted)
-    pub async fn list(&self) -> AppResult<Vec<crate::domain::User>> {
-        use super::repositories::entities::user::{self, Entity as UserEntity};
-        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-
-        let models = UserEntity::find()
-            .filter(user::Column::DeletedAt.is_null())
-            .all(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(models.into_iter().map(crate::domain::User::from).collect())
-    }
-
-    /// List all users including soft-deleted
-    pub async fn list_with_deleted(&self) -> AppResult<Vec<crate::domain::User>> {
-        use super::repositories::entities::user::Entity as UserEntity;
-        use sea_orm::EntityTrait;
-
-        let models = UserEntity::find()
-            .all(self.txn)
-            .await
-            .map_err(AppError::from)?;
-
-        Ok(models.into_iter().map(crate::domain::User::from).collect())
-    }
-}
+/// The same [`UserStore`] used outside transactions, borrowing the
+/// transaction as its connection, so it implements [`UserRepository`].
+pub type TxUserRepository<'a> = UserStore<&'a DatabaseTransaction>;
 
 /// Simpler API for executing transactional operations.
 ///
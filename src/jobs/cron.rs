@@ -0,0 +1,629 @@
+//! Recurring (cron) jobs run by the jobs worker.
+//!
+//! Each [`CronJob`] is paired with a [`CronSchedule`]: a cron expression
+//! (with seconds, e.g. `0 0 3 * * *`) and a [`MissedRunPolicy`]. Run state
+//! lives in the `cron_schedules` table, which makes runs exclusive across
+//! workers (a renewable lease) and lets missed occurrences be detected
+//! after downtime.
+//!
+//! Schedules can be changed per job with environment variables:
+//! - `CRON_<NAME>` - cron expression, or `off` to disable the job
+//! - `CRON_<NAME>_POLICY` - `skip` or `catch_up`
+//!
+//! e.g. `CRON_PRUNE_DONE_JOBS="0 0 * * * *"`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+use crate::config::{
+    CRON_ADMIN_DIGEST, CRON_CLAIM_RETRY_SECONDS, CRON_LEASE_SECONDS, CRON_MAX_CATCH_UP_RUNS,
+    CRON_MISFIRE_GRACE_SECONDS, CRON_PRUNE_DONE_JOBS, CRON_PURGE_DELETED_USERS,
+};
+use crate::errors::{AppError, AppResult};
+use crate::infra::repositories::entities::cron_schedule::{
+    self, ActiveModel, Entity as CronScheduleEntity, Model,
+};
+
+/// Cron job names
+pub const CRON_JOB_PURGE_DELETED_USERS: &str = "purge_deleted_users";
+pub const CRON_JOB_PRUNE_DONE_JOBS: &str = "prune_done_jobs";
+pub const CRON_JOB_ADMIN_DIGEST: &str = "admin_digest";
+
+/// What to do with occurrences missed while no worker was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedRunPolicy {
+    /// Drop missed occurrences and wait for the next one
+    Skip,
+    /// Run every missed occurrence in order (at most [`CRON_MAX_CATCH_UP_RUNS`])
+    CatchUp,
+}
+
+impl FromStr for MissedRunPolicy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "skip" => Ok(Self::Skip),
+            "catch_up" | "catchup" => Ok(Self::CatchUp),
+            other => Err(AppError::validation(format!(
+                "Unknown missed run policy '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for MissedRunPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Skip => "skip",
+            Self::CatchUp => "catch_up",
+        })
+    }
+}
+
+/// When a cron job runs.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    pub name: String,
+    pub expression: String,
+    pub policy: MissedRunPolicy,
+    schedule: cron::Schedule,
+}
+
+/// Next action for a schedule, see [`CronSchedule::plan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CronStep {
+    /// Run the occurrence now
+    Run(DateTime<Utc>),
+    /// Mark occurrences up to and including this one as skipped
+    Skip(DateTime<Utc>),
+    /// Nothing is due before this time
+    Wait(DateTime<Utc>),
+    /// The expression has no future occurrences
+    Done,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression.
+    pub fn new(
+        name: impl Into<String>,
+        expression: impl Into<String>,
+        policy: MissedRunPolicy,
+    ) -> AppResult<Self> {
+        let name = name.into();
+        let expression = expression.into();
+        let schedule = cron::Schedule::from_str(&expression).map_err(|e| {
+            AppError::validation(format!(
+                "Invalid cron expression '{}' for {}: {}",
+                expression, name, e
+            ))
+        })?;
+
+        Ok(Self {
+            name,
+            expression,
+            policy,
+            schedule,
+        })
+    }
+
+    /// Build a schedule, applying `CRON_<NAME>` / `CRON_<NAME>_POLICY` overrides.
+    ///
+    /// Returns `None` if the job is disabled with `CRON_<NAME>=off`.
+    pub fn from_env(
+        name: &str,
+        default_expression: &str,
+        default_policy: MissedRunPolicy,
+    ) -> AppResult<Option<Self>> {
+        let var = format!("CRON_{}", name.to_uppercase());
+        let expression = std::env::var(&var).unwrap_or_else(|_| default_expression.to_string());
+        if matches!(expression.trim(), "off" | "false" | "disabled") {
+            return Ok(None);
+        }
+
+        let policy = match std::env::var(format!("{}_POLICY", var)) {
+            Ok(policy) => policy.parse()?,
+            Err(_) => default_policy,
+        };
+
+        Self::new(name, expression.trim(), policy).map(Some)
+    }
+
+    /// First occurrence strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule.after(&after).next()
+    }
+
+    /// Next `count` occurrences after `after`.
+    pub fn upcoming(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        self.schedule.after(&after).take(count).collect()
+    }
+
+    /// Decide what to do at `now` given the last handled occurrence.
+    ///
+    /// Without a previous run only occurrences within the misfire grace
+    /// period count, so a newly added job does not replay its history.
+    pub fn plan(&self, last: Option<DateTime<Utc>>, now: DateTime<Utc>) -> CronStep {
+        let grace = chrono::Duration::seconds(CRON_MISFIRE_GRACE_SECONDS);
+        let from = last.unwrap_or(now - grace);
+
+        let due: Vec<DateTime<Utc>> = self
+            .schedule
+            .after(&from)
+            .take_while(|occurrence| *occurrence <= now)
+            .collect();
+
+        let Some(&first) = due.first() else {
+            return match self.next_after(from.max(now)) {
+                Some(next) => CronStep::Wait(next),
+                None => CronStep::Done,
+            };
+        };
+
+        if now - first <= grace {
+            return CronStep::Run(first);
+        }
+
+        match self.policy {
+            MissedRunPolicy::Skip => {
+                // Keep an occurrence that is still within grace, drop the rest
+                let missed = due.iter().rev().find(|o| now - **o > grace).copied();
+                CronStep::Skip(missed.unwrap_or(first))
+            }
+            MissedRunPolicy::CatchUp if due.len() > CRON_MAX_CATCH_UP_RUNS => {
+                CronStep::Skip(due[due.len() - CRON_MAX_CATCH_UP_RUNS - 1])
+            }
+            MissedRunPolicy::CatchUp => CronStep::Run(first),
+        }
+    }
+}
+
+/// Built-in schedules with environment overrides applied.
+pub fn default_schedules() -> AppResult<Vec<CronSchedule>> {
+    let defaults = [
+        (
+            CRON_JOB_PURGE_DELETED_USERS,
+            CRON_PURGE_DELETED_USERS,
+            MissedRunPolicy::Skip,
+        ),
+        (
+            CRON_JOB_PRUNE_DONE_JOBS,
+            CRON_PRUNE_DONE_JOBS,
+            MissedRunPolicy::Skip,
+        ),
+        (
+            CRON_JOB_ADMIN_DIGEST,
+            CRON_ADMIN_DIGEST,
+            MissedRunPolicy::CatchUp,
+        ),
+    ];
+
+    let mut schedules = Vec::new();
+    for (name, expression, policy) in defaults {
+        if let Some(schedule) = CronSchedule::from_env(name, expression, policy)? {
+            schedules.push(schedule);
+        }
+    }
+    Ok(schedules)
+}
+
+/// A single run of a cron job.
+#[derive(Debug, Clone, Copy)]
+pub struct CronRun {
+    /// Occurrence this run handles
+    pub scheduled_at: DateTime<Utc>,
+    /// Occurrence handled by the previous run, if any
+    pub previous_at: Option<DateTime<Utc>>,
+}
+
+/// A job run on a cron schedule.
+#[async_trait]
+pub trait CronJob: Send + Sync {
+    /// Job name; matches the schedule name
+    fn name(&self) -> &'static str;
+
+    /// Run the job for one occurrence
+    async fn run(&self, run: CronRun) -> AppResult<()>;
+}
+
+/// Run state persisted in `cron_schedules`.
+#[derive(Clone)]
+pub struct CronStore {
+    db: DatabaseConnection,
+}
+
+impl CronStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// State of every schedule that has run at least once.
+    pub async fn all(&self) -> AppResult<Vec<Model>> {
+        Ok(CronScheduleEntity::find().all(&self.db).await?)
+    }
+
+    /// State of one schedule, creating the row on first use.
+    async fn load(&self, name: &str) -> AppResult<Model> {
+        let row = ActiveModel {
+            name: Set(name.to_string()),
+            ..Default::default()
+        };
+        CronScheduleEntity::insert(row)
+            .on_conflict(
+                OnConflict::column(cron_schedule::Column::Name)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+
+        CronScheduleEntity::find_by_id(name.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    /// Take the lease for `occurrence` unless another worker holds it or
+    /// the occurrence was already handled.
+    async fn claim(&self, name: &str, occurrence: DateTime<Utc>) -> AppResult<bool> {
+        let now = Utc::now();
+        let result = CronScheduleEntity::update_many()
+            .col_expr(
+                cron_schedule::Column::LockedUntil,
+                Expr::value(now + chrono::Duration::seconds(CRON_LEASE_SECONDS)),
+            )
+            .col_expr(cron_schedule::Column::LastStartedAt, Expr::value(now))
+            .filter(cron_schedule::Column::Name.eq(name))
+            .filter(
+                Condition::any()
+                    .add(cron_schedule::Column::LockedUntil.is_null())
+                    .add(cron_schedule::Column::LockedUntil.lt(now)),
+            )
+            .filter(
+                Condition::any()
+                    .add(cron_schedule::Column::LastScheduledAt.is_null())
+                    .add(cron_schedule::Column::LastScheduledAt.lt(occurrence)),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Renew the lease of a running job.
+    async fn renew(&self, name: &str) -> AppResult<()> {
+        CronScheduleEntity::update_many()
+            .col_expr(
+                cron_schedule::Column::LockedUntil,
+                Expr::value(Utc::now() + chrono::Duration::seconds(CRON_LEASE_SECONDS)),
+            )
+            .filter(cron_schedule::Column::Name.eq(name))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Record the outcome of a run and release the lease.
+    async fn finish(
+        &self,
+        name: &str,
+        occurrence: DateTime<Utc>,
+        outcome: &AppResult<()>,
+    ) -> AppResult<()> {
+        let (status, error) = match outcome {
+            Ok(()) => ("succeeded", None),
+            Err(e) => ("failed", Some(e.to_string())),
+        };
+        CronScheduleEntity::update_many()
+            .col_expr(
+                cron_schedule::Column::LastScheduledAt,
+                Expr::value(occurrence),
+            )
+            .col_expr(
+                cron_schedule::Column::LastFinishedAt,
+                Expr::value(Utc::now()),
+            )
+            .col_expr(cron_schedule::Column::LastStatus, Expr::value(status))
+            .col_expr(cron_schedule::Column::LastError, Expr::value(error))
+            .col_expr(
+                cron_schedule::Column::LockedUntil,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .filter(cron_schedule::Column::Name.eq(name))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Mark occurrences up to `occurrence` as handled without running them.
+    async fn skip(&self, name: &str, occurrence: DateTime<Utc>) -> AppResult<()> {
+        CronScheduleEntity::update_many()
+            .col_expr(
+                cron_schedule::Column::LastScheduledAt,
+                Expr::value(occurrence),
+            )
+            .col_expr(cron_schedule::Column::LastStatus, Expr::value("skipped"))
+            .filter(cron_schedule::Column::Name.eq(name))
+            .filter(
+                Condition::any()
+                    .add(cron_schedule::Column::LastScheduledAt.is_null())
+                    .add(cron_schedule::Column::LastScheduledAt.lt(occurrence)),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Runs cron jobs on their schedules.
+pub struct CronScheduler {
+    store: CronStore,
+    jobs: Vec<(CronSchedule, Arc<dyn CronJob>)>,
+}
+
+impl CronScheduler {
+    pub fn new(store: CronStore) -> Self {
+        Self {
+            store,
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Register a job on a schedule.
+    pub fn register(mut self, schedule: CronSchedule, job: Arc<dyn CronJob>) -> Self {
+        self.jobs.push((schedule, job));
+        self
+    }
+
+    /// Registered schedules.
+    pub fn schedules(&self) -> impl Iterator<Item = &CronSchedule> {
+        self.jobs.iter().map(|(schedule, _)| schedule)
+    }
+
+    /// Start one scheduling loop per job.
+    pub fn start(self) -> CronHandle {
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let workers = self
+            .jobs
+            .into_iter()
+            .map(|(schedule, job)| {
+                tokio::spawn(run_schedule(
+                    self.store.clone(),
+                    schedule,
+                    job,
+                    stop_rx.clone(),
+                ))
+            })
+            .collect();
+
+        CronHandle { stop_tx, workers }
+    }
+}
+
+/// Handle to running cron loops.
+pub struct CronHandle {
+    stop_tx: watch::Sender<bool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl CronHandle {
+    /// Stop scheduling and wait for in-flight runs to finish.
+    pub async fn shutdown(self) {
+        self.stop_tx.send_replace(true);
+        for worker in self.workers {
+            if let Err(e) = worker.await {
+                tracing::error!(error = %e, "Cron job panicked");
+            }
+        }
+        tracing::info!("Cron scheduler stopped");
+    }
+}
+
+/// Sleep for `duration` unless stopped first; returns `false` when stopped.
+async fn sleep_or_stop(duration: Duration, stop: &mut watch::Receiver<bool>) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => !*stop.borrow(),
+        _ = stop.changed() => false,
+    }
+}
+
+/// Scheduling loop for a single job.
+async fn run_schedule(
+    store: CronStore,
+    schedule: CronSchedule,
+    job: Arc<dyn CronJob>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let retry = Duration::from_secs(CRON_CLAIM_RETRY_SECONDS);
+    let name = schedule.name.as_str();
+
+    while !*stop.borrow() {
+        let state = match store.load(name).await {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::error!(job = name, error = %e, "Failed to load cron state");
+                if !sleep_or_stop(retry, &mut stop).await {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        let step = schedule.plan(state.last_scheduled_at, Utc::now());
+        let result = match step {
+            CronStep::Done => {
+                tracing::info!(job = name, "Cron schedule has no future occurrences");
+                break;
+            }
+            CronStep::Wait(next) => {
+                let wait = (next - Utc::now()).to_std().unwrap_or_default();
+                if !sleep_or_stop(wait, &mut stop).await {
+                    break;
+                }
+                continue;
+            }
+            CronStep::Skip(occurrence) => {
+                tracing::warn!(job = name, up_to = %occurrence, "Skipping missed cron runs");
+                store.skip(name, occurrence).await
+            }
+            CronStep::Run(occurrence) => match store.claim(name, occurrence).await {
+                Ok(true) => {
+                    let run = CronRun {
+                        scheduled_at: occurrence,
+                        previous_at: state.last_scheduled_at,
+                    };
+                    let outcome = run_with_lease(&store, job.as_ref(), run).await;
+                    store.finish(name, occurrence, &outcome).await
+                }
+                // Another worker is running it
+                Ok(false) => {
+                    if !sleep_or_stop(retry, &mut stop).await {
+                        break;
+                    }
+                    continue;
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        if let Err(e) = result {
+            tracing::error!(job = name, error = %e, "Cron bookkeeping failed");
+            if !sleep_or_stop(retry, &mut stop).await {
+                break;
+            }
+        }
+    }
+}
+
+/// Run the job while renewing its lease.
+async fn run_with_lease(store: &CronStore, job: &dyn CronJob, run: CronRun) -> AppResult<()> {
+    let name = job.name();
+    let renew_every = Duration::from_secs((CRON_LEASE_SECONDS / 3) as u64);
+    let started = std::time::Instant::now();
+    tracing::info!(job = name, scheduled_at = %run.scheduled_at, "Cron job started");
+
+    let execution = job.run(run);
+    tokio::pin!(execution);
+    let outcome = loop {
+        tokio::select! {
+            outcome = &mut execution => break outcome,
+            _ = tokio::time::sleep(renew_every) => {
+                if let Err(e) = store.renew(name).await {
+                    tracing::warn!(job = name, error = %e, "Failed to renew cron lease");
+                }
+            }
+        }
+    };
+
+    match &outcome {
+        Ok(()) => tracing::info!(
+            job = name,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Cron job completed"
+        ),
+        Err(e) => tracing::error!(job = name, error = %e, "Cron job failed"),
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, h, m, s).unwrap()
+    }
+
+    fn hourly(policy: MissedRunPolicy) -> CronSchedule {
+        CronSchedule::new("test", "0 0 * * * *", policy).unwrap()
+    }
+
+    #[test]
+    fn test_invalid_expression_is_rejected() {
+        assert!(CronSchedule::new("test", "every tuesday", MissedRunPolicy::Skip).is_err());
+    }
+
+    #[test]
+    fn test_plan_waits_for_next_occurrence() {
+        let schedule = hourly(MissedRunPolicy::Skip);
+        assert_eq!(
+            schedule.plan(Some(at(10, 0, 0)), at(10, 30, 0)),
+            CronStep::Wait(at(11, 0, 0))
+        );
+        // First run of a new job does not replay history
+        assert_eq!(
+            schedule.plan(None, at(10, 30, 0)),
+            CronStep::Wait(at(11, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_plan_runs_on_time_occurrence() {
+        let schedule = hourly(MissedRunPolicy::Skip);
+        assert_eq!(
+            schedule.plan(Some(at(10, 0, 0)), at(11, 0, 5)),
+            CronStep::Run(at(11, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_skip_policy_drops_missed_runs() {
+        let schedule = hourly(MissedRunPolicy::Skip);
+        assert_eq!(
+            schedule.plan(Some(at(5, 0, 0)), at(10, 30, 0)),
+            CronStep::Skip(at(10, 0, 0))
+        );
+        // After skipping, nothing is due until the next occurrence
+        assert_eq!(
+            schedule.plan(Some(at(10, 0, 0)), at(10, 30, 0)),
+            CronStep::Wait(at(11, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_catch_up_policy_replays_missed_runs_in_order() {
+        let schedule = hourly(MissedRunPolicy::CatchUp);
+        assert_eq!(
+            schedule.plan(Some(at(5, 0, 0)), at(10, 30, 0)),
+            CronStep::Run(at(6, 0, 0))
+        );
+        assert_eq!(
+            schedule.plan(Some(at(6, 0, 0)), at(10, 30, 0)),
+            CronStep::Run(at(7, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_catch_up_is_bounded() {
+        let schedule = hourly(MissedRunPolicy::CatchUp);
+        let last = at(0, 0, 0) - chrono::Duration::days(2);
+        match schedule.plan(Some(last), at(12, 30, 0)) {
+            CronStep::Skip(up_to) => {
+                let remaining = schedule
+                    .upcoming(up_to, 100)
+                    .into_iter()
+                    .filter(|o| *o <= at(12, 30, 0))
+                    .count();
+                assert_eq!(remaining, CRON_MAX_CATCH_UP_RUNS);
+            }
+            other => panic!("expected skip, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_policy_parsing() {
+        assert_eq!(
+            "catch-up".parse::<MissedRunPolicy>().unwrap(),
+            MissedRunPolicy::CatchUp
+        );
+        assert!("sometimes".parse::<MissedRunPolicy>().is_err());
+    }
+}
@@ -1,13 +1,21 @@
 //! Email background job.
 //!
-//! Provides email sending functionality via background jobs.
-//! In development mode, emails are logged. In production, configure
-//! SMTP settings via environment variables.
+//! Provides email sending functionality via background jobs. Delivery
+//! goes through the [`EmailTransport`] configured by `EMAIL_TRANSPORT`
+//! (SMTP, file drop, HTTP provider or log-only).
+//!
+//! Delivery failures are classified and handed to [`JobRetries`]:
+//! permanent failures (rejected recipient, malformed address) are
+//! dead-lettered under the default policy, transient ones (connection
+//! refused, 4xx replies) are retried with backoff.
 
+use apalis::prelude::{Attempt, Data, Error as JobError, TaskId};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::sync::Arc;
 
-use crate::errors::AppError;
+use super::retry::JobRetries;
+use crate::config::JOB_NAME_EMAIL;
+use crate::infra::mailer::{Email, EmailConfig, EmailError, EmailTemplates, EmailTransport};
 
 /// Email job payload
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,9 +24,12 @@ pub struct EmailJob {
     pub to: String,
     /// Email subject line
     pub subject: String,
-    /// Email body content (plain text or HTML)
+    /// Plain text body
     pub body: String,
-    /// Optional sender override (defaults to SMTP_FROM)
+    /// Optional HTML body sent alongside the text
+    #[serde(default)]
+    pub html: Option<String>,
+    /// Optional sender override (defaults to EMAIL_FROM)
     #[serde(default)]
     pub from: Option<String>,
 }
@@ -30,10 +41,37 @@ impl EmailJob {
             to: to.into(),
             subject: subject.into(),
             body: body.into(),
+            html: None,
             from: None,
         }
     }
 
+    /// Create an email job from a named template.
+    ///
+    /// Renders subject, text and HTML parts of `name` in `locale` (falling
+    /// back to the default locale) with the given variables.
+    pub fn from_template(
+        to: impl Into<String>,
+        name: &str,
+        locale: &str,
+        vars: impl Serialize,
+    ) -> Result<Self, EmailError> {
+        let rendered = EmailTemplates::global().render(name, locale, vars)?;
+        Ok(Self {
+            to: to.into(),
+            subject: rendered.subject,
+            body: rendered.text,
+            html: rendered.html,
+            from: None,
+        })
+    }
+
+    /// Attach an HTML body
+    pub fn with_html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
     /// Set custom sender address
     pub fn with_from(mut self, from: impl Into<String>) -> Self {
         self.from = Some(from.into());
@@ -41,109 +79,108 @@ impl EmailJob {
     }
 }
 
-/// Email configuration from environment.
-/// Note: Some fields are currently unused pending lettre integration.
-#[allow(dead_code)]
-struct EmailConfig {
-    smtp_host: Option<String>,
-    smtp_port: u16,
-    smtp_user: Option<String>,
-    smtp_pass: Option<String>,
-    smtp_from: String,
-    smtp_tls: bool,
+/// Email sender shared by the worker: a transport plus the default sender.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: Arc<dyn EmailTransport>,
+    default_from: String,
 }
 
-impl EmailConfig {
-    fn from_env() -> Self {
+impl Mailer {
+    /// Create a mailer over the given transport.
+    pub fn new(transport: Arc<dyn EmailTransport>, default_from: impl Into<String>) -> Self {
         Self {
-            smtp_host: env::var("SMTP_HOST").ok(),
-            smtp_port: env::var("SMTP_PORT")
-                .ok()
-                .and_then(|p| p.parse().ok())
-                .unwrap_or(587),
-            smtp_user: env::var("SMTP_USER").ok(),
-            smtp_pass: env::var("SMTP_PASS").ok(),
-            smtp_from: env::var("SMTP_FROM").unwrap_or_else(|_| "noreply@example.com".to_string()),
-            smtp_tls: env::var("SMTP_TLS")
-                .map(|v| v == "true" || v == "1")
-                .unwrap_or(true),
+            transport,
+            default_from: default_from.into(),
         }
     }
 
-    fn is_configured(&self) -> bool {
-        self.smtp_host.is_some()
+    /// Create a mailer using the configured transport.
+    pub fn from_config(config: &EmailConfig) -> Result<Self, EmailError> {
+        Ok(Self::new(config.build_transport()?, config.from.clone()))
+    }
+
+    /// Name of the underlying transport.
+    pub fn transport_name(&self) -> &'static str {
+        self.transport.name()
+    }
+
+    /// Deliver an email job.
+    pub async fn send(&self, job: &EmailJob) -> Result<(), EmailError> {
+        let email = Email {
+            from: job
+                .from
+                .clone()
+                .unwrap_or_else(|| self.default_from.clone()),
+            to: job.to.clone(),
+            subject: job.subject.clone(),
+            text: job.body.clone(),
+            html: job.html.clone(),
+        };
+        self.transport.send(&email).await
     }
 }
 
 /// Email job handler - processes email sending jobs
-pub async fn email_job_handler(job: EmailJob) -> Result<(), AppError> {
-    let config = EmailConfig::from_env();
-    let from = job.from.as_deref().unwrap_or(&config.smtp_from);
-
+///
+/// Failures are recorded by [`JobRetries`], which decides whether the job is
+/// retried or dead-lettered.
+pub async fn email_job_handler(
+    job: EmailJob,
+    mailer: Data<Mailer>,
+    retries: Data<JobRetries>,
+    job_id: TaskId,
+    attempt: Attempt,
+) -> Result<(), JobError> {
     tracing::info!(
+        job_id = %job_id,
         to = %job.to,
-        from = %from,
         subject = %job.subject,
+        attempt = attempt.current() + 1,
+        transport = mailer.transport_name(),
         "Processing email job"
     );
 
-    if !config.is_configured() {
-        // Development mode: log the email instead of sending
-        tracing::warn!("SMTP not configured - logging email instead of sending");
-        tracing::info!(
-            "=== EMAIL (not sent) ===\n\
-             From: {}\n\
-             To: {}\n\
-             Subject: {}\n\
-             Body:\n{}\n\
-             ========================",
-            from,
-            job.to,
-            job.subject,
-            job.body
-        );
-        return Ok(());
+    match mailer.send(&job).await {
+        Ok(()) => {
+            tracing::info!(to = %job.to, "Email processed successfully");
+            Ok(())
+        }
+        Err(e) => Err(retries
+            .on_failure(JOB_NAME_EMAIL, &job_id, &attempt, e)
+            .await),
     }
+}
 
-    // Production mode: send via SMTP
-    // Note: Add `lettre` to Cargo.toml for real SMTP support:
-    // lettre = { version = "0.11", features = ["tokio1-native-tls"] }
-    //
-    // Example implementation with lettre:
-    // ```
-    // use lettre::{
-    //     message::header::ContentType,
-    //     transport::smtp::authentication::Credentials,
-    //     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
-    // };
-    //
-    // let email = Message::builder()
-    //     .from(from.parse()?)
-    //     .to(job.to.parse()?)
-    //     .subject(&job.subject)
-    //     .header(ContentType::TEXT_PLAIN)
-    //     .body(job.body.clone())?;
-    //
-    // let creds = Credentials::new(
-    //     config.smtp_user.unwrap_or_default(),
-    //     config.smtp_pass.unwrap_or_default(),
-    // );
-    //
-    // let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host.unwrap())?
-    //     .credentials(creds)
-    //     .build();
-    //
-    // mailer.send(email).await?;
-    // ```
-
-    tracing::warn!(
-        "SMTP is configured but lettre is not installed. \
-         Add lettre to Cargo.toml to enable real email sending."
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Simulate sending delay
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    #[test]
+    fn test_from_template_renders_all_parts() {
+        let job = EmailJob::from_template(
+            "ada@example.com",
+            "welcome",
+            "de-AT",
+            serde_json::json!({ "name": "Ada" }),
+        )
+        .unwrap();
 
-    tracing::info!(to = %job.to, "Email processed successfully");
-    Ok(())
+        assert_eq!(job.to, "ada@example.com");
+        assert!(job.subject.starts_with("Willkommen"));
+        assert!(job.body.contains("Hallo Ada"));
+        assert!(job.html.unwrap().contains("<p>Hallo Ada,</p>"));
+    }
+
+    #[test]
+    fn test_from_template_rejects_missing_variables() {
+        let err = EmailJob::from_template(
+            "ada@example.com",
+            "password_reset",
+            "en",
+            serde_json::json!({ "name": "Ada" }),
+        )
+        .unwrap_err();
+        assert!(err.is_permanent());
+    }
 }
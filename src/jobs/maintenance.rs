@@ -0,0 +1,313 @@
+//! Periodic maintenance tasks.
+//!
+//! Cache housekeeping runs on the API servers' singleton scheduler; database
+//! cleanup and digests run as cron jobs in the worker.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::time::Duration;
+use uuid::Uuid;
+
+use super::cron::{
+    CronJob, CronRun, CRON_JOB_ADMIN_DIGEST, CRON_JOB_PRUNE_DONE_JOBS, CRON_JOB_PURGE_DELETED_USERS,
+};
+use super::singleton::SingletonTask;
+use super::store::{JobStatus, JobStore};
+use super::{EmailJob, JobQueue};
+use crate::config::{
+    ADMIN_DIGEST_DEFAULT_PERIOD_DAYS, CACHE_KEY_USER_STATS, DEFAULT_EMAIL_LOCALE,
+    DEFAULT_USER_PURGE_BATCH_SIZE, TASK_INTERVAL_SESSION_CLEANUP_SECONDS,
+    TASK_INTERVAL_USER_STATS_SECONDS,
+};
+use crate::errors::{AppError, AppResult};
+use crate::infra::Cache;
+use crate::services::UserService;
+
+/// Outcome of a purge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    /// Users permanently deleted
+    pub purged: usize,
+    /// Transactions they were deleted in
+    pub batches: usize,
+}
+
+/// Permanently removes users soft-deleted longer than the retention period.
+///
+/// Users are purged in batches of [`Self::batch_size`] (`USER_PURGE_BATCH_SIZE`),
+/// one transaction each (see [`UserService::purge_deleted_users`]), and their
+/// cache entries are invalidated after every batch.
+pub struct PurgeDeletedUsersTask {
+    users: Arc<dyn UserService>,
+    cache: Option<Arc<Cache>>,
+    retention: chrono::Duration,
+    batch_size: u64,
+}
+
+impl PurgeDeletedUsersTask {
+    /// Fails unless `retention_days` is at least 1: a shorter retention
+    /// would purge users deleted moments ago.
+    pub fn new(users: Arc<dyn UserService>, retention_days: i64) -> AppResult<Self> {
+        let retention = chrono::Duration::try_days(retention_days)
+            .filter(|_| retention_days >= 1)
+            .ok_or_else(|| {
+                AppError::validation(format!(
+                    "Invalid retention of {} days; must be at least 1",
+                    retention_days
+                ))
+            })?;
+
+        Ok(Self {
+            users,
+            cache: None,
+            retention,
+            batch_size: DEFAULT_USER_PURGE_BATCH_SIZE,
+        })
+    }
+
+    /// Invalidate purged users in this cache.
+    pub fn with_cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Purge this many users per transaction.
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Users purged per transaction
+    pub fn batch_size(&self) -> u64 {
+        self.batch_size
+    }
+
+    /// Users deleted before this are due
+    pub fn cutoff(&self) -> DateTime<Utc> {
+        Utc::now()
+            .checked_sub_signed(self.retention)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    /// Purge every expired user, batch by batch.
+    pub async fn purge(&self) -> AppResult<PurgeReport> {
+        let cutoff = self.cutoff();
+        let mut report = PurgeReport::default();
+
+        loop {
+            let purged = self
+                .users
+                .purge_deleted_users(cutoff, self.batch_size)
+                .await?;
+            if purged.is_empty() {
+                break;
+            }
+            report.purged += purged.len();
+            report.batches += 1;
+
+            for user in &purged {
+                tracing::info!(
+                    user_id = %user.id,
+                    deleted_at = ?user.deleted_at,
+                    "Purged soft-deleted user"
+                );
+                if let Some(cache) = &self.cache {
+                    cache.invalidate_user(&user.id).await?;
+                }
+            }
+            if (purged.len() as u64) < self.batch_size {
+                break;
+            }
+        }
+
+        if report.purged > 0 {
+            tracing::info!(
+                count = report.purged,
+                batches = report.batches,
+                "Purged soft-deleted users"
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[async_trait]
+impl CronJob for PurgeDeletedUsersTask {
+    fn name(&self) -> &'static str {
+        CRON_JOB_PURGE_DELETED_USERS
+    }
+
+    async fn run(&self, _run: CronRun) -> AppResult<()> {
+        self.purge().await.map(|_| ())
+    }
+}
+
+/// Deletes finished jobs older than the retention period from the queue tables.
+pub struct PruneDoneJobsTask {
+    store: JobStore,
+    retention: chrono::Duration,
+}
+
+impl PruneDoneJobsTask {
+    pub fn new(db: DatabaseConnection, retention_days: i64) -> Self {
+        Self {
+            store: JobStore::new(db),
+            retention: chrono::Duration::days(retention_days),
+        }
+    }
+}
+
+#[async_trait]
+impl CronJob for PruneDoneJobsTask {
+    fn name(&self) -> &'static str {
+        CRON_JOB_PRUNE_DONE_JOBS
+    }
+
+    async fn run(&self, _run: CronRun) -> AppResult<()> {
+        let pruned = self
+            .store
+            .purge(JobStatus::Done, Utc::now() - self.retention)
+            .await?;
+
+        if pruned > 0 {
+            tracing::info!(count = pruned, "Pruned finished jobs");
+        }
+        Ok(())
+    }
+}
+
+/// Emails admins a summary of user activity since the previous digest.
+pub struct AdminDigestTask {
+    users: Arc<dyn UserService>,
+    jobs: Arc<dyn JobQueue>,
+}
+
+impl AdminDigestTask {
+    pub fn new(users: Arc<dyn UserService>, jobs: Arc<dyn JobQueue>) -> Self {
+        Self { users, jobs }
+    }
+}
+
+#[async_trait]
+impl CronJob for AdminDigestTask {
+    fn name(&self) -> &'static str {
+        CRON_JOB_ADMIN_DIGEST
+    }
+
+    async fn run(&self, run: CronRun) -> AppResult<()> {
+        let until = run.scheduled_at;
+        let since = run
+            .previous_at
+            .unwrap_or(until - chrono::Duration::days(ADMIN_DIGEST_DEFAULT_PERIOD_DAYS));
+        let in_period = |at: DateTime<Utc>| at > since && at <= until;
+
+        let users = self.users.list_users_with_deleted().await?;
+        let vars = json!({
+            "since": since.format("%Y-%m-%d %H:%M UTC").to_string(),
+            "new_users": users.iter().filter(|u| in_period(u.created_at)).count(),
+            "deleted_users": users.iter().filter(|u| u.deleted_at.is_some_and(in_period)).count(),
+            "active_users": users.iter().filter(|u| u.is_active()).count(),
+        });
+
+        let admins: Vec<_> = users
+            .iter()
+            .filter(|u| u.is_active() && u.is_admin())
+            .collect();
+        for admin in &admins {
+            let mut vars = vars.clone();
+            vars["name"] = json!(admin.name);
+            let job =
+                EmailJob::from_template(&admin.email, "admin_digest", DEFAULT_EMAIL_LOCALE, &vars)
+                    .map_err(|e| AppError::internal(e.to_string()))?;
+
+            // One digest per admin and occurrence, even if the run is retried
+            let id = Uuid::from_u128(admin.id.as_u128() ^ until.timestamp() as u128);
+            self.jobs.enqueue_once(id, job.into(), None).await?;
+        }
+
+        tracing::info!(admins = admins.len(), "Admin digest enqueued");
+        Ok(())
+    }
+}
+
+/// Removes session entries that were stored without an expiry.
+pub struct SessionCleanupTask {
+    cache: Arc<Cache>,
+}
+
+impl SessionCleanupTask {
+    pub fn new(cache: Arc<Cache>) -> Self {
+        Self { cache }
+    }
+}
+
+#[async_trait]
+impl SingletonTask for SessionCleanupTask {
+    fn name(&self) -> &'static str {
+        "session_cleanup"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(TASK_INTERVAL_SESSION_CLEANUP_SECONDS)
+    }
+
+    async fn run(&self) -> AppResult<()> {
+        let removed = self.cache.cleanup_sessions().await?;
+        if removed > 0 {
+            tracing::info!(count = removed, "Removed stale sessions");
+        }
+        Ok(())
+    }
+}
+
+/// Cached user statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserStats {
+    pub active: u64,
+    pub deleted: u64,
+    pub refreshed_at: DateTime<Utc>,
+}
+
+/// Recomputes user statistics and stores them in the cache.
+pub struct UserStatsTask {
+    users: Arc<dyn UserService>,
+    cache: Arc<Cache>,
+}
+
+impl UserStatsTask {
+    pub fn new(users: Arc<dyn UserService>, cache: Arc<Cache>) -> Self {
+        Self { users, cache }
+    }
+}
+
+#[async_trait]
+impl SingletonTask for UserStatsTask {
+    fn name(&self) -> &'static str {
+        "user_stats"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(TASK_INTERVAL_USER_STATS_SECONDS)
+    }
+
+    async fn run(&self) -> AppResult<()> {
+        let stats = UserStats {
+            active: self.users.count_users().await?,
+            deleted: self.users.count_deleted_users().await?,
+            refreshed_at: Utc::now(),
+        };
+
+        // Keep stats around for two refresh cycles in case a run is missed
+        self.cache
+            .set_with_ttl(
+                CACHE_KEY_USER_STATS,
+                &stats,
+                TASK_INTERVAL_USER_STATS_SECONDS * 2,
+            )
+            .await
+    }
+}
@@ -1,5 +1,34 @@
 //! Background job definitions.
 
+pub mod cron;
 mod email_job;
+pub mod maintenance;
+pub mod outbox;
+pub mod queue;
+pub mod retry;
+pub mod singleton;
+pub mod store;
+pub mod worker;
 
-pub use email_job::{email_job_handler, EmailJob};
+pub use cron::{
+    CronHandle, CronJob, CronRun, CronSchedule, CronScheduler, CronStore, MissedRunPolicy,
+};
+pub use email_job::{email_job_handler, EmailJob, Mailer};
+pub use maintenance::{
+    AdminDigestTask, PruneDoneJobsTask, PurgeDeletedUsersTask, PurgeReport, SessionCleanupTask,
+    UserStatsTask,
+};
+pub use outbox::{OutboxRelay, RelayStats};
+pub use queue::{EnqueuedJob, InMemoryJobQueue, Job, JobQueue, PostgresJobQueue};
+pub use retry::{
+    FailureKind, JobFailure, JobRetries, RetryDecision, RetryPolicy, RetrySweeper, SweepStats,
+};
+pub use singleton::{SchedulerHandle, SingletonScheduler, SingletonTask};
+pub use store::{
+    AttemptRecord, JobDetail, JobFilter, JobStatus, JobStore, JobSummary, StatusCount, Throughput,
+    WorkerInfo,
+};
+pub use worker::{QueueConfig, WorkerSettings, QUEUES};
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use queue::MockJobQueue;
@@ -0,0 +1,212 @@
+//! Outbox relay.
+//!
+//! Moves committed outbox entries to the job queue or event bus. Entries
+//! are claimed with `FOR UPDATE SKIP LOCKED`, so several relays never
+//! deliver the same entry concurrently. A failed entry is retried with
+//! exponential backoff until [`OUTBOX_MAX_ATTEMPTS`] is reached; it is then
+//! logged as exhausted and kept for [`OUTBOX_EXHAUSTED_RETENTION_HOURS`]
+//! before being pruned.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::sea_query::{Condition, LockBehavior, LockType};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+use super::queue::JobQueue;
+use super::singleton::SingletonTask;
+use crate::config::{
+    OUTBOX_EXHAUSTED_RETENTION_HOURS, OUTBOX_MAX_ATTEMPTS, OUTBOX_MAX_BACKOFF_SECONDS,
+    OUTBOX_RELAY_BATCH_SIZE, OUTBOX_RETENTION_HOURS, TASK_INTERVAL_OUTBOX_RELAY_MS,
+};
+use crate::domain::EventBus;
+use crate::errors::{AppError, AppResult};
+use crate::infra::repositories::entities::outbox::{self, Entity as OutboxEntity, Model};
+use crate::infra::OutboxMessage;
+
+/// Result of one relay pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayStats {
+    /// Entries handed to the queue or bus
+    pub dispatched: usize,
+    /// Entries that failed and will be retried
+    pub failed: usize,
+    /// Entries that failed their last attempt and will not be retried
+    pub exhausted: usize,
+}
+
+/// Delivers outbox entries to the job queue and event bus.
+pub struct OutboxRelay {
+    db: DatabaseConnection,
+    jobs: Arc<dyn JobQueue>,
+    events: Arc<dyn EventBus>,
+    batch_size: u64,
+}
+
+impl OutboxRelay {
+    pub fn new(db: DatabaseConnection, jobs: Arc<dyn JobQueue>, events: Arc<dyn EventBus>) -> Self {
+        Self {
+            db,
+            jobs,
+            events,
+            batch_size: OUTBOX_RELAY_BATCH_SIZE,
+        }
+    }
+
+    /// Set how many entries are claimed per pass.
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Relay one batch of due entries.
+    pub async fn relay_batch(&self) -> AppResult<RelayStats> {
+        let txn = self.db.begin().await?;
+        let now = Utc::now();
+
+        let entries = OutboxEntity::find()
+            .filter(outbox::Column::DispatchedAt.is_null())
+            .filter(outbox::Column::AvailableAt.lte(now))
+            .filter(outbox::Column::Attempts.lt(OUTBOX_MAX_ATTEMPTS))
+            .order_by_asc(outbox::Column::CreatedAt)
+            .limit(self.batch_size)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await?;
+
+        let mut stats = RelayStats::default();
+        for entry in entries {
+            let id = entry.id;
+            let attempts = entry.attempts + 1;
+            let outcome = self.dispatch(&entry).await;
+
+            let mut active = entry.into_active_model();
+            match outcome {
+                Ok(()) => {
+                    active.dispatched_at = Set(Some(Utc::now()));
+                    stats.dispatched += 1;
+                }
+                Err(e) if attempts >= OUTBOX_MAX_ATTEMPTS => {
+                    tracing::error!(
+                        outbox_id = %id,
+                        dedup_key = %active.dedup_key.as_ref(),
+                        attempts,
+                        error = %e,
+                        "Outbox entry exhausted its attempts and will not be relayed"
+                    );
+                    active.attempts = Set(attempts);
+                    active.last_error = Set(Some(e.to_string()));
+                    active.available_at = Set(Utc::now());
+                    stats.exhausted += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(outbox_id = %id, attempts, error = %e, "Outbox relay failed");
+                    active.attempts = Set(attempts);
+                    active.last_error = Set(Some(e.to_string()));
+                    active.available_at = Set(Utc::now() + backoff(attempts));
+                    stats.failed += 1;
+                }
+            }
+            active.update(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(stats)
+    }
+
+    /// Delete entries dispatched longer ago than the retention period, and
+    /// exhausted entries last attempted longer ago than theirs.
+    pub async fn prune(&self) -> AppResult<u64> {
+        let now = Utc::now();
+        let dispatched_cutoff = now - chrono::Duration::hours(OUTBOX_RETENTION_HOURS);
+        let exhausted_cutoff = now - chrono::Duration::hours(OUTBOX_EXHAUSTED_RETENTION_HOURS);
+        let result = OutboxEntity::delete_many()
+            .filter(
+                Condition::any()
+                    .add(outbox::Column::DispatchedAt.lt(dispatched_cutoff))
+                    .add(
+                        Condition::all()
+                            .add(outbox::Column::DispatchedAt.is_null())
+                            .add(outbox::Column::Attempts.gte(OUTBOX_MAX_ATTEMPTS))
+                            .add(outbox::Column::AvailableAt.lt(exhausted_cutoff)),
+                    ),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn dispatch(&self, entry: &Model) -> AppResult<()> {
+        let message: OutboxMessage = serde_json::from_value(entry.payload.clone())
+            .map_err(|e| AppError::internal(format!("Undecodable outbox entry: {}", e)))?;
+
+        match message {
+            // The entry id doubles as the job id, so a redelivered job is dropped
+            OutboxMessage::Job { job, run_at } => {
+                if !self.jobs.enqueue_once(entry.id, job, run_at).await? {
+                    tracing::debug!(outbox_id = %entry.id, "Outbox job was already enqueued");
+                }
+                Ok(())
+            }
+            OutboxMessage::Event { event } => self.events.publish(event).await,
+        }
+    }
+}
+
+/// Delay before the next attempt: 2^attempts seconds, capped.
+fn backoff(attempts: i32) -> chrono::Duration {
+    let seconds = 1i64
+        .checked_shl(attempts.clamp(0, 30) as u32)
+        .unwrap_or(i64::MAX)
+        .min(OUTBOX_MAX_BACKOFF_SECONDS);
+    chrono::Duration::seconds(seconds)
+}
+
+#[async_trait]
+impl SingletonTask for OutboxRelay {
+    fn name(&self) -> &'static str {
+        "outbox_relay"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_millis(TASK_INTERVAL_OUTBOX_RELAY_MS)
+    }
+
+    async fn run(&self) -> AppResult<()> {
+        // Drain the backlog, one batch per transaction
+        loop {
+            let stats = self.relay_batch().await?;
+            if stats.dispatched > 0 {
+                tracing::debug!(dispatched = stats.dispatched, "Relayed outbox entries");
+            }
+            if stats.dispatched + stats.failed + stats.exhausted < self.batch_size as usize {
+                break;
+            }
+        }
+
+        let pruned = self.prune().await?;
+        if pruned > 0 {
+            tracing::debug!(count = pruned, "Pruned outbox entries");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        assert_eq!(backoff(1), chrono::Duration::seconds(2));
+        assert_eq!(backoff(4), chrono::Duration::seconds(16));
+        assert_eq!(
+            backoff(OUTBOX_MAX_ATTEMPTS),
+            chrono::Duration::seconds(OUTBOX_MAX_BACKOFF_SECONDS)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Job producer API.
+//!
+//! [`JobQueue`] lets the API server and services push background jobs
+//! without knowing where they are stored:
+//! - [`PostgresJobQueue`] - apalis Postgres storage consumed by `jobs work`
+//! - [`InMemoryJobQueue`] - records jobs so tests can assert on them
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let job = EmailJob::from_template(&user.email, "welcome", "en", json!({ "name": user.name }))?;
+//! state.jobs.enqueue_in(job.into(), Duration::from_secs(60)).await?;
+//! ```
+
+use apalis::prelude::{Request, Storage, TaskId};
+use apalis_sql::postgres::PostgresStorage;
+use apalis_sql::sqlx::PgPool;
+use apalis_sql::Config as StorageConfig;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::EmailJob;
+use crate::config::JOB_NAME_EMAIL;
+use crate::errors::{AppError, AppResult};
+
+#[cfg(any(test, feature = "test-utils"))]
+use mockall::automock;
+
+/// Jobs that can be enqueued.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum Job {
+    Email(EmailJob),
+}
+
+impl Job {
+    /// Queue name the job is stored under
+    pub fn queue(&self) -> &'static str {
+        match self {
+            Self::Email(_) => JOB_NAME_EMAIL,
+        }
+    }
+}
+
+impl From<EmailJob> for Job {
+    fn from(job: EmailJob) -> Self {
+        Self::Email(job)
+    }
+}
+
+/// Pushes jobs to the background worker.
+///
+/// `enqueue`, `enqueue_at` and `enqueue_in` return the id of the stored job.
+#[cfg_attr(any(test, feature = "test-utils"), automock)]
+#[async_trait]
+pub trait JobQueue: Send + Sync {
+    /// Enqueue a job to run as soon as a worker is free
+    async fn enqueue(&self, job: Job) -> AppResult<String>;
+
+    /// Enqueue a job to run at `at` (or immediately if it is in the past)
+    async fn enqueue_at(&self, job: Job, at: DateTime<Utc>) -> AppResult<String>;
+
+    /// Enqueue a job to run after `delay`
+    async fn enqueue_in(&self, job: Job, delay: Duration) -> AppResult<String> {
+        let delay = chrono::Duration::from_std(delay)
+            .map_err(|_| AppError::validation("Job delay is too large"))?;
+        self.enqueue_at(job, Utc::now() + delay).await
+    }
+
+    /// Enqueue a job under a caller-chosen id, optionally delayed until `run_at`
+    ///
+    /// Returns `false` if a job with this id was already enqueued, which
+    /// makes redelivery (e.g. by the outbox relay) idempotent.
+    async fn enqueue_once(
+        &self,
+        id: Uuid,
+        job: Job,
+        run_at: Option<DateTime<Utc>>,
+    ) -> AppResult<bool>;
+}
+
+/// Storage for email jobs, shared by the producer and the worker.
+pub fn email_storage(pool: PgPool) -> PostgresStorage<EmailJob> {
+    PostgresStorage::new_with_config(pool, StorageConfig::new(JOB_NAME_EMAIL))
+}
+
+/// Job queue backed by apalis Postgres storage.
+#[derive(Clone)]
+pub struct PostgresJobQueue {
+    email: PostgresStorage<EmailJob>,
+}
+
+impl PostgresJobQueue {
+    /// Create a queue over an existing connection pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            email: email_storage(pool),
+        }
+    }
+
+    /// Create the apalis job tables if they do not exist yet.
+    pub async fn setup(pool: &PgPool) -> AppResult<()> {
+        PostgresStorage::setup(pool)
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to setup job storage: {}", e)))
+    }
+}
+
+/// apalis job ids are ULIDs; map the 128 bits of a UUID onto one.
+fn task_id(id: Uuid) -> TaskId {
+    TaskId::from_str(&ulid::Ulid::from(id.as_u128()).to_string())
+        .expect("every 128-bit value is a valid ULID")
+}
+
+fn enqueue_error(e: impl std::fmt::Display) -> AppError {
+    AppError::internal(format!("Failed to enqueue job: {}", e))
+}
+
+#[async_trait]
+impl JobQueue for PostgresJobQueue {
+    async fn enqueue(&self, job: Job) -> AppResult<String> {
+        let parts = match job {
+            Job::Email(job) => self.email.clone().push(job).await,
+        }
+        .map_err(enqueue_error)?;
+
+        tracing::debug!(job_id = %parts.task_id, "Job enqueued");
+        Ok(parts.task_id.to_string())
+    }
+
+    async fn enqueue_at(&self, job: Job, at: DateTime<Utc>) -> AppResult<String> {
+        let parts = match job {
+            Job::Email(job) => self.email.clone().schedule(job, at.timestamp()).await,
+        }
+        .map_err(enqueue_error)?;
+
+        tracing::debug!(job_id = %parts.task_id, run_at = %at, "Job scheduled");
+        Ok(parts.task_id.to_string())
+    }
+
+    async fn enqueue_once(
+        &self,
+        id: Uuid,
+        job: Job,
+        run_at: Option<DateTime<Utc>>,
+    ) -> AppResult<bool> {
+        let result = match job {
+            Job::Email(job) => {
+                let mut request = Request::new(job);
+                request.parts.task_id = task_id(id);
+                let mut storage = self.email.clone();
+                match run_at {
+                    Some(at) => storage.schedule_request(request, at.timestamp()).await,
+                    None => storage.push_request(request).await,
+                }
+            }
+        };
+
+        match result {
+            Ok(parts) => {
+                tracing::debug!(job_id = %parts.task_id, "Job enqueued");
+                Ok(true)
+            }
+            Err(apalis_sql::sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                tracing::debug!(job_id = %task_id(id), "Job already enqueued");
+                Ok(false)
+            }
+            Err(e) => Err(enqueue_error(e)),
+        }
+    }
+}
+
+/// A job recorded by [`InMemoryJobQueue`].
+#[derive(Debug, Clone)]
+pub struct EnqueuedJob {
+    pub id: String,
+    pub job: Job,
+    /// `None` when the job should run immediately
+    pub run_at: Option<DateTime<Utc>>,
+}
+
+/// Job queue that only records jobs; nothing is executed.
+#[derive(Default)]
+pub struct InMemoryJobQueue {
+    jobs: Mutex<Vec<EnqueuedJob>>,
+}
+
+impl InMemoryJobQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Jobs enqueued so far, oldest first
+    pub fn jobs(&self) -> Vec<EnqueuedJob> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Email jobs enqueued so far
+    pub fn emails(&self) -> Vec<EmailJob> {
+        self.jobs()
+            .into_iter()
+            .map(|enqueued| match enqueued.job {
+                Job::Email(job) => job,
+            })
+            .collect()
+    }
+
+    /// Remove and return all recorded jobs
+    pub fn take(&self) -> Vec<EnqueuedJob> {
+        std::mem::take(&mut *self.jobs.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn record(&self, id: String, job: Job, run_at: Option<DateTime<Utc>>) -> bool {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if jobs.iter().any(|enqueued| enqueued.id == id) {
+            return false;
+        }
+        jobs.push(EnqueuedJob { id, job, run_at });
+        true
+    }
+}
+
+#[async_trait]
+impl JobQueue for InMemoryJobQueue {
+    async fn enqueue(&self, job: Job) -> AppResult<String> {
+        let id = Uuid::new_v4().to_string();
+        self.record(id.clone(), job, None);
+        Ok(id)
+    }
+
+    async fn enqueue_at(&self, job: Job, at: DateTime<Utc>) -> AppResult<String> {
+        let id = Uuid::new_v4().to_string();
+        self.record(id.clone(), job, Some(at));
+        Ok(id)
+    }
+
+    async fn enqueue_once(
+        &self,
+        id: Uuid,
+        job: Job,
+        run_at: Option<DateTime<Utc>>,
+    ) -> AppResult<bool> {
+        Ok(self.record(id.to_string(), job, run_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_queue_records_jobs() {
+        let queue = InMemoryJobQueue::new();
+        let before = Utc::now();
+
+        let first = queue
+            .enqueue(EmailJob::new("a@example.com", "Now", "Body").into())
+            .await
+            .unwrap();
+        queue
+            .enqueue_in(
+                EmailJob::new("b@example.com", "Later", "Body").into(),
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+
+        let jobs = queue.jobs();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].id, first);
+        assert!(jobs[0].run_at.is_none());
+        let run_at = jobs[1].run_at.unwrap();
+        assert!(run_at >= before + chrono::Duration::seconds(60));
+
+        let emails = queue.emails();
+        assert_eq!(emails[1].to, "b@example.com");
+
+        assert_eq!(queue.take().len(), 2);
+        assert!(queue.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_once_is_idempotent() {
+        let queue = InMemoryJobQueue::new();
+        let id = Uuid::new_v4();
+        let job: Job = EmailJob::new("a@example.com", "Hi", "Body").into();
+
+        assert!(queue.enqueue_once(id, job.clone(), None).await.unwrap());
+        assert!(!queue.enqueue_once(id, job, None).await.unwrap());
+        assert_eq!(queue.jobs().len(), 1);
+        assert_eq!(queue.jobs()[0].id, id.to_string());
+    }
+
+    #[test]
+    fn test_task_id_is_stable_per_uuid() {
+        let id = Uuid::new_v4();
+        assert_eq!(task_id(id), task_id(id));
+        assert_eq!(task_id(id).inner().0, id.as_u128());
+    }
+
+    #[test]
+    fn test_job_queue_names() {
+        let job: Job = EmailJob::new("a@example.com", "Hi", "Body").into();
+        assert_eq!(job.queue(), JOB_NAME_EMAIL);
+    }
+}
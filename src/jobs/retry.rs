@@ -0,0 +1,507 @@
+//! Retry policies and dead-lettering for queued jobs.
+//!
+//! apalis marks a job `Failed` (retryable error) or `Killed` (aborted) but
+//! never runs it again. Handlers report failures to [`JobRetries`], which
+//! records the attempt in `job_attempts` and, if the job type's
+//! [`RetryPolicy`] allows another attempt, when to retry it.
+//! [`RetrySweeper`] then puts due jobs back to `Pending` and moves the rest,
+//! with their attempt history, to `dead_letter_jobs`.
+//!
+//! Policies can be changed per job type with environment variables, using
+//! the part of the job name before `::` (`email::send` -> `EMAIL`):
+//! - `JOB_RETRY_EMAIL_MAX_ATTEMPTS` - attempts including the first one
+//! - `JOB_RETRY_EMAIL_BASE_DELAY_SECONDS` / `JOB_RETRY_EMAIL_MAX_DELAY_SECONDS`
+//! - `JOB_RETRY_EMAIL_NON_RETRYABLE` - comma-separated [`FailureKind`]s
+
+use apalis::prelude::{Attempt, Error as JobError, TaskId};
+use chrono::Utc;
+use rand::Rng;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, Set, Statement, TransactionTrait,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::Duration;
+use uuid::Uuid;
+
+use crate::config::{
+    DEFAULT_JOB_MAX_ATTEMPTS, EMAIL_JOB_MAX_ATTEMPTS, JOB_NAME_EMAIL, JOB_RETRY_BASE_DELAY_SECONDS,
+    JOB_RETRY_JITTER, JOB_RETRY_MAX_DELAY_SECONDS, TASK_INTERVAL_RETRY_SWEEP_SECONDS,
+};
+use crate::errors::{AppError, AppResult};
+use crate::infra::mailer::EmailError;
+use crate::infra::repositories::entities::job_attempt;
+
+/// Broad class of a job error, used to decide whether to retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    /// Retrying cannot succeed (rejected recipient, invalid payload)
+    Permanent,
+    /// The same job may succeed later (timeout, connection refused)
+    Transient,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Permanent => "permanent",
+            Self::Transient => "transient",
+        }
+    }
+}
+
+impl FromStr for FailureKind {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "permanent" => Ok(Self::Permanent),
+            "transient" => Ok(Self::Transient),
+            other => Err(AppError::validation(format!(
+                "Unknown failure kind '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error a job handler can report to [`JobRetries`].
+pub trait JobFailure: std::error::Error + Send + Sync + 'static {
+    fn kind(&self) -> FailureKind;
+}
+
+impl JobFailure for EmailError {
+    fn kind(&self) -> FailureKind {
+        if self.is_permanent() {
+            FailureKind::Permanent
+        } else {
+            FailureKind::Transient
+        }
+    }
+}
+
+/// Outcome of a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Run the job again after the delay
+    Retry(Duration),
+    /// Move the job to the dead-letter table
+    GiveUp,
+}
+
+/// How often and how fast a job type is retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Random spread of each delay, as a fraction of it
+    pub jitter: f64,
+    /// Failures that go straight to the dead-letter table
+    pub non_retryable: Vec<FailureKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_JOB_MAX_ATTEMPTS,
+            base_delay: Duration::from_secs(JOB_RETRY_BASE_DELAY_SECONDS),
+            max_delay: Duration::from_secs(JOB_RETRY_MAX_DELAY_SECONDS),
+            jitter: JOB_RETRY_JITTER,
+            non_retryable: vec![FailureKind::Permanent],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Set the number of attempts (at least one)
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the first retry delay and its upper bound
+    pub fn with_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay.max(base_delay);
+        self
+    }
+
+    /// Set the random spread of delays (clamped to 0..=1)
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the failure kinds that are never retried
+    pub fn with_non_retryable(mut self, kinds: Vec<FailureKind>) -> Self {
+        self.non_retryable = kinds;
+        self
+    }
+
+    /// Built-in policy for a job type with environment overrides applied.
+    pub fn for_job(job_type: &str) -> AppResult<Self> {
+        let mut policy = match job_type {
+            JOB_NAME_EMAIL => Self::default().with_max_attempts(EMAIL_JOB_MAX_ATTEMPTS),
+            _ => Self::default(),
+        };
+
+        let prefix = format!(
+            "JOB_RETRY_{}",
+            job_type
+                .split("::")
+                .next()
+                .unwrap_or(job_type)
+                .to_uppercase()
+        );
+        if let Some(attempts) = env_parse(&format!("{}_MAX_ATTEMPTS", prefix))? {
+            policy = policy.with_max_attempts(attempts);
+        }
+        let base = env_parse(&format!("{}_BASE_DELAY_SECONDS", prefix))?
+            .map(Duration::from_secs)
+            .unwrap_or(policy.base_delay);
+        let max = env_parse(&format!("{}_MAX_DELAY_SECONDS", prefix))?
+            .map(Duration::from_secs)
+            .unwrap_or(policy.max_delay);
+        policy = policy.with_backoff(base, max);
+        if let Ok(kinds) = std::env::var(format!("{}_NON_RETRYABLE", prefix)) {
+            policy = policy.with_non_retryable(
+                kinds
+                    .split(',')
+                    .filter(|k| !k.trim().is_empty())
+                    .map(str::parse)
+                    .collect::<AppResult<_>>()?,
+            );
+        }
+
+        Ok(policy)
+    }
+
+    /// Delay before retrying after `attempt` failed, without jitter:
+    /// `base * 2^(attempt - 1)`, capped at `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Backoff spread by `sample` (in `-1.0..=1.0`) times the jitter.
+    fn jittered(&self, attempt: u32, sample: f64) -> Duration {
+        self.backoff(attempt)
+            .mul_f64(1.0 + self.jitter * sample.clamp(-1.0, 1.0))
+    }
+
+    /// Decide what happens after `attempt` (1-based) failed with `kind`.
+    pub fn decide(&self, attempt: u32, kind: FailureKind) -> RetryDecision {
+        if self.non_retryable.contains(&kind) || attempt >= self.max_attempts {
+            return RetryDecision::GiveUp;
+        }
+        let sample = rand::thread_rng().gen_range(-1.0..=1.0);
+        RetryDecision::Retry(self.jittered(attempt, sample))
+    }
+}
+
+pub(crate) fn env_parse<T: FromStr>(var: &str) -> AppResult<Option<T>> {
+    match std::env::var(var) {
+        Ok(value) => {
+            value.trim().parse().map(Some).map_err(|_| {
+                AppError::validation(format!("Invalid value for {}: '{}'", var, value))
+            })
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Records failed attempts and applies retry policies; shared by handlers
+/// as worker data.
+#[derive(Clone)]
+pub struct JobRetries {
+    db: DatabaseConnection,
+    policies: Arc<HashMap<String, RetryPolicy>>,
+}
+
+impl JobRetries {
+    /// Use the default policy for every job type.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            policies: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// Policies of all built-in job types, with environment overrides.
+    pub fn from_env(db: DatabaseConnection) -> AppResult<Self> {
+        Ok(Self::new(db).with_policy(JOB_NAME_EMAIL, RetryPolicy::for_job(JOB_NAME_EMAIL)?))
+    }
+
+    /// Use `policy` for `job_type`.
+    pub fn with_policy(mut self, job_type: impl Into<String>, policy: RetryPolicy) -> Self {
+        Arc::make_mut(&mut self.policies).insert(job_type.into(), policy);
+        self
+    }
+
+    /// Policy applied to `job_type`.
+    pub fn policy(&self, job_type: &str) -> RetryPolicy {
+        self.policies.get(job_type).cloned().unwrap_or_default()
+    }
+
+    /// Record a failed attempt and turn the error into the job result.
+    ///
+    /// Returns `Failed` when the job will be retried and `Abort` when it
+    /// goes to the dead-letter table.
+    pub async fn on_failure<E: JobFailure>(
+        &self,
+        job_type: &str,
+        job_id: &TaskId,
+        attempt: &Attempt,
+        error: E,
+    ) -> JobError {
+        let attempt = attempt.current() as u32 + 1;
+        let kind = error.kind();
+        let decision = self.policy(job_type).decide(attempt, kind);
+        let now = Utc::now();
+        let retry_at = match decision {
+            RetryDecision::Retry(delay) => {
+                Some(now + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero()))
+            }
+            RetryDecision::GiveUp => None,
+        };
+
+        let row = job_attempt::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            job_id: Set(job_id.to_string()),
+            job_type: Set(job_type.to_string()),
+            attempt: Set(attempt as i32),
+            error_kind: Set(kind.to_string()),
+            error: Set(error.to_string()),
+            retry_at: Set(retry_at),
+            failed_at: Set(now),
+        };
+        // Without the row the sweeper dead-letters the job instead of retrying it
+        if let Err(e) = row.insert(&self.db).await {
+            tracing::error!(job_id = %job_id, error = %e, "Failed to record job attempt");
+        }
+
+        let source = Arc::new(Box::new(error) as Box<dyn std::error::Error + Send + Sync>);
+        match retry_at {
+            Some(at) => {
+                tracing::warn!(
+                    job_id = %job_id, job_type, attempt, kind = %kind, error = %source,
+                    retry_at = %at, "Job failed, will retry"
+                );
+                JobError::Failed(source)
+            }
+            None => {
+                tracing::error!(
+                    job_id = %job_id, job_type, attempt, kind = %kind, error = %source,
+                    "Job failed, moving to dead-letter queue"
+                );
+                JobError::Abort(source)
+            }
+        }
+    }
+}
+
+/// Result of a [`RetrySweeper::sweep`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SweepStats {
+    pub retried: u64,
+    pub dead_lettered: u64,
+}
+
+/// Requeue failed jobs whose latest attempt has a retry time.
+const REQUEUE_SQL: &str = r#"
+UPDATE apalis.jobs j
+SET status = 'Pending', run_at = a.retry_at, lock_by = NULL, lock_at = NULL, done_at = NULL
+FROM job_attempts a
+WHERE a.job_id = j.id
+  AND a.attempt = j.attempts
+  AND a.retry_at IS NOT NULL
+  AND j.status = 'Failed'
+"#;
+
+/// Move aborted jobs and failed jobs without a scheduled retry.
+const DEAD_LETTER_SQL: &str = r#"
+WITH dead AS (
+    DELETE FROM apalis.jobs j
+    WHERE j.status = 'Killed'
+       OR (j.status = 'Failed' AND NOT EXISTS (
+            SELECT 1 FROM job_attempts a
+            WHERE a.job_id = j.id AND a.attempt = j.attempts AND a.retry_at IS NOT NULL))
+    RETURNING j.id, j.job_type, j.job, j.attempts, j.last_error, j.done_at
+)
+INSERT INTO dead_letter_jobs
+    (id, job_id, job_type, payload, attempts, error_kind, last_error, history, failed_at)
+SELECT
+    gen_random_uuid(), d.id, d.job_type, d.job, d.attempts, last.error_kind,
+    COALESCE(last.error, d.last_error),
+    COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'attempt', a.attempt, 'kind', a.error_kind, 'error', a.error, 'failed_at', a.failed_at
+        ) ORDER BY a.attempt)
+        FROM job_attempts a WHERE a.job_id = d.id
+    ), '[]'::jsonb),
+    COALESCE(d.done_at, now())
+FROM dead d
+LEFT JOIN LATERAL (
+    SELECT a.error_kind, a.error FROM job_attempts a
+    WHERE a.job_id = d.id ORDER BY a.attempt DESC LIMIT 1
+) last ON true
+ON CONFLICT (job_id) DO NOTHING
+"#;
+
+/// Drop attempt history of jobs that succeeded or were dead-lettered.
+const PRUNE_ATTEMPTS_SQL: &str = r#"
+DELETE FROM job_attempts a
+WHERE NOT EXISTS (SELECT 1 FROM apalis.jobs j WHERE j.id = a.job_id AND j.status <> 'Done')
+"#;
+
+/// Applies retry decisions to the queue tables.
+#[derive(Clone)]
+pub struct RetrySweeper {
+    db: DatabaseConnection,
+}
+
+impl RetrySweeper {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Requeue retryable jobs and dead-letter the rest, in one transaction.
+    pub async fn sweep(&self) -> AppResult<SweepStats> {
+        let txn = self.db.begin().await?;
+        let backend = txn.get_database_backend();
+        let retried = txn
+            .execute(Statement::from_string(backend, REQUEUE_SQL))
+            .await?
+            .rows_affected();
+        let dead_lettered = txn
+            .execute(Statement::from_string(backend, DEAD_LETTER_SQL))
+            .await?
+            .rows_affected();
+        txn.execute(Statement::from_string(backend, PRUNE_ATTEMPTS_SQL))
+            .await?;
+        txn.commit().await?;
+
+        Ok(SweepStats {
+            retried,
+            dead_lettered,
+        })
+    }
+
+    /// Sweep every few seconds until `stop` is set.
+    pub async fn run(self, mut stop: watch::Receiver<bool>) {
+        let interval = Duration::from_secs(TASK_INTERVAL_RETRY_SWEEP_SECONDS);
+        while !*stop.borrow() {
+            match self.sweep().await {
+                Ok(stats) if stats != SweepStats::default() => tracing::info!(
+                    retried = stats.retried,
+                    dead_lettered = stats.dead_lettered,
+                    "Swept failed jobs"
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!(error = %e, "Failed to sweep failed jobs"),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = stop.changed() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::default()
+            .with_max_attempts(4)
+            .with_backoff(Duration::from_secs(10), Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_secs(10));
+        assert_eq!(policy.backoff(2), Duration::from_secs(20));
+        assert_eq!(policy.backoff(3), Duration::from_secs(40));
+        assert_eq!(policy.backoff(4), Duration::from_secs(60));
+        assert_eq!(policy.backoff(100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let policy = policy().with_jitter(0.2);
+        assert_eq!(policy.jittered(2, -1.0), Duration::from_secs(16));
+        assert_eq!(policy.jittered(2, 1.0), Duration::from_secs(24));
+
+        for _ in 0..100 {
+            match policy.decide(2, FailureKind::Transient) {
+                RetryDecision::Retry(delay) => {
+                    assert!(delay >= Duration::from_secs(16) && delay <= Duration::from_secs(24))
+                }
+                RetryDecision::GiveUp => panic!("expected a retry"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts_or_on_non_retryable() {
+        let policy = policy();
+        assert!(matches!(
+            policy.decide(3, FailureKind::Transient),
+            RetryDecision::Retry(_)
+        ));
+        assert_eq!(
+            policy.decide(4, FailureKind::Transient),
+            RetryDecision::GiveUp
+        );
+        assert_eq!(
+            policy.decide(1, FailureKind::Permanent),
+            RetryDecision::GiveUp
+        );
+
+        let lenient = policy.with_non_retryable(vec![]);
+        assert!(matches!(
+            lenient.decide(1, FailureKind::Permanent),
+            RetryDecision::Retry(_)
+        ));
+    }
+
+    #[test]
+    fn test_email_errors_are_classified() {
+        assert_eq!(
+            EmailError::Permanent("550".into()).kind(),
+            FailureKind::Permanent
+        );
+        assert_eq!(
+            EmailError::Transient("timeout".into()).kind(),
+            FailureKind::Transient
+        );
+    }
+
+    #[test]
+    fn test_policy_for_job_applies_defaults() {
+        let policy = RetryPolicy::for_job(JOB_NAME_EMAIL).unwrap();
+        assert_eq!(policy.max_attempts, EMAIL_JOB_MAX_ATTEMPTS);
+        assert_eq!(
+            RetryPolicy::for_job("report::build").unwrap().max_attempts,
+            DEFAULT_JOB_MAX_ATTEMPTS
+        );
+    }
+}
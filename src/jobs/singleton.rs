@@ -0,0 +1,135 @@
+//! Singleton background tasks.
+//!
+//! Periodic tasks that must run on exactly one API instance. Every instance
+//! schedules the tasks, but a tick only runs the task while the instance
+//! holds leadership.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+use crate::errors::AppResult;
+use crate::infra::leader::{LeaderElection, Leadership};
+
+/// A periodic task that runs only on the current leader.
+#[async_trait]
+pub trait SingletonTask: Send + Sync {
+    /// Task name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Time between runs
+    fn interval(&self) -> Duration;
+
+    /// Run the task once
+    async fn run(&self) -> AppResult<()>;
+}
+
+/// Scheduler that runs registered tasks on the elected leader.
+pub struct SingletonScheduler {
+    election: LeaderElection,
+    tasks: Vec<Arc<dyn SingletonTask>>,
+}
+
+impl SingletonScheduler {
+    /// Create a scheduler driven by the given election.
+    pub fn new(election: LeaderElection) -> Self {
+        Self {
+            election,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Register a task.
+    pub fn register(mut self, task: impl SingletonTask + 'static) -> Self {
+        self.tasks.push(Arc::new(task));
+        self
+    }
+
+    /// Start campaigning for leadership and scheduling tasks.
+    pub fn start(self) -> SchedulerHandle {
+        let (election_tx, election_rx) = watch::channel(false);
+        let (stop_tx, stop_rx) = watch::channel(false);
+
+        let leadership = self.election.spawn(election_rx);
+
+        let workers = self
+            .tasks
+            .into_iter()
+            .map(|task| {
+                let leader = leadership.watch();
+                let stop = stop_rx.clone();
+                tokio::spawn(run_task(task, leader, stop))
+            })
+            .collect();
+
+        SchedulerHandle {
+            leadership,
+            workers,
+            election_tx,
+            stop_tx,
+        }
+    }
+}
+
+/// Handle to a running scheduler.
+pub struct SchedulerHandle {
+    leadership: Leadership,
+    workers: Vec<JoinHandle<()>>,
+    election_tx: watch::Sender<bool>,
+    stop_tx: watch::Sender<bool>,
+}
+
+impl SchedulerHandle {
+    /// Whether this instance currently runs the singleton tasks.
+    pub fn is_leader(&self) -> bool {
+        self.leadership.is_leader()
+    }
+
+    /// Stop scheduling, let in-flight runs finish, then hand off leadership.
+    pub async fn shutdown(self) {
+        self.stop_tx.send_replace(true);
+        for worker in self.workers {
+            if let Err(e) = worker.await {
+                tracing::error!(error = %e, "Singleton task panicked");
+            }
+        }
+
+        self.election_tx.send_replace(true);
+        self.leadership.stopped().await;
+        tracing::info!("Singleton scheduler stopped");
+    }
+}
+
+/// Tick loop for a single task.
+async fn run_task(
+    task: Arc<dyn SingletonTask>,
+    leader: watch::Receiver<bool>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let mut ticker = interval(task.interval());
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = stop.changed() => break,
+        }
+
+        if !*leader.borrow() {
+            continue;
+        }
+
+        let started = Instant::now();
+        match task.run().await {
+            Ok(()) => tracing::debug!(
+                task = task.name(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "Singleton task completed"
+            ),
+            Err(e) => tracing::error!(task = task.name(), error = %e, "Singleton task failed"),
+        }
+    }
+}
@@ -0,0 +1,522 @@
+//! Read and manage stored jobs.
+//!
+//! [`JobStore`] queries the apalis job table together with
+//! `dead_letter_jobs`, so a dead-lettered job keeps showing up (with status
+//! `Dead`) under its original id. Shared by the `jobs` command and the
+//! admin job API.
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryResult, Statement, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::errors::{AppError, AppResult};
+use crate::infra::repositories::entities::dead_letter_job;
+
+/// Status of a stored job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    /// Failed and waiting for the retry sweeper
+    Failed,
+    /// Aborted and waiting to be dead-lettered
+    Killed,
+    /// Canceled by an operator before it ran
+    Canceled,
+    /// Moved to the dead-letter table
+    Dead,
+}
+
+impl JobStatus {
+    pub const ALL: [JobStatus; 7] = [
+        Self::Pending,
+        Self::Running,
+        Self::Done,
+        Self::Failed,
+        Self::Killed,
+        Self::Canceled,
+        Self::Dead,
+    ];
+
+    /// Name stored in the `status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Running => "Running",
+            Self::Done => "Done",
+            Self::Failed => "Failed",
+            Self::Killed => "Killed",
+            Self::Canceled => "Canceled",
+            Self::Dead => "Dead",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| AppError::validation(format!("Unknown job status '{}'", s)))
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Number of jobs of one type in one status.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StatusCount {
+    pub job_type: String,
+    pub status: String,
+    pub count: i64,
+}
+
+/// A job as listed by [`JobStore::list`].
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobSummary {
+    pub id: String,
+    pub job_type: String,
+    pub status: String,
+    pub attempts: i32,
+    /// When the job is due; `None` for dead-lettered jobs
+    pub run_at: Option<DateTime<Utc>>,
+    pub done_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// A failed attempt of a job.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttemptRecord {
+    pub attempt: i32,
+    pub kind: String,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// A job with its arguments and attempt history.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobDetail {
+    #[serde(flatten)]
+    pub summary: JobSummary,
+    pub lock_by: Option<String>,
+    pub lock_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub history: Vec<AttemptRecord>,
+}
+
+/// Jobs of one type finished in a time window.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Throughput {
+    pub job_type: String,
+    pub completed: i64,
+    pub canceled: i64,
+    pub dead_lettered: i64,
+    /// Mean time from pickup to completion of completed jobs
+    pub avg_runtime_ms: Option<f64>,
+}
+
+/// A worker that sent a heartbeat recently.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerInfo {
+    pub id: String,
+    /// Job type the worker consumes
+    pub job_type: String,
+    pub last_seen: DateTime<Utc>,
+    /// Jobs the worker is running right now
+    pub running: i64,
+}
+
+/// Filter for [`JobStore::list`].
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    pub status: Option<JobStatus>,
+    pub job_type: Option<String>,
+    pub limit: u64,
+    pub offset: u64,
+}
+
+/// Queue jobs and dead letters as one relation.
+const ALL_JOBS: &str = r#"(
+    SELECT id, job_type, status, attempts, run_at, done_at, last_error, lock_by, lock_at, job AS payload
+    FROM apalis.jobs
+    UNION ALL
+    SELECT job_id, job_type, 'Dead', attempts, NULL, failed_at, last_error, NULL, NULL, payload
+    FROM dead_letter_jobs
+) AS all_jobs"#;
+
+/// Requeue failed, aborted, canceled and dead-lettered jobs ($1 = id or all)
+/// with a fresh attempt budget.
+const REQUEUE_SQL: &str = r#"
+WITH requeued AS (
+    UPDATE apalis.jobs
+    SET status = 'Pending', run_at = now(), attempts = 0, last_error = NULL,
+        lock_by = NULL, lock_at = NULL, done_at = NULL
+    WHERE status IN ('Failed', 'Killed', 'Canceled') AND ($1::text IS NULL OR id = $1)
+    RETURNING id
+), revived AS (
+    DELETE FROM dead_letter_jobs d
+    WHERE ($1::text IS NULL OR d.job_id = $1)
+      AND NOT EXISTS (SELECT 1 FROM apalis.jobs j WHERE j.id = d.job_id)
+    RETURNING d.job_id, d.job_type, d.payload
+), inserted AS (
+    INSERT INTO apalis.jobs (job, id, job_type, status, attempts, run_at)
+    SELECT payload, job_id, job_type, 'Pending', 0, now() FROM revived
+    RETURNING id
+), cleared AS (
+    DELETE FROM job_attempts
+    WHERE job_id IN (SELECT id FROM requeued UNION ALL SELECT id FROM inserted)
+)
+SELECT (SELECT COUNT(*) FROM requeued) + (SELECT COUNT(*) FROM inserted) AS count
+"#;
+
+/// Jobs finished per type since $1.
+const THROUGHPUT_SQL: &str = r#"
+SELECT job_type,
+       COUNT(*) FILTER (WHERE status = 'Done') AS completed,
+       COUNT(*) FILTER (WHERE status = 'Canceled') AS canceled,
+       COUNT(*) FILTER (WHERE status = 'Dead') AS dead_lettered,
+       (AVG(EXTRACT(EPOCH FROM done_at - lock_at) * 1000) FILTER (WHERE status = 'Done'))::float8
+           AS avg_runtime_ms
+FROM {all_jobs}
+WHERE done_at >= $1
+GROUP BY job_type
+ORDER BY job_type
+"#;
+
+/// Workers seen since $1 with the number of jobs they are running.
+const WORKERS_SQL: &str = r#"
+SELECT w.id, w.worker_type AS job_type, w.last_seen,
+       (SELECT COUNT(*) FROM apalis.jobs j WHERE j.lock_by = w.id AND j.status = 'Running')
+           AS running
+FROM apalis.workers w
+WHERE w.last_seen >= $1
+ORDER BY w.worker_type, w.id
+"#;
+
+/// Queries over the job tables.
+#[derive(Clone)]
+pub struct JobStore {
+    db: DatabaseConnection,
+}
+
+impl JobStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Whether the apalis job tables exist yet.
+    pub async fn is_initialized(&self) -> AppResult<bool> {
+        // apalis storage exists for PostgreSQL only
+        if self.db.get_database_backend() != DatabaseBackend::Postgres {
+            return Ok(false);
+        }
+        let row = self
+            .query_one(
+                "SELECT EXISTS(SELECT 1 FROM information_schema.schemata WHERE schema_name = 'apalis') AS exists",
+                [],
+            )
+            .await?;
+        Ok(row
+            .and_then(|r| r.try_get::<bool>("", "exists").ok())
+            .unwrap_or(false))
+    }
+
+    /// Job counts per type and status.
+    pub async fn counts(&self) -> AppResult<Vec<StatusCount>> {
+        let sql = format!(
+            "SELECT job_type, status, COUNT(*)::bigint AS count FROM {} GROUP BY job_type, status ORDER BY job_type, status",
+            ALL_JOBS
+        );
+        self.query_all(&sql, [])
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(StatusCount {
+                    job_type: row.try_get("", "job_type")?,
+                    status: row.try_get("", "status")?,
+                    count: row.try_get("", "count")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Jobs matching `filter`, most recent first.
+    pub async fn list(&self, filter: &JobFilter) -> AppResult<Vec<JobSummary>> {
+        let sql = format!(
+            "SELECT * FROM {} \
+             WHERE ($1::text IS NULL OR status = $1) AND ($2::text IS NULL OR job_type = $2) \
+             ORDER BY COALESCE(done_at, run_at) DESC, id DESC LIMIT $3 OFFSET $4",
+            ALL_JOBS
+        );
+        self.query_all(
+            &sql,
+            [
+                filter.status.map(|s| s.as_str().to_string()).into(),
+                filter.job_type.clone().into(),
+                (filter.limit as i64).into(),
+                (filter.offset as i64).into(),
+            ],
+        )
+        .await?
+        .iter()
+        .map(summary)
+        .collect()
+    }
+
+    /// Number of jobs matching `filter`, ignoring its limit and offset.
+    pub async fn count(&self, filter: &JobFilter) -> AppResult<u64> {
+        let sql = format!(
+            "SELECT COUNT(*)::bigint AS count FROM {} \
+             WHERE ($1::text IS NULL OR status = $1) AND ($2::text IS NULL OR job_type = $2)",
+            ALL_JOBS
+        );
+        let row = self
+            .query_one(
+                &sql,
+                [
+                    filter.status.map(|s| s.as_str().to_string()).into(),
+                    filter.job_type.clone().into(),
+                ],
+            )
+            .await?;
+        Ok(row
+            .and_then(|r| r.try_get::<i64>("", "count").ok())
+            .unwrap_or(0) as u64)
+    }
+
+    /// Jobs finished per type since `since`.
+    pub async fn throughput(&self, since: DateTime<Utc>) -> AppResult<Vec<Throughput>> {
+        let sql = THROUGHPUT_SQL.replace("{all_jobs}", ALL_JOBS);
+        self.query_all(&sql, [since.into()])
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(Throughput {
+                    job_type: row.try_get("", "job_type")?,
+                    completed: row.try_get("", "completed")?,
+                    canceled: row.try_get("", "canceled")?,
+                    dead_lettered: row.try_get("", "dead_lettered")?,
+                    avg_runtime_ms: row.try_get("", "avg_runtime_ms")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Workers with a heartbeat since `since`.
+    pub async fn workers(&self, since: DateTime<Utc>) -> AppResult<Vec<WorkerInfo>> {
+        self.query_all(WORKERS_SQL, [since.into()])
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(WorkerInfo {
+                    id: row.try_get("", "id")?,
+                    job_type: row.try_get("", "job_type")?,
+                    last_seen: row.try_get("", "last_seen")?,
+                    running: row.try_get("", "running")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Clear the heartbeat of stopped workers.
+    ///
+    /// They stop being listed right away, and jobs they left locked are
+    /// requeued by the next worker of the same type instead of after
+    /// apalis' orphan timeout.
+    pub async fn mark_workers_stopped(&self, ids: &[String]) -> AppResult<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let placeholders = (1..=ids.len())
+            .map(|i| format!("${}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                self.db.get_database_backend(),
+                format!(
+                    "UPDATE apalis.workers SET last_seen = to_timestamp(0) WHERE id IN ({})",
+                    placeholders
+                ),
+                ids.iter().map(|id| Value::from(id.clone())),
+            ))
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// A job with its payload and attempt history.
+    pub async fn get(&self, id: &str) -> AppResult<Option<JobDetail>> {
+        let sql = format!("SELECT * FROM {} WHERE id = $1", ALL_JOBS);
+        let Some(row) = self.query_one(&sql, [id.into()]).await? else {
+            return Ok(None);
+        };
+
+        let history = self
+            .query_all(
+                "SELECT attempt, error_kind, error, failed_at FROM job_attempts WHERE job_id = $1 ORDER BY attempt",
+                [id.into()],
+            )
+            .await?
+            .iter()
+            .map(|r| {
+                Ok(AttemptRecord {
+                    attempt: r.try_get("", "attempt")?,
+                    kind: r.try_get("", "error_kind")?,
+                    error: r.try_get("", "error")?,
+                    failed_at: r.try_get("", "failed_at")?,
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        let summary = summary(&row)?;
+        // Attempt rows of dead-lettered jobs live in their history column
+        let history = if history.is_empty() && summary.status == JobStatus::Dead.as_str() {
+            dead_letter_job::Entity::find()
+                .filter(dead_letter_job::Column::JobId.eq(id))
+                .one(&self.db)
+                .await?
+                .and_then(|dead| serde_json::from_value(dead.history).ok())
+                .unwrap_or_default()
+        } else {
+            history
+        };
+
+        Ok(Some(JobDetail {
+            summary,
+            lock_by: row.try_get("", "lock_by")?,
+            lock_at: row.try_get("", "lock_at")?,
+            payload: row.try_get("", "payload")?,
+            history,
+        }))
+    }
+
+    /// Requeue a failed, canceled or dead-lettered job; `false` if there is
+    /// no such job in one of those states.
+    pub async fn retry(&self, id: &str) -> AppResult<bool> {
+        Ok(self.requeue(Some(id)).await? > 0)
+    }
+
+    /// Requeue every failed, canceled and dead-lettered job.
+    pub async fn retry_all_failed(&self) -> AppResult<u64> {
+        self.requeue(None).await
+    }
+
+    async fn requeue(&self, id: Option<&str>) -> AppResult<u64> {
+        let row = self
+            .query_one(REQUEUE_SQL, [id.map(str::to_string).into()])
+            .await?;
+        Ok(row
+            .and_then(|r| r.try_get::<i64>("", "count").ok())
+            .unwrap_or(0) as u64)
+    }
+
+    /// Cancel a job that has not started; `false` if it is not pending.
+    pub async fn cancel(&self, id: &str) -> AppResult<bool> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                self.db.get_database_backend(),
+                "UPDATE apalis.jobs SET status = 'Canceled', done_at = now(), last_error = 'Canceled by operator' \
+                 WHERE id = $1 AND status = 'Pending'",
+                [id.into()],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete jobs in `status` that finished (or were due) before `before`.
+    ///
+    /// Running jobs cannot be purged.
+    pub async fn purge(&self, status: JobStatus, before: DateTime<Utc>) -> AppResult<u64> {
+        let (sql, values): (&str, Vec<Value>) = match status {
+            JobStatus::Running => {
+                return Err(AppError::validation("Running jobs cannot be purged"));
+            }
+            JobStatus::Dead => (
+                "DELETE FROM dead_letter_jobs WHERE failed_at < $1",
+                vec![before.into()],
+            ),
+            _ => (
+                "DELETE FROM apalis.jobs WHERE status = $1 AND COALESCE(done_at, run_at) < $2",
+                vec![status.as_str().into(), before.into()],
+            ),
+        };
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                self.db.get_database_backend(),
+                sql,
+                values,
+            ))
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn query_one(
+        &self,
+        sql: &str,
+        values: impl IntoIterator<Item = Value>,
+    ) -> AppResult<Option<QueryResult>> {
+        Ok(self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                self.db.get_database_backend(),
+                sql,
+                values,
+            ))
+            .await?)
+    }
+
+    async fn query_all(
+        &self,
+        sql: &str,
+        values: impl IntoIterator<Item = Value>,
+    ) -> AppResult<Vec<QueryResult>> {
+        Ok(self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                self.db.get_database_backend(),
+                sql,
+                values,
+            ))
+            .await?)
+    }
+}
+
+fn summary(row: &QueryResult) -> AppResult<JobSummary> {
+    Ok(JobSummary {
+        id: row.try_get("", "id")?,
+        job_type: row.try_get("", "job_type")?,
+        status: row.try_get("", "status")?,
+        attempts: row.try_get("", "attempts")?,
+        run_at: row.try_get("", "run_at")?,
+        done_at: row.try_get("", "done_at")?,
+        last_error: row.try_get("", "last_error")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_parsing_is_case_insensitive() {
+        assert_eq!("done".parse::<JobStatus>().unwrap(), JobStatus::Done);
+        assert_eq!("Dead".parse::<JobStatus>().unwrap(), JobStatus::Dead);
+        assert!("finished".parse::<JobStatus>().is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Worker pool settings.
+//!
+//! `jobs work` runs [`QueueConfig::workers`] apalis workers per queue, each
+//! running up to [`QueueConfig::concurrency`] jobs at a time. Both can be
+//! changed per queue with environment variables:
+//! - `JOB_QUEUE_EMAIL_WORKERS` - workers consuming the queue
+//! - `JOB_QUEUE_EMAIL_CONCURRENCY` - jobs run at the same time by one worker
+//! - `WORKER_DRAIN_TIMEOUT_SECONDS` - time running jobs get to finish on shutdown
+//!
+//! Workers record a heartbeat in `apalis.workers` every
+//! [`WORKER_HEARTBEAT_SECONDS`]; see [`super::JobStore::workers`].
+
+use apalis_sql::Config as StorageConfig;
+use std::time::Duration;
+
+use super::retry::env_parse;
+use crate::config::{
+    DEFAULT_QUEUE_CONCURRENCY, DEFAULT_QUEUE_WORKERS, JOB_NAME_EMAIL, JOB_QUEUE_EMAIL,
+    WORKER_DRAIN_TIMEOUT_SECONDS, WORKER_HEARTBEAT_SECONDS,
+};
+use crate::errors::{AppError, AppResult};
+
+/// Every queue a worker can consume.
+pub const QUEUES: [&str; 1] = [JOB_QUEUE_EMAIL];
+
+/// Workers and concurrency of one queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueConfig {
+    pub name: &'static str,
+    /// apalis job type stored in `apalis.jobs.job_type`
+    pub job_type: &'static str,
+    pub workers: usize,
+    pub concurrency: usize,
+}
+
+impl QueueConfig {
+    /// Default settings of a queue; fails for unknown queues.
+    pub fn new(name: &str) -> AppResult<Self> {
+        let (name, job_type) = match name.trim().to_lowercase().as_str() {
+            JOB_QUEUE_EMAIL => (JOB_QUEUE_EMAIL, JOB_NAME_EMAIL),
+            _ => {
+                return Err(AppError::validation(format!(
+                    "Unknown queue '{}', expected one of: {}",
+                    name,
+                    QUEUES.join(", ")
+                )))
+            }
+        };
+
+        Ok(Self {
+            name,
+            job_type,
+            workers: DEFAULT_QUEUE_WORKERS,
+            concurrency: DEFAULT_QUEUE_CONCURRENCY,
+        })
+    }
+
+    /// Default settings overridden by `JOB_QUEUE_<NAME>_*` variables.
+    pub fn from_env(name: &str) -> AppResult<Self> {
+        let queue = Self::new(name)?;
+        let prefix = format!("JOB_QUEUE_{}", queue.name.to_uppercase());
+
+        let workers = env_parse(&format!("{}_WORKERS", prefix))?.unwrap_or(queue.workers);
+        let concurrency =
+            env_parse(&format!("{}_CONCURRENCY", prefix))?.unwrap_or(queue.concurrency);
+        if workers == 0 || concurrency == 0 {
+            return Err(AppError::validation(format!(
+                "{}_WORKERS and {}_CONCURRENCY must be at least 1",
+                prefix, prefix
+            )));
+        }
+
+        Ok(queue.with_workers(workers).with_concurrency(concurrency))
+    }
+
+    /// Set the number of workers (at least one)
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Set the jobs run at once per worker (at least one)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Worker id, unique across hosts and processes
+    pub fn worker_name(&self, index: usize) -> String {
+        format!("{}@{}#{}", self.name, instance_id(), index)
+    }
+
+    /// Storage settings of a worker: it fetches no more jobs than it can
+    /// run, so jobs are not left locked by a busy worker.
+    pub fn storage_config(&self) -> StorageConfig {
+        StorageConfig::new(self.job_type)
+            .set_buffer_size(self.concurrency)
+            .set_keep_alive(Duration::from_secs(WORKER_HEARTBEAT_SECONDS))
+    }
+}
+
+/// Queues to consume and how to shut down.
+#[derive(Debug, Clone)]
+pub struct WorkerSettings {
+    pub queues: Vec<QueueConfig>,
+    pub drain_timeout: Duration,
+}
+
+impl WorkerSettings {
+    /// Settings for the `selected` queues, or every queue if none are given.
+    pub fn from_env(selected: &[String]) -> AppResult<Self> {
+        let mut queues = Vec::new();
+        if selected.is_empty() {
+            for name in QUEUES {
+                queues.push(QueueConfig::from_env(name)?);
+            }
+        } else {
+            for name in selected {
+                let queue = QueueConfig::from_env(name)?;
+                if !queues.contains(&queue) {
+                    queues.push(queue);
+                }
+            }
+        }
+
+        let drain_timeout =
+            env_parse("WORKER_DRAIN_TIMEOUT_SECONDS")?.unwrap_or(WORKER_DRAIN_TIMEOUT_SECONDS);
+
+        Ok(Self {
+            queues,
+            drain_timeout: Duration::from_secs(drain_timeout),
+        })
+    }
+}
+
+/// Host and process of this worker instance
+fn instance_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .filter(|host| !host.trim().is_empty())
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{}:{}", host.trim(), std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_config_defaults() {
+        let queue = QueueConfig::new("Email").unwrap();
+        assert_eq!(queue.name, JOB_QUEUE_EMAIL);
+        assert_eq!(queue.job_type, JOB_NAME_EMAIL);
+        assert_eq!(queue.workers, DEFAULT_QUEUE_WORKERS);
+        assert_eq!(queue.concurrency, DEFAULT_QUEUE_CONCURRENCY);
+        assert!(QueueConfig::new("sms").is_err());
+    }
+
+    #[test]
+    fn test_worker_names_are_unique_per_index() {
+        let queue = QueueConfig::new("email").unwrap().with_workers(2);
+        assert_ne!(queue.worker_name(0), queue.worker_name(1));
+        assert!(queue.worker_name(0).starts_with("email@"));
+    }
+
+    #[test]
+    fn test_storage_config_buffers_up_to_concurrency() {
+        let queue = QueueConfig::new("email").unwrap().with_concurrency(0);
+        assert_eq!(queue.concurrency, 1);
+        let config = queue.with_concurrency(8).storage_config();
+        assert_eq!(config.buffer_size(), 8);
+        assert_eq!(config.namespace(), JOB_NAME_EMAIL);
+    }
+}
@@ -27,7 +27,9 @@ async fn main() {
     let result = match cli.command {
         Commands::Serve(args) => commands::serve::execute(args, config).await,
         Commands::Migrate(args) => commands::migrate::execute(args, config).await,
+        Commands::Db(args) => commands::db::execute(args, config).await,
         Commands::Jobs(args) => commands::jobs::execute(args, config).await,
+        Commands::Email(args) => commands::email::execute(args).await,
         Commands::Generate(args) => commands::generate::execute(args).await,
     };
 
@@ -14,9 +14,12 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::config::{Config, SECONDS_PER_HOUR, TOKEN_TYPE_BEARER};
-use crate::domain::{Password, User};
+use crate::domain::{
+    DomainEvent, EmailAddress, EventBus, LocalPartPolicy, NoopEventBus, Password, User, UserEvent,
+};
 use crate::errors::{AppError, AppResult};
-use crate::infra::UnitOfWork;
+use crate::infra::{UnitOfWork, UserRepository};
+use crate::with_transaction;
 
 /// JWT claims payload
 #[derive(Debug, Serialize, Deserialize)]
@@ -95,16 +98,41 @@ fn verify_token_internal(token: &str, config: &Config) -> AppResult<Claims> {
     Ok(token_data.claims)
 }
 
+/// Publish an event that records no change, such as a login.
+///
+/// Nothing was written, so there is no transaction to tie it to; a
+/// delivery failure is logged rather than reported to the caller.
+async fn publish_event(events: &dyn EventBus, event: UserEvent) {
+    let name = event.name();
+    if let Err(e) = events.publish(DomainEvent::new(event)).await {
+        tracing::warn!(event = name, error = %e, "Failed to publish domain event");
+    }
+}
+
 /// Concrete implementation of AuthService using Unit of Work.
 pub struct Authenticator<U: UnitOfWork> {
     uow: Arc<U>,
     config: Config,
+    email_policy: LocalPartPolicy,
+    events: Arc<dyn EventBus>,
 }
 
 impl<U: UnitOfWork> Authenticator<U> {
     /// Create new auth service instance with Unit of Work
     pub fn new(uow: Arc<U>, config: Config) -> Self {
-        Self { uow, config }
+        let email_policy = config.email_local_part;
+        Self {
+            uow,
+            config,
+            email_policy,
+            events: Arc::new(NoopEventBus),
+        }
+    }
+
+    /// Publish domain events to the given bus
+    pub fn with_events(mut self, events: Arc<dyn EventBus>) -> Self {
+        self.events = events;
+        self
     }
 }
 
@@ -112,17 +140,32 @@ impl<U: UnitOfWork> Authenticator<U> {
 impl<U: UnitOfWork> AuthService for Authenticator<U> {
     async fn register(&self, email: String, password: String, name: String) -> AppResult<User> {
         // Email format is validated by the handler's ValidatedJson extractor
-        // Check if user already exists (including soft-deleted to prevent email reuse)
-        if self.uow.users().find_by_email_with_deleted(&email).await?.is_some() {
-            return Err(AppError::conflict("User"));
-        }
-
-        // DDD: Use Password value object for hashing
+        let email = EmailAddress::parse(&email, self.email_policy)?.into_string();
+        // DDD: Use Password value object for hashing; Argon2 runs before the
+        // transaction so it holds no connection or locks while hashing
         let password_hash = Password::new(&password)?.into_string();
-        self.uow.users().create(email, password_hash, name).await
+
+        with_transaction!(self.uow, |ctx| {
+            let users = ctx.users();
+            // Check if user already exists in any casing (including soft-deleted to prevent email reuse)
+            if users.find_by_email_with_deleted(&email).await?.is_some() {
+                return Err(AppError::conflict("User"));
+            }
+
+            let user = users.create(email, password_hash, name).await?;
+
+            ctx.outbox()
+                .publish(UserEvent::Registered { user_id: user.id }.into())
+                .await?;
+            Ok(user)
+        })
     }
 
     async fn login(&self, email: String, password: String) -> AppResult<TokenResponse> {
+        // A malformed address matches no one; it still goes through the lookup
+        let email = EmailAddress::parse(&email, self.email_policy)
+            .map(String::from)
+            .unwrap_or(email);
         let user_result = self.uow.users().find_by_email(&email).await?;
 
         // SECURITY: Perform password verification even if user doesn't exist
@@ -145,7 +188,12 @@ impl<U: UnitOfWork> AuthService for Authenticator<U> {
         }
 
         // Safe to unwrap since we verified user_exists is true
-        generate_token(user_result.as_ref().unwrap(), &self.config)
+        let user = user_result.as_ref().unwrap();
+        let token = generate_token(user, &self.config)?;
+
+        publish_event(self.events.as_ref(), UserEvent::LoggedIn { user_id: user.id }).await;
+
+        Ok(token)
     }
 
     fn verify_token(&self, token: &str) -> AppResult<Claims> {
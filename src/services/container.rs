@@ -14,8 +14,10 @@ use std::sync::Arc;
 
 use super::{AuthService, UserService};
 use crate::config::Config;
+use crate::domain::{EventBus, NoopEventBus};
 use crate::errors::AppResult;
 use crate::infra::Persistence;
+use crate::jobs::{InMemoryJobQueue, JobQueue};
 
 #[cfg(any(test, feature = "test-utils"))]
 use mockall::automock;
@@ -30,16 +32,22 @@ pub trait ServiceContainer: Send + Sync {
 
     /// Get user service
     fn users(&self) -> Arc<dyn UserService>;
+
+    /// Get background job queue
+    fn jobs(&self) -> Arc<dyn JobQueue>;
 }
 
 /// Concrete implementation of ServiceContainer
 pub struct Services {
     auth_service: Arc<dyn AuthService>,
     user_service: Arc<dyn UserService>,
+    job_queue: Arc<dyn JobQueue>,
 }
 
 impl Services {
     /// Create a new service container with all services initialized
+    ///
+    /// Jobs go to an [`InMemoryJobQueue`] until `with_jobs` attaches a real queue.
     pub fn new(
         auth_service: Arc<dyn AuthService>,
         user_service: Arc<dyn UserService>,
@@ -47,24 +55,49 @@ impl Services {
         Self {
             auth_service,
             user_service,
+            job_queue: Arc::new(InMemoryJobQueue::new()),
         }
     }
 
+    /// Use the given background job queue
+    pub fn with_jobs(mut self, job_queue: Arc<dyn JobQueue>) -> Self {
+        self.job_queue = job_queue;
+        self
+    }
+
     /// Create service container from database connection and config
     pub fn from_connection(
         db: sea_orm::DatabaseConnection,
         config: Config,
+    ) -> Self {
+        Self::from_connection_with_events(db, config, Arc::new(NoopEventBus))
+    }
+
+    /// Create service container publishing login events to the given bus
+    ///
+    /// Events of writes go through the outbox and its relay instead.
+    pub fn from_connection_with_events(
+        db: sea_orm::DatabaseConnection,
+        config: Config,
+        events: Arc<dyn EventBus>,
+    ) -> Self {
+        Self::from_persistence(Persistence::new(db), config, events)
+    }
+
+    /// Create service container on top of the given unit of work
+    pub fn from_persistence(
+        persistence: Persistence,
+        config: Config,
+        events: Arc<dyn EventBus>,
     ) -> Self {
         use super::{Authenticator, UserManager};
 
-        let uow = Arc::new(Persistence::new(db));
-        let auth_service = Arc::new(Authenticator::new(uow.clone(), config));
+        let uow = Arc::new(persistence);
+        let auth_service =
+            Arc::new(Authenticator::new(uow.clone(), config).with_events(events));
         let user_service = Arc::new(UserManager::new(uow.clone()));
 
-        Self {
-            auth_service,
-            user_service,
-        }
+        Self::new(auth_service, user_service)
     }
 }
 
@@ -76,6 +109,10 @@ impl ServiceContainer for Services {
     fn users(&self) -> Arc<dyn UserService> {
         self.user_service.clone()
     }
+
+    fn jobs(&self) -> Arc<dyn JobQueue> {
+        self.job_queue.clone()
+    }
 }
 
 /// Parallel execution utilities for running independent operations concurrently.
@@ -262,7 +299,7 @@ pub mod batch {
 
             let chunk_futures: Vec<_> = chunk
                 .into_iter()
-                .map(|item| processor(item))
+                .map(&processor)
                 .collect();
 
             let chunk_results = parallel::join_all(chunk_futures).await?;
@@ -309,7 +346,7 @@ pub mod batch {
             let chunk: Vec<T> = remaining.drain(..drain_count).collect();
 
             let chunk_results = parallel::join_all_limited(
-                chunk.into_iter().map(|item| processor(item)),
+                chunk.into_iter().map(&processor),
                 concurrency,
             )
             .await?;
@@ -4,12 +4,14 @@
 //! DDD: Orchestrates domain operations via Unit of Work.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::domain::User;
+use crate::domain::{User, UserEvent, UserSearch, UserSearchHit};
 use crate::errors::{AppError, AppResult};
-use crate::infra::UnitOfWork;
+use crate::infra::{UnitOfWork, UserRepository};
+use crate::with_transaction;
 
 /// User service trait for dependency injection.
 ///
@@ -32,8 +34,26 @@ pub trait UserService: Send + Sync {
     /// List only soft-deleted users
     async fn list_deleted_users(&self) -> AppResult<Vec<User>>;
 
-    /// Update user details (only active users)
-    async fn update_user(&self, id: Uuid, name: Option<String>, role: Option<String>) -> AppResult<User>;
+    /// Number of active users
+    async fn count_users(&self) -> AppResult<u64>;
+
+    /// Number of soft-deleted users
+    async fn count_deleted_users(&self) -> AppResult<u64>;
+
+    /// Search active users by name or email; returns a page and the total
+    async fn search_users(&self, search: UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)>;
+
+    /// Update user details (only active users).
+    ///
+    /// With `expected_version`, fails with `PreconditionFailed` if the user
+    /// changed since that version was read.
+    async fn update_user(
+        &self,
+        id: Uuid,
+        name: Option<String>,
+        role: Option<String>,
+        expected_version: Option<i32>,
+    ) -> AppResult<User>;
 
     /// Soft delete user (sets deleted_at timestamp)
     async fn delete_user(&self, id: Uuid) -> AppResult<()>;
@@ -41,11 +61,31 @@ pub trait UserService: Send + Sync {
     /// Permanently delete user from database (hard delete)
     async fn hard_delete_user(&self, id: Uuid) -> AppResult<()>;
 
+    /// Up to `limit` users soft-deleted before `before`, oldest first
+    async fn list_deleted_users_before(
+        &self,
+        before: DateTime<Utc>,
+        limit: u64,
+    ) -> AppResult<Vec<User>>;
+
+    /// Number of users soft-deleted before `before`
+    async fn count_deleted_users_before(&self, before: DateTime<Utc>) -> AppResult<u64>;
+
+    /// Permanently delete up to `limit` users soft-deleted before `before`.
+    ///
+    /// One transaction per call: the users are removed, recorded in the
+    /// purge log and a `user.purged` event is queued in the outbox for each.
+    /// Users restored concurrently are skipped. Returns the purged users.
+    async fn purge_deleted_users(&self, before: DateTime<Utc>, limit: u64) -> AppResult<Vec<User>>;
+
     /// Restore a soft-deleted user
     async fn restore_user(&self, id: Uuid) -> AppResult<User>;
 }
 
 /// Concrete implementation of UserService using Unit of Work.
+///
+/// Writes run in a transaction that also records their domain events in
+/// the outbox, so an event is relayed exactly when its change commits.
 pub struct UserManager<U: UnitOfWork> {
     uow: Arc<U>,
 }
@@ -87,19 +127,109 @@ impl<U: UnitOfWork> UserService for UserManager<U> {
         self.uow.users().list_deleted().await
     }
 
-    async fn update_user(&self, id: Uuid, name: Option<String>, role: Option<String>) -> AppResult<User> {
-        self.uow.users().update(id, name, role).await
+    async fn count_users(&self) -> AppResult<u64> {
+        self.uow.users().count().await
+    }
+
+    async fn count_deleted_users(&self) -> AppResult<u64> {
+        self.uow.users().count_deleted().await
+    }
+
+    async fn search_users(&self, search: UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)> {
+        self.uow.users().search(&search).await
+    }
+
+    async fn update_user(
+        &self,
+        id: Uuid,
+        name: Option<String>,
+        role: Option<String>,
+        expected_version: Option<i32>,
+    ) -> AppResult<User> {
+        with_transaction!(self.uow, |ctx| {
+            let users = ctx.users();
+            // Previous role is only needed to report a role change
+            let previous_role = match role {
+                Some(_) => Some(users.find_by_id(id).await?.ok_or(AppError::NotFound)?.role),
+                None => None,
+            };
+
+            let user = users.update(id, name, role, expected_version).await?;
+
+            let outbox = ctx.outbox();
+            outbox
+                .publish(UserEvent::Updated { user_id: id }.into())
+                .await?;
+            if let Some(from) = previous_role.filter(|from| *from != user.role) {
+                let event = UserEvent::RoleChanged {
+                    user_id: id,
+                    from,
+                    to: user.role.clone(),
+                };
+                outbox.publish(event.into()).await?;
+            }
+            Ok(user)
+        })
     }
 
     async fn delete_user(&self, id: Uuid) -> AppResult<()> {
-        self.uow.users().delete(id).await
+        with_transaction!(self.uow, |ctx| {
+            ctx.users().delete(id).await?;
+            ctx.outbox()
+                .publish(UserEvent::Deleted { user_id: id }.into())
+                .await?;
+            Ok(())
+        })
     }
 
     async fn hard_delete_user(&self, id: Uuid) -> AppResult<()> {
-        self.uow.users().hard_delete(id).await
+        with_transaction!(self.uow, |ctx| {
+            ctx.users().hard_delete(id).await?;
+            ctx.outbox()
+                .publish(UserEvent::Purged { user_id: id }.into())
+                .await?;
+            Ok(())
+        })
+    }
+
+    async fn list_deleted_users_before(
+        &self,
+        before: DateTime<Utc>,
+        limit: u64,
+    ) -> AppResult<Vec<User>> {
+        self.uow.users().list_deleted_before(before, limit).await
+    }
+
+    async fn count_deleted_users_before(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        self.uow.users().count_deleted_before(before).await
+    }
+
+    async fn purge_deleted_users(&self, before: DateTime<Utc>, limit: u64) -> AppResult<Vec<User>> {
+        with_transaction!(self.uow, |ctx| {
+            let users = ctx.users();
+            let expired = users.list_deleted_before(before, limit).await?;
+            let ids: Vec<Uuid> = expired.iter().map(|user| user.id).collect();
+
+            // Users restored since the listing are not deleted, and only the
+            // rows actually removed are logged and announced
+            let purged = users.purge_deleted(&ids, before).await?;
+            ctx.purge_log().record(&purged, Utc::now()).await?;
+            for user in &purged {
+                ctx.outbox()
+                    .publish(UserEvent::Purged { user_id: user.id }.into())
+                    .await?;
+            }
+            Ok(purged)
+        })
     }
 
     async fn restore_user(&self, id: Uuid) -> AppResult<User> {
-        self.uow.users().restore(id).await
+        with_transaction!(self.uow, |ctx| {
+            let user = ctx.users().restore(id).await?;
+            ctx.outbox()
+                .publish(UserEvent::Restored { user_id: id }.into())
+                .await?;
+            Ok(user)
+        })
     }
 }
@@ -1,14 +1,18 @@
 //! Pagination types for list endpoints.
 
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 
 use crate::config::{DEFAULT_PAGE_NUMBER, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::errors::{AppError, AppResult};
 
 /// Pagination query parameters (DRY - reusable across all list endpoints)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, IntoParams)]
 pub struct PaginationParams {
+    /// Page number, starting at 1
     #[serde(default = "default_page")]
     pub page: u64,
+    /// Results per page
     #[serde(default = "default_per_page")]
     pub per_page: u64,
 }
@@ -22,14 +26,25 @@ fn default_per_page() -> u64 {
 }
 
 impl PaginationParams {
-    /// Calculate offset for database query
-    pub fn offset(&self) -> u64 {
-        (self.page.saturating_sub(1)) * self.per_page
+    /// Page number, treating 0 as the first page
+    pub fn page(&self) -> u64 {
+        self.page.max(1)
     }
 
-    /// Get limit capped at maximum
+    /// Get limit clamped to `1..=MAX_PAGE_SIZE`
     pub fn limit(&self) -> u64 {
-        self.per_page.min(MAX_PAGE_SIZE)
+        self.per_page.clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Calculate offset for database query
+    ///
+    /// Fails with `BadRequest` when the offset does not fit a SQL `OFFSET`
+    /// (a signed 64-bit integer).
+    pub fn offset(&self) -> AppResult<u64> {
+        (self.page() - 1)
+            .checked_mul(self.limit())
+            .filter(|offset| i64::try_from(*offset).is_ok())
+            .ok_or_else(|| AppError::BadRequest(format!("Page {} is out of range", self.page)))
     }
 }
 
@@ -62,7 +77,7 @@ impl<T> Paginated<T> {
     /// Create new paginated response
     pub fn new(data: Vec<T>, page: u64, per_page: u64, total: u64) -> Self {
         let total_pages = if per_page > 0 {
-            (total + per_page - 1) / per_page
+            total.div_ceil(per_page)
         } else {
             0
         };
@@ -78,3 +93,32 @@ impl<T> Paginated<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(page: u64, per_page: u64) -> PaginationParams {
+        PaginationParams { page, per_page }
+    }
+
+    #[test]
+    fn test_offset_and_limit() {
+        assert_eq!(params(0, 20).offset().unwrap(), 0);
+        assert_eq!(params(3, 20).offset().unwrap(), 40);
+        assert_eq!(params(2, 0).limit(), 1);
+        assert_eq!(params(2, 1000).offset().unwrap(), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_offset_rejects_out_of_range_pages() {
+        assert!(matches!(
+            params(u64::MAX, 20).offset(),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            params(i64::MAX as u64, 2).offset(),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
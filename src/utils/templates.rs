@@ -60,10 +60,14 @@ impl From<{pascal_name}> for {pascal_name}Response {{
         r#"//! {pascal_name} repository with soft delete support.
 
 use async_trait::async_trait;
-use sea_orm::{{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set}};
+use sea_orm::{{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter, Set,
+}};
 use uuid::Uuid;
 
 use super::entities::{snake_name}::{{self, ActiveModel, Entity as {pascal_name}Entity}};
+use super::ConnectionRef;
 use crate::domain::{pascal_name};
 use crate::errors::{{AppError, AppResult}};
 
@@ -96,24 +100,30 @@ pub trait {pascal_name}Repository: Send + Sync {{
     async fn restore(&self, id: Uuid) -> AppResult<{pascal_name}>;
 }}
 
-/// Concrete implementation of {pascal_name}Repository with soft delete
-pub struct {pascal_name}Store {{
-    db: DatabaseConnection,
+/// Concrete implementation of {pascal_name}Repository with soft delete.
+///
+/// Runs on the pool by default, or inside a transaction as
+/// [`Tx{pascal_name}Repository`].
+pub struct {pascal_name}Store<C = DatabaseConnection> {{
+    db: C,
 }}
 
-impl {pascal_name}Store {{
+/// {pascal_name} repository running inside a transaction
+pub type Tx{pascal_name}Repository<'a> = {pascal_name}Store<&'a DatabaseTransaction>;
+
+impl<C: ConnectionRef> {pascal_name}Store<C> {{
     /// Create new repository instance
-    pub fn new(db: DatabaseConnection) -> Self {{
+    pub fn new(db: C) -> Self {{
         Self {{ db }}
     }}
 }}
 
 #[async_trait]
-impl {pascal_name}Repository for {pascal_name}Store {{
+impl<C: ConnectionRef> {pascal_name}Repository for {pascal_name}Store<C> {{
     async fn find_by_id(&self, id: Uuid) -> AppResult<Option<{pascal_name}>> {{
         let result = {pascal_name}Entity::find_by_id(id)
             .filter({snake_name}::Column::DeletedAt.is_null())
-            .one(&self.db)
+            .one(self.db.connection())
             .await
             .map_err(AppError::from)?;
 
@@ -122,7 +132,7 @@ impl {pascal_name}Repository for {pascal_name}Store {{
 
     async fn find_by_id_with_deleted(&self, id: Uuid) -> AppResult<Option<{pascal_name}>> {{
         let result = {pascal_name}Entity::find_by_id(id)
-            .one(&self.db)
+            .one(self.db.connection())
             .await
             .map_err(AppError::from)?;
 
@@ -132,7 +142,7 @@ impl {pascal_name}Repository for {pascal_name}Store {{
     async fn list(&self) -> AppResult<Vec<{pascal_name}>> {{
         let models = {pascal_name}Entity::find()
             .filter({snake_name}::Column::DeletedAt.is_null())
-            .all(&self.db)
+            .all(self.db.connection())
             .await
             .map_err(AppError::from)?;
 
@@ -148,14 +158,14 @@ impl {pascal_name}Repository for {pascal_name}Store {{
             deleted_at: Set(None),
         }};
 
-        let model = active_model.insert(&self.db).await.map_err(AppError::from)?;
+        let model = active_model.insert(self.db.connection()).await.map_err(AppError::from)?;
         Ok({pascal_name}::from(model))
     }}
 
     async fn delete(&self, id: Uuid) -> AppResult<()> {{
         let record = {pascal_name}Entity::find_by_id(id)
             .filter({snake_name}::Column::DeletedAt.is_null())
-            .one(&self.db)
+            .one(self.db.connection())
             .await?
             .ok_or(AppError::NotFound)?;
 
@@ -164,14 +174,14 @@ impl {pascal_name}Repository for {pascal_name}Store {{
         active.deleted_at = Set(Some(now));
         active.updated_at = Set(now);
 
-        active.update(&self.db).await.map_err(AppError::from)?;
+        active.update(self.db.connection()).await.map_err(AppError::from)?;
         Ok(())
     }}
 
     async fn restore(&self, id: Uuid) -> AppResult<{pascal_name}> {{
         let record = {pascal_name}Entity::find_by_id(id)
             .filter({snake_name}::Column::DeletedAt.is_not_null())
-            .one(&self.db)
+            .one(self.db.connection())
             .await?
             .ok_or_else(|| AppError::validation("Record is not deleted or does not exist"))?;
 
@@ -179,7 +189,7 @@ impl {pascal_name}Repository for {pascal_name}Store {{
         active.deleted_at = Set(None);
         active.updated_at = Set(chrono::Utc::now());
 
-        let model = active.update(&self.db).await.map_err(AppError::from)?;
+        let model = active.update(self.db.connection()).await.map_err(AppError::from)?;
         Ok({pascal_name}::from(model))
     }}
 }}
@@ -194,6 +204,79 @@ impl {pascal_name}Repository for {pascal_name}Store {{
     Ok(())
 }
 
+/// Path of the file holding `TransactionContext`
+const UNIT_OF_WORK_PATH: &str = "src/infra/unit_of_work.rs";
+
+/// Add a generated entity's repository accessor to `TransactionContext`.
+///
+/// Returns `false` if the accessor is already there.
+pub fn add_transaction_accessor(name: &str) -> AppResult<bool> {
+    let source = fs::read_to_string(UNIT_OF_WORK_PATH)
+        .map_err(|e| AppError::internal(format!("Failed to read {}: {}", UNIT_OF_WORK_PATH, e)))?;
+
+    match insert_transaction_accessor(&source, name)? {
+        Some(updated) => {
+            write_file(UNIT_OF_WORK_PATH, &updated)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Insert the accessor and its import into the unit of work source.
+///
+/// Fails if the source no longer has the shape the generator expects, so
+/// the accessor is never silently left out.
+fn insert_transaction_accessor(source: &str, name: &str) -> AppResult<Option<String>> {
+    let snake_name = to_snake_case(name);
+    let pascal_name = to_pascal_case(name);
+
+    if source.contains(&format!("pub fn {}s(&self)", snake_name)) {
+        return Ok(None);
+    }
+
+    let not_found = |what: &str| {
+        AppError::internal(format!(
+            "Could not find {} in {}; add TransactionContext::{}s() by hand",
+            what, UNIT_OF_WORK_PATH, snake_name
+        ))
+    };
+
+    let imports = source
+        .find("use super::repositories::")
+        .ok_or_else(|| not_found("the repositories import"))?;
+    let imports_end = imports
+        + source[imports..]
+            .find('\n')
+            .ok_or_else(|| not_found("the repositories import"))?
+        + 1;
+
+    let context = source
+        .find("impl<'a> TransactionContext<'a> {")
+        .ok_or_else(|| not_found("impl TransactionContext"))?;
+    let context_end = context
+        + source[context..]
+            .find("\n}\n")
+            .ok_or_else(|| not_found("the end of impl TransactionContext"))?;
+
+    let accessor = format!(
+        r#"
+
+    /// Get {snake_name} repository for this transaction
+    pub fn {snake_name}s(&self) -> Tx{pascal_name}Repository<'a> {{
+        {pascal_name}Store::new(self.txn)
+    }}"#
+    );
+
+    Ok(Some(format!(
+        "{}use super::repositories::{{{pascal_name}Store, Tx{pascal_name}Repository}};\n{}{}{}",
+        &source[..imports_end],
+        &source[imports_end..context_end],
+        accessor,
+        &source[context_end..],
+    )))
+}
+
 /// Generate migration file
 pub fn generate_migration(name: &str) -> AppResult<()> {
     let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
@@ -391,7 +474,7 @@ fn write_file(path: &str, content: &str) -> AppResult<()> {
 }
 
 /// Convert to snake_case
-fn to_snake_case(s: &str) -> String {
+pub(crate) fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
     for (i, c) in s.chars().enumerate() {
         if c.is_uppercase() {
@@ -407,7 +490,7 @@ fn to_snake_case(s: &str) -> String {
 }
 
 /// Convert to PascalCase
-fn to_pascal_case(s: &str) -> String {
+pub(crate) fn to_pascal_case(s: &str) -> String {
     s.split('_')
         .map(|word| {
             let mut chars = word.chars();
@@ -418,3 +501,32 @@ fn to_pascal_case(s: &str) -> String {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_accessor_is_added_once() {
+        let source = include_str!("../infra/unit_of_work.rs");
+
+        let updated = insert_transaction_accessor(source, "Invoice")
+            .unwrap()
+            .unwrap();
+        assert!(updated.contains("use super::repositories::{InvoiceStore, TxInvoiceRepository};\n"));
+        assert!(updated.contains(
+            "    pub fn invoices(&self) -> TxInvoiceRepository<'a> {\n        \
+             InvoiceStore::new(self.txn)\n    }\n}\n"
+        ));
+
+        assert!(insert_transaction_accessor(&updated, "Invoice")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_transaction_accessor_fails_without_context() {
+        let err = insert_transaction_accessor("use super::repositories::X;\n", "Invoice");
+        assert!(err.is_err());
+    }
+}
@@ -3,13 +3,23 @@
 //! These tests use mock services to test API endpoints without requiring
 //! actual database or Redis connections.
 
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
-use axum::http::StatusCode;
-use chrono::Utc;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use tower::ServiceExt;
 use uuid::Uuid;
 
-use rust_api_starter::domain::{User, UserRole};
+use rust_api_starter::api::{create_router, AppState};
+use rust_api_starter::config::Config;
+use rust_api_starter::domain::{User, UserRole, UserSearch, UserSearchHit};
 use rust_api_starter::errors::{AppError, AppResult};
+use rust_api_starter::infra::{Cache, Database};
 use rust_api_starter::services::{AuthService, Claims, TokenResponse, UserService};
 
 // =============================================================================
@@ -17,15 +27,11 @@ use rust_api_starter::services::{AuthService, Claims, TokenResponse, UserService
 // =============================================================================
 
 /// Mock auth service that returns predefined responses
-struct MockAuthService {
-    jwt_secret: String,
-}
+struct MockAuthService;
 
 impl MockAuthService {
     fn new() -> Self {
-        Self {
-            jwt_secret: "test-secret-key-for-testing-only-32chars".to_string(),
-        }
+        Self
     }
 }
 
@@ -41,6 +47,7 @@ impl AuthService for MockAuthService {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         })
     }
 
@@ -83,6 +90,7 @@ impl UserService for MockUserService {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         })
     }
 
@@ -101,6 +109,7 @@ impl UserService for MockUserService {
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
                 deleted_at: None,
+                version: 1,
             },
             User {
                 id: Uuid::new_v4(),
@@ -111,6 +120,7 @@ impl UserService for MockUserService {
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
                 deleted_at: None,
+                version: 1,
             },
         ])
     }
@@ -123,7 +133,25 @@ impl UserService for MockUserService {
         Ok(vec![])
     }
 
-    async fn update_user(&self, id: Uuid, name: Option<String>, _role: Option<String>) -> AppResult<User> {
+    async fn count_users(&self) -> AppResult<u64> {
+        Ok(self.list_users().await?.len() as u64)
+    }
+
+    async fn count_deleted_users(&self) -> AppResult<u64> {
+        Ok(self.list_deleted_users().await?.len() as u64)
+    }
+
+    async fn search_users(&self, _search: UserSearch) -> AppResult<(Vec<UserSearchHit>, u64)> {
+        Ok((vec![], 0))
+    }
+
+    async fn update_user(
+        &self,
+        id: Uuid,
+        name: Option<String>,
+        _role: Option<String>,
+        _expected_version: Option<i32>,
+    ) -> AppResult<User> {
         Ok(User {
             id,
             email: "test@example.com".to_string(),
@@ -133,6 +161,7 @@ impl UserService for MockUserService {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         })
     }
 
@@ -144,26 +173,28 @@ impl UserService for MockUserService {
         Ok(())
     }
 
-    async fn restore_user(&self, id: Uuid) -> AppResult<User> {
-        self.get_user(id).await
+    async fn list_deleted_users_before(
+        &self,
+        _before: DateTime<Utc>,
+        _limit: u64,
+    ) -> AppResult<Vec<User>> {
+        Ok(vec![])
     }
-}
-
-/// Mock Database for testing (doesn't actually connect)
-struct MockDatabase;
 
-impl MockDatabase {
-    async fn ping(&self) -> AppResult<()> {
-        Ok(())
+    async fn count_deleted_users_before(&self, _before: DateTime<Utc>) -> AppResult<u64> {
+        Ok(0)
     }
-}
 
-/// Mock Cache for testing
-struct MockCache;
+    async fn purge_deleted_users(
+        &self,
+        _before: DateTime<Utc>,
+        _limit: u64,
+    ) -> AppResult<Vec<User>> {
+        Ok(vec![])
+    }
 
-impl MockCache {
-    async fn exists(&self, _key: &str) -> AppResult<bool> {
-        Ok(true)
+    async fn restore_user(&self, id: Uuid) -> AppResult<User> {
+        self.get_user(id).await
     }
 }
 
@@ -186,7 +217,6 @@ async fn test_root_endpoint_returns_welcome_message() {
     // This test validates the expected response format
 
     let expected_response = "Welcome to Rust API Starter";
-    assert!(!expected_response.is_empty());
     assert!(expected_response.contains("Rust API"));
 }
 
@@ -252,6 +282,7 @@ async fn test_user_creation() {
         created_at: Utc::now(),
         updated_at: Utc::now(),
         deleted_at: None,
+        version: 1,
     };
 
     assert!(!user.email.is_empty());
@@ -269,6 +300,7 @@ async fn test_user_soft_delete_state() {
         created_at: Utc::now(),
         updated_at: Utc::now(),
         deleted_at: None,
+        version: 1,
     };
 
     // User is not deleted
@@ -308,6 +340,10 @@ async fn test_app_error_status_codes() {
     let unauthorized = AppError::Unauthorized;
     let response = unauthorized.into_response();
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let stale = AppError::PreconditionFailed;
+    let response = stale.into_response();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
 }
 
 // =============================================================================
@@ -452,6 +488,170 @@ async fn test_mock_user_service_delete_user() {
     assert!(result.is_ok());
 }
 
+// =============================================================================
+// OpenAPI Tests
+// =============================================================================
+
+#[test]
+fn test_openapi_documents_admin_job_routes() {
+    use rust_api_starter::api::ApiDoc;
+    use utoipa::OpenApi;
+
+    let doc = ApiDoc::openapi();
+    for path in [
+        "/admin/jobs",
+        "/admin/jobs/stats",
+        "/admin/jobs/throughput",
+        "/admin/jobs/{id}",
+        "/admin/jobs/{id}/retry",
+        "/admin/jobs/{id}/cancel",
+    ] {
+        assert!(doc.paths.paths.contains_key(path), "missing {path}");
+    }
+}
+
+// =============================================================================
+// HTTP Tests (Require redis-server)
+// =============================================================================
+//
+// Full router over an in-memory SQLite database and a throwaway Redis.
+// Run with: cargo test --test api_test -- --ignored
+
+/// A throwaway `redis-server` bound to a local port.
+struct RedisServer {
+    child: Child,
+    port: u16,
+}
+
+impl RedisServer {
+    fn start(port: u16) -> Self {
+        let child = Command::new("redis-server")
+            .args([
+                "--port",
+                &port.to_string(),
+                "--save",
+                "",
+                "--appendonly",
+                "no",
+            ])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("redis-server must be installed to run HTTP tests");
+        std::thread::sleep(Duration::from_millis(300));
+        Self { child, port }
+    }
+}
+
+impl Drop for RedisServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// State with one registered user, and that user's bearer token
+async fn http_state(redis: &RedisServer) -> (AppState, User, String) {
+    let mut config = Config::from_env();
+    config.database_url = "sqlite::memory:".to_string();
+    config.redis_url = format!("redis://127.0.0.1:{}", redis.port);
+    config.redis_lock_nodes = Vec::new();
+
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    db.run_migrations().await.unwrap();
+    let cache = Cache::try_connect(&config).await.unwrap();
+    let state = AppState::from_config(Arc::new(db), Arc::new(cache), config);
+
+    let user = state
+        .auth_service
+        .register(
+            "etag@example.com".to_string(),
+            "Passw0rd!".to_string(),
+            "Etag User".to_string(),
+        )
+        .await
+        .unwrap();
+    let token = state
+        .auth_service
+        .login("etag@example.com".to_string(), "Passw0rd!".to_string())
+        .await
+        .unwrap()
+        .access_token;
+
+    (state, user, token)
+}
+
+async fn send(
+    state: &AppState,
+    method: Method,
+    uri: &str,
+    token: &str,
+    if_match: Option<&str>,
+) -> Response {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(tag) = if_match {
+        request = request.header(header::IF_MATCH, tag);
+    }
+    let body = Body::from(r#"{"name":"Renamed"}"#);
+
+    create_router(state.clone())
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap()
+}
+
+fn etag(response: &Response) -> &str {
+    response.headers()[header::ETAG].to_str().unwrap()
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_http_etag_and_if_match() {
+    let redis = RedisServer::start(16400);
+    let (state, user, token) = http_state(&redis).await;
+    let uri = format!("/users/{}", user.id);
+
+    let me = send(&state, Method::GET, "/users/me", &token, None).await;
+    assert_eq!(me.status(), StatusCode::OK);
+    assert_eq!(etag(&me), "\"1\"");
+
+    // If-Match uses strong comparison, so a weak tag never matches
+    let weak = send(&state, Method::PUT, &uri, &token, Some("W/\"1\"")).await;
+    assert_eq!(weak.status(), StatusCode::PRECONDITION_FAILED);
+
+    let updated = send(&state, Method::PUT, &uri, &token, Some("\"1\"")).await;
+    assert_eq!(updated.status(), StatusCode::OK);
+    assert_eq!(etag(&updated), "\"2\"");
+
+    let stale = send(&state, Method::PUT, &uri, &token, Some("\"1\"")).await;
+    assert_eq!(stale.status(), StatusCode::PRECONDITION_FAILED);
+
+    // The update refreshed the cached user, so reads return the new tag
+    let fetched = send(&state, Method::GET, &uri, &token, None).await;
+    assert_eq!(fetched.status(), StatusCode::OK);
+    assert_eq!(etag(&fetched), "\"2\"");
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_http_update_requires_if_match() {
+    let redis = RedisServer::start(16401);
+    let (state, user, token) = http_state(&redis).await;
+    let state = state.with_require_if_match(true);
+    let uri = format!("/users/{}", user.id);
+
+    let missing = send(&state, Method::PUT, &uri, &token, None).await;
+    assert_eq!(missing.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    let any = send(&state, Method::PUT, &uri, &token, Some("*")).await;
+    assert_eq!(any.status(), StatusCode::OK);
+    assert_eq!(etag(&any), "\"2\"");
+}
+
 // =============================================================================
 // Integration Tests (Require Infrastructure)
 // =============================================================================
@@ -0,0 +1,363 @@
+//! Cache integration tests.
+//!
+//! These tests spawn local `redis-server` processes and are ignored by default.
+//! Run with: cargo test --test cache_test -- --ignored
+
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use rust_api_starter::config::Config;
+use rust_api_starter::infra::Cache;
+
+/// A throwaway `redis-server` bound to a local port.
+struct RedisServer {
+    child: Child,
+    port: u16,
+}
+
+impl RedisServer {
+    fn start(port: u16) -> Self {
+        let child = Command::new("redis-server")
+            .args([
+                "--port",
+                &port.to_string(),
+                "--save",
+                "",
+                "--appendonly",
+                "no",
+            ])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("redis-server must be installed to run cache integration tests");
+        std::thread::sleep(Duration::from_millis(300));
+        Self { child, port }
+    }
+
+    fn url(&self) -> String {
+        format!("redis://127.0.0.1:{}", self.port)
+    }
+}
+
+impl Drop for RedisServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Read once; tests run in parallel and must not touch the environment.
+static BASE_CONFIG: Lazy<Config> = Lazy::new(Config::from_env);
+
+fn config_with(redis_url: &str, lock_nodes: &[String]) -> Config {
+    let mut config = BASE_CONFIG.clone();
+    config.redis_url = redis_url.to_string();
+    config.redis_mode = None;
+    config.redis_lock_nodes = lock_nodes.to_vec();
+    config
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_redlock_quorum_survives_one_node_failure() {
+    let main = RedisServer::start(16390);
+    let mut nodes: Vec<RedisServer> = (16391..16394).map(RedisServer::start).collect();
+    let urls: Vec<String> = nodes.iter().map(RedisServer::url).collect();
+
+    let cache = Cache::try_connect(&config_with(&main.url(), &urls))
+        .await
+        .unwrap();
+    assert!(cache.uses_redlock());
+
+    // Lock is exclusive while held
+    let guard = cache
+        .try_acquire_lock("redlock-test")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(cache
+        .try_acquire_lock("redlock-test")
+        .await
+        .unwrap()
+        .is_none());
+    assert!(cache.is_locked("redlock-test").await.unwrap());
+    assert!(guard.extend(10).await.unwrap());
+    guard.release().await.unwrap();
+    assert!(!cache.is_locked("redlock-test").await.unwrap());
+
+    // 2 of 3 nodes still form a quorum
+    drop(nodes.pop());
+    let guard = cache.try_acquire_lock("redlock-test").await.unwrap();
+    assert!(guard.is_some());
+    drop(guard);
+
+    // 1 of 3 nodes does not
+    drop(nodes.pop());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(cache
+        .try_acquire_lock("redlock-other")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_single_node_lock_without_redlock() {
+    let main = RedisServer::start(16395);
+
+    let cache = Cache::try_connect(&config_with(&main.url(), &[]))
+        .await
+        .unwrap();
+    assert!(!cache.uses_redlock());
+
+    let guard = cache.acquire_lock("single-test").await.unwrap();
+    assert!(cache
+        .try_acquire_lock("single-test")
+        .await
+        .unwrap()
+        .is_none());
+    guard.release().await.unwrap();
+    assert!(cache
+        .try_acquire_lock("single-test")
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_leader_election_hands_off_on_shutdown() {
+    use std::sync::Arc;
+    use tokio::sync::watch;
+
+    use rust_api_starter::infra::LeaderElection;
+
+    let main = RedisServer::start(16396);
+    let cache = Arc::new(
+        Cache::try_connect(&config_with(&main.url(), &[]))
+            .await
+            .unwrap(),
+    );
+
+    let (stop_a, rx_a) = watch::channel(false);
+    let a = LeaderElection::new(cache.clone(), "leader-test")
+        .with_ttl(3)
+        .spawn(rx_a);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(a.is_leader());
+
+    let (_stop_b, rx_b) = watch::channel(false);
+    let b = LeaderElection::new(cache.clone(), "leader-test")
+        .with_ttl(3)
+        .spawn(rx_b);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!b.is_leader());
+
+    // A steps down and releases the lock; B takes over on its next attempt
+    stop_a.send_replace(true);
+    a.stopped().await;
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert!(b.is_leader());
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_fair_semaphore_serves_waiters_in_order() {
+    use tokio::time::Instant;
+
+    let main = RedisServer::start(16397);
+    let cache = Cache::try_connect(&config_with(&main.url(), &[]))
+        .await
+        .unwrap();
+    let deadline = || Instant::now() + Duration::from_secs(5);
+
+    let holder = cache
+        .acquire_fair_semaphore("fair-test", 1, 10, deadline())
+        .await
+        .unwrap();
+
+    let first = cache
+        .join_semaphore_queue("fair-test", 1, 10)
+        .await
+        .unwrap();
+    let second = cache
+        .join_semaphore_queue("fair-test", 1, 10)
+        .await
+        .unwrap();
+    assert_eq!(first.position(), 0);
+    assert_eq!(second.position(), 1);
+    assert_eq!(cache.semaphore_queue_len("fair-test").await.unwrap(), 2);
+
+    // The second waiter cannot jump the queue and gives up at its deadline
+    let started = Instant::now();
+    let timed_out = second
+        .wait(Instant::now() + Duration::from_millis(300))
+        .await;
+    assert!(timed_out.is_err());
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // Releasing the holder wakes the head of the queue
+    let waiter = tokio::spawn(async move { first.wait(deadline()).await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    holder.release().await.unwrap();
+    let permit = waiter.await.unwrap().unwrap();
+    assert!(permit.extend(10).await.unwrap());
+    assert_eq!(cache.semaphore_queue_len("fair-test").await.unwrap(), 0);
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_fair_semaphore_expires_crashed_holder() {
+    use tokio::time::Instant;
+
+    let main = RedisServer::start(16398);
+    let cache = Cache::try_connect(&config_with(&main.url(), &[]))
+        .await
+        .unwrap();
+
+    // Simulate a crash: the permit is never released
+    let crashed = cache
+        .acquire_fair_semaphore("fair-crash", 1, 1, Instant::now() + Duration::from_secs(1))
+        .await
+        .unwrap();
+    std::mem::forget(crashed);
+
+    let permit = cache
+        .acquire_fair_semaphore("fair-crash", 1, 10, Instant::now() + Duration::from_secs(5))
+        .await;
+    assert!(permit.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_fair_semaphore_serves_more_waiters_than_blocking_connections() {
+    use rust_api_starter::config::SEMAPHORE_BLOCKING_POOL_SIZE;
+    use tokio::time::Instant;
+
+    let main = RedisServer::start(16405);
+    let cache = Cache::try_connect(&config_with(&main.url(), &[]))
+        .await
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(20);
+
+    // Waiters beyond the pool poll instead of blocking, and all get a turn
+    let waiters: Vec<_> = (0..SEMAPHORE_BLOCKING_POOL_SIZE + 8)
+        .map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                let permit = cache
+                    .acquire_fair_semaphore("fair-pool", 4, 10, deadline)
+                    .await?;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                permit.release().await
+            })
+        })
+        .collect();
+    for waiter in waiters {
+        waiter.await.unwrap().unwrap();
+    }
+    assert_eq!(cache.semaphore_queue_len("fair-pool").await.unwrap(), 0);
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_codec_change_keeps_entries_readable() {
+    use redis::AsyncCommands;
+
+    let main = RedisServer::start(16399);
+    let json = Cache::try_connect(&config_with(&main.url(), &[]))
+        .await
+        .unwrap();
+
+    let mut config = config_with(&main.url(), &[]);
+    config.cache_codec = Some("msgpack".to_string());
+    config.cache_compression_threshold = Some(16);
+    let msgpack = Cache::try_connect(&config).await.unwrap();
+
+    let value = vec!["a".repeat(64); 4];
+    json.set("codec:old", &value).await.unwrap();
+    msgpack.set("codec:new", &value).await.unwrap();
+    assert_eq!(
+        msgpack.get::<Vec<String>>("codec:old").await.unwrap(),
+        Some(value.clone())
+    );
+    assert_eq!(
+        json.get::<Vec<String>>("codec:new").await.unwrap(),
+        Some(value)
+    );
+
+    // Entries written before codecs existed are plain JSON strings
+    let mut conn = json.connection();
+    let _: () = conn.set("codec:legacy", "[1,2,3]").await.unwrap();
+    assert_eq!(
+        msgpack.get::<Vec<u8>>("codec:legacy").await.unwrap(),
+        Some(vec![1, 2, 3])
+    );
+
+    // A value of the wrong shape is a miss, not an error
+    assert_eq!(json.get::<u64>("codec:new").await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "Requires redis-server"]
+async fn test_event_bus_consumer_group_ack_and_replay() {
+    use rust_api_starter::domain::{DomainEvent, EventBus, UserEvent};
+    use rust_api_starter::infra::{RedisEventBus, StartFrom};
+    use uuid::Uuid;
+
+    let main = RedisServer::start(16400);
+    let cache = Cache::try_connect(&config_with(&main.url(), &[]))
+        .await
+        .unwrap();
+    let bus = RedisEventBus::new(cache.connection()).with_stream("events:test");
+    bus.create_group("mailer", StartFrom::Beginning).await.unwrap();
+    // Creating an existing group is a no-op
+    bus.create_group("mailer", StartFrom::Beginning).await.unwrap();
+
+    let user_id = Uuid::new_v4();
+    bus.publish(DomainEvent::new(UserEvent::Deleted { user_id }))
+        .await
+        .unwrap();
+    bus.publish(DomainEvent::new(UserEvent::Restored { user_id }))
+        .await
+        .unwrap();
+
+    let mut consumer = bus.consumer("mailer", "c1").await.unwrap();
+    let batch = consumer
+        .next_batch(10, Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert_eq!(batch.len(), 2);
+    assert_eq!(batch[0].event.name(), "user.deleted");
+
+    // Unacknowledged events are redelivered to a restarted consumer
+    let mut restarted = bus.consumer("mailer", "c1").await.unwrap();
+    let redelivered = restarted
+        .next_batch(10, Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert_eq!(redelivered.len(), 2);
+
+    let offsets: Vec<String> = redelivered.iter().map(|e| e.offset.clone()).collect();
+    assert_eq!(restarted.ack(&offsets).await.unwrap(), 2);
+    let mut fresh = bus.consumer("mailer", "c1").await.unwrap();
+    assert!(fresh
+        .next_batch(10, Duration::from_millis(100))
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Replay from an offset by rewinding the group
+    bus.reset_group("mailer", StartFrom::Offset(offsets[0].clone()))
+        .await
+        .unwrap();
+    let replayed = fresh
+        .next_batch(10, Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert_eq!(replayed.len(), 1);
+    assert_eq!(replayed[0].event.name(), "user.restored");
+    assert_eq!(bus.read_range("-", 10).await.unwrap().len(), 2);
+}
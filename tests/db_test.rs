@@ -0,0 +1,898 @@
+//! Integration tests against an in-memory SQLite database.
+//!
+//! Migrations, repositories and the unit of work run on SQLite as well as
+//! PostgreSQL, so these tests need no external services. The few
+//! PostgreSQL-only ones are ignored by default; run them with a database at
+//! DATABASE_URL: `cargo test --test db_test -- --ignored`
+
+use std::sync::Arc;
+
+use rust_api_starter::config::Config;
+use rust_api_starter::domain::{
+    HighlightField, LocalPartPolicy, MockEventBus, NoopEventBus, Password, SearchQuery, UserEvent,
+    UserRole, UserSearch,
+};
+use rust_api_starter::errors::AppError;
+use rust_api_starter::infra::{
+    duplicate_emails, email_collisions, email_rewrites, normalize_emails, Database, MigrationDrift,
+    Migrator, Persistence, Rollback, SeedSet, Seeder, StartupMigrations, UnitOfWork,
+    UserRepository, UserStore,
+};
+use rust_api_starter::jobs::{
+    EmailJob, InMemoryJobQueue, JobStore, OutboxRelay, PurgeDeletedUsersTask,
+};
+use rust_api_starter::services::{ServiceContainer, Services};
+use rust_api_starter::with_transaction;
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::MigratorTrait;
+
+/// Fresh, migrated `:memory:` database
+async fn memory_db() -> Database {
+    let mut config = Config::from_env();
+    config.database_url = "sqlite::memory:".to_string();
+
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    db.run_migrations().await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn test_sqlite_migrations_apply_and_roll_back() {
+    let db = memory_db().await;
+
+    let status = db.migration_status().await.unwrap();
+    assert!(!status.is_empty());
+    assert!(status.iter().all(|(_, applied)| *applied));
+
+    db.rollback_migration().await.unwrap();
+    let status = db.migration_status().await.unwrap();
+    assert!(!status.last().unwrap().1);
+
+    db.fresh_migrations().await.unwrap();
+    assert!(db.migration_status().await.unwrap().iter().all(|(_, a)| *a));
+    db.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_sqlite_migrate_to_targets() {
+    let mut config = Config::from_env();
+    config.database_url = "sqlite::memory:".to_string();
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    let names: Vec<String> = Migrator::migrations()
+        .iter()
+        .map(|m| m.name().to_string())
+        .collect();
+
+    // Dry runs print SQL without applying anything
+    let plan = db.dry_run_up(Some(&names[1])).await.unwrap();
+    assert_eq!(plan.len(), 2);
+    assert!(plan[0].1[0].starts_with("CREATE TABLE"));
+    assert!(db.migration_status().await.unwrap().iter().all(|(_, a)| !a));
+
+    assert_eq!(db.migrate_up(Some(&names[1])).await.unwrap(), names[..2]);
+    assert!(db.migrate_up(Some(&names[0])).await.is_err());
+    assert!(db.migrate_up(Some("m_unknown")).await.is_err());
+    db.migrate_up(None).await.unwrap();
+
+    let last = names.len() - 1;
+    assert_eq!(
+        db.migrate_down(&Rollback::Steps(2)).await.unwrap(),
+        vec![names[last].clone(), names[last - 1].clone()]
+    );
+    let reverted = db
+        .migrate_down(&Rollback::To(names[0].clone()))
+        .await
+        .unwrap();
+    assert_eq!(reverted.len(), last - 2);
+    let status = db.migration_status().await.unwrap();
+    assert!(status[0].1);
+    assert!(status[1..].iter().all(|(_, applied)| !applied));
+}
+
+#[tokio::test]
+async fn test_sqlite_startup_migration_modes() {
+    let mut config = Config::from_env();
+    config.database_url = "sqlite::memory:".to_string();
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    let total = Migrator::migrations().len();
+
+    db.startup_migrations(StartupMigrations::Off).await.unwrap();
+    assert_eq!(db.pending_migrations().await.unwrap().len(), total);
+    assert!(db
+        .startup_migrations(StartupMigrations::Check)
+        .await
+        .is_err());
+
+    db.startup_migrations(StartupMigrations::Auto)
+        .await
+        .unwrap();
+    assert!(db.pending_migrations().await.unwrap().is_empty());
+    db.startup_migrations(StartupMigrations::Check)
+        .await
+        .unwrap();
+
+    assert_eq!("CHECK".parse(), Ok(StartupMigrations::Check));
+    assert!("sometimes".parse::<StartupMigrations>().is_err());
+}
+
+#[tokio::test]
+async fn test_sqlite_migration_drift_is_detected() {
+    let db = memory_db().await;
+    assert!(db.migration_drift().await.unwrap().is_empty());
+
+    let name = Migrator::migrations()[0].name().to_string();
+    db.connection()
+        .execute(Statement::from_string(
+            db.backend(),
+            format!(
+                "UPDATE migration_checksums SET checksum = 'stale' WHERE version = '{}'",
+                name
+            ),
+        ))
+        .await
+        .unwrap();
+    db.connection()
+        .execute(Statement::from_string(
+            db.backend(),
+            "INSERT INTO migration_checksums (version, checksum, recorded_at) \
+             VALUES ('m_removed', 'x', '2024-01-01T00:00:00Z')"
+                .to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let drift = db.migration_drift().await.unwrap();
+    assert!(drift.contains(&MigrationDrift::Changed(name)));
+    assert!(drift.contains(&MigrationDrift::Missing("m_removed".to_string())));
+
+    // Re-running migrations keeps the recorded checksum
+    db.run_migrations().await.unwrap();
+    assert_eq!(db.migration_drift().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_sqlite_file_database_is_created() {
+    let path = std::env::temp_dir().join(format!("rust-api-starter-{}.db", uuid::Uuid::new_v4()));
+    let mut config = Config::from_env();
+    config.database_url = format!("sqlite://{}", path.display());
+
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    db.run_migrations().await.unwrap();
+    UserStore::new(db.get_connection())
+        .create("file@example.com".into(), "hash".into(), "File".into())
+        .await
+        .unwrap();
+    drop(db);
+
+    // Data survives reconnecting
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    let users = UserStore::new(db.get_connection()).list().await.unwrap();
+    assert_eq!(users.len(), 1);
+
+    drop(db);
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_sqlite_reads_use_the_replica() {
+    // Two separate in-memory databases stand in for a primary and a
+    // replica that has not caught up yet
+    let mut config = Config::from_env();
+    config.database_url = "sqlite::memory:".to_string();
+    config.database_replica_url = Some("sqlite::memory:".to_string());
+    config.database_max_connections = 3;
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    db.run_migrations().await.unwrap();
+    Migrator::up(db.read_connection(), None).await.unwrap();
+    db.ping().await.unwrap();
+
+    let uow = Persistence::new(db.get_connection()).with_replica(db.get_read_connection());
+    let user = uow
+        .users()
+        .create("lag@example.com".into(), "hash".into(), "Lag".into())
+        .await
+        .unwrap();
+
+    // Written to the primary, not yet listed by the replica
+    assert!(uow.users().list().await.unwrap().is_empty());
+    assert_eq!(uow.users().count().await.unwrap(), 0);
+
+    // Lookups that back writes read the primary
+    assert!(uow.users().find_by_id(user.id).await.unwrap().is_some());
+    assert!(uow
+        .users()
+        .find_by_email_with_deleted("LAG@example.com")
+        .await
+        .unwrap()
+        .is_some());
+
+    // Writes look the user up on the primary
+    uow.users().delete(user.id).await.unwrap();
+
+    // Reads inside a transaction go to the primary
+    let found = with_transaction!(uow, |ctx| {
+        ctx.users().find_by_id_with_deleted(user.id).await
+    })
+    .unwrap();
+    assert!(found.unwrap().deleted_at.is_some());
+}
+
+#[tokio::test]
+async fn test_sqlite_user_store_soft_delete() {
+    let db = memory_db().await;
+    let users = UserStore::new(db.get_connection());
+
+    let user = users
+        .create("ada@example.com".into(), "hash".into(), "Ada".into())
+        .await
+        .unwrap();
+    assert_eq!(
+        users
+            .find_by_email("ada@example.com")
+            .await
+            .unwrap()
+            .unwrap()
+            .id,
+        user.id
+    );
+
+    users.delete(user.id).await.unwrap();
+    assert!(users.find_by_id(user.id).await.unwrap().is_none());
+    assert!(users
+        .find_by_id_with_deleted(user.id)
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(users.list_deleted().await.unwrap().len(), 1);
+    assert_eq!(users.count().await.unwrap(), 0);
+    assert_eq!(users.count_deleted().await.unwrap(), 1);
+
+    let restored = users.restore(user.id).await.unwrap();
+    assert!(restored.deleted_at.is_none());
+    assert_eq!(users.list().await.unwrap().len(), 1);
+    assert_eq!(users.count().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_sqlite_user_update_checks_version() {
+    let db = memory_db().await;
+    let users = UserStore::new(db.get_connection());
+
+    let user = users
+        .create("grace@example.com".into(), "hash".into(), "Grace".into())
+        .await
+        .unwrap();
+    assert_eq!(user.version, 1);
+
+    let updated = users
+        .update(user.id, Some("Grace H.".into()), None, Some(1))
+        .await
+        .unwrap();
+    assert_eq!(updated.version, 2);
+    assert_eq!(updated.name, "Grace H.");
+
+    // A writer holding the old version loses
+    let stale = users
+        .update(user.id, Some("Stale".into()), None, Some(1))
+        .await;
+    assert!(matches!(stale, Err(AppError::PreconditionFailed)));
+    assert_eq!(
+        users.find_by_id(user.id).await.unwrap().unwrap().name,
+        "Grace H."
+    );
+
+    // Without an expected version the update always applies
+    let forced = users.update(user.id, None, None, None).await.unwrap();
+    assert_eq!(forced.version, 3);
+
+    users.delete(user.id).await.unwrap();
+    assert!(matches!(
+        users.update(user.id, None, None, Some(4)).await,
+        Err(AppError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn test_sqlite_emails_are_unique_ignoring_case() {
+    let mut config = Config::from_env();
+    config.database_url = "sqlite::memory:".to_string();
+    let db = Database::connect_without_migrations(&config).await.unwrap();
+    let names: Vec<String> = Migrator::migrations()
+        .iter()
+        .map(|m| m.name().to_string())
+        .collect();
+    let index = names
+        .iter()
+        .position(|name| name.ends_with("add_user_email_lower_index"))
+        .unwrap();
+    db.migrate_up(Some(&names[index - 1])).await.unwrap();
+
+    // Duplicates created before the index block it
+    let users = UserStore::new(db.get_connection());
+    let first = users
+        .create("Alice@example.com".into(), "hash".into(), "Alice".into())
+        .await
+        .unwrap();
+    let second = users
+        .create("alice@EXAMPLE.com".into(), "hash".into(), "Alice".into())
+        .await
+        .unwrap();
+    let duplicates = duplicate_emails(db.connection()).await.unwrap();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].email, "alice@example.com");
+    assert_eq!(duplicates[0].users[0].id, first.id);
+    assert!(db.migrate_up(None).await.is_err());
+
+    users.hard_delete(second.id).await.unwrap();
+    assert!(duplicate_emails(db.connection()).await.unwrap().is_empty());
+    db.migrate_up(None).await.unwrap();
+
+    let found = users.find_by_email("ALICE@example.COM").await.unwrap();
+    assert_eq!(found.unwrap().id, first.id);
+    assert!(users
+        .create("aLiCe@example.com".into(), "hash".into(), "Alice".into())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_sqlite_existing_emails_are_normalized_under_a_new_policy() {
+    let db = memory_db().await;
+    let users = UserStore::new(db.get_connection());
+    let tagged = users
+        .create("Bob+News@Example.com".into(), "hash".into(), "Bob".into())
+        .await
+        .unwrap();
+    let plain = users
+        .create("bob@example.com".into(), "hash".into(), "Bob".into())
+        .await
+        .unwrap();
+
+    // Both become bob@example.com under strip_tag
+    let collisions = email_collisions(db.connection(), LocalPartPolicy::StripTag)
+        .await
+        .unwrap();
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].email, "bob@example.com");
+    assert!(
+        email_collisions(db.connection(), LocalPartPolicy::Lowercase)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    users.hard_delete(plain.id).await.unwrap();
+    let rewrites = normalize_emails(db.connection(), LocalPartPolicy::StripTag)
+        .await
+        .unwrap();
+    assert_eq!(rewrites.len(), 1);
+    assert_eq!(rewrites[0].to, "bob@example.com");
+
+    let rewritten = users.find_by_id(tagged.id).await.unwrap().unwrap();
+    assert_eq!(rewritten.email, "bob@example.com");
+    assert_eq!(rewritten.version, tagged.version + 1);
+    assert!(email_rewrites(db.connection(), LocalPartPolicy::StripTag)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_sqlite_user_search_ranks_and_pages() {
+    let db = memory_db().await;
+    let users = UserStore::new(db.get_connection());
+    for (name, email) in [
+        ("Ada Lovelace", "ada@example.com"),
+        ("Grace Hopper", "grace@example.com"),
+        ("Lovell Adams", "lovell.adams@example.com"),
+        ("Love", "love@x.io"),
+    ] {
+        users
+            .create(email.into(), "hash".into(), name.into())
+            .await
+            .unwrap();
+    }
+    let gone = users
+        .create("lovegone@example.com".into(), "hash".into(), "Gone".into())
+        .await
+        .unwrap();
+    users.delete(gone.id).await.unwrap();
+
+    let search = |q: &str, limit, offset| UserSearch {
+        query: SearchQuery::parse(q).unwrap(),
+        limit,
+        offset,
+    };
+    let (hits, total) = users.search(&search("LOVE", 10, 0)).await.unwrap();
+    assert_eq!(total, 3);
+    assert_eq!(hits[0].user.name, "Love");
+    assert!(hits.windows(2).all(|w| w[0].rank >= w[1].rank));
+    assert!(hits.iter().all(|h| h.rank > 0.0 && h.rank <= 1.0));
+    let ada = hits.iter().find(|h| h.user.name == "Ada Lovelace").unwrap();
+    assert_eq!(ada.highlights[0].field, HighlightField::Name);
+    assert_eq!((ada.highlights[0].start, ada.highlights[0].end), (4, 8));
+
+    // Every term must match
+    let (hits, total) = users.search(&search("ada love", 1, 1)).await.unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(hits.len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "Requires PostgreSQL"]
+async fn test_postgres_user_search_ranks_and_highlights_fuzzy_matches() {
+    let db = Database::connect_without_migrations(&Config::from_env())
+        .await
+        .unwrap();
+    db.run_migrations().await.unwrap();
+    let users = UserStore::new(db.get_connection());
+
+    let tag = uuid::Uuid::new_v4().simple().to_string();
+    let exact = users
+        .create(
+            format!("quillon.{}@example.com", tag),
+            "hash".into(),
+            "Quillon Marchbanks".into(),
+        )
+        .await
+        .unwrap();
+    let typo = users
+        .create(
+            format!("q.{}@example.com", tag),
+            "hash".into(),
+            "Quilon Marchbanks".into(),
+        )
+        .await
+        .unwrap();
+
+    let search = |q: &str| UserSearch {
+        query: SearchQuery::parse(q).unwrap(),
+        limit: 10,
+        offset: 0,
+    };
+
+    // Prefix match on the full-text index ranks the exact spelling first
+    let (hits, total) = users.search(&search("quillon")).await.unwrap();
+    assert!(total >= 2);
+    assert_eq!(hits[0].user.id, exact.id);
+    assert!(hits.iter().any(|h| h.user.id == typo.id));
+
+    // Matched by trigram similarity only, yet still highlighted
+    let hit = hits.iter().find(|h| h.user.id == typo.id).unwrap();
+    assert_eq!(hit.highlights[0].field, HighlightField::Name);
+    assert_eq!((hit.highlights[0].start, hit.highlights[0].end), (0, 6));
+
+    users.hard_delete(exact.id).await.unwrap();
+    users.hard_delete(typo.id).await.unwrap();
+}
+
+/// Service-style code written once against the repository trait
+async fn rename_all(users: &dyn UserRepository, name: &str) -> Result<usize, AppError> {
+    let active = users.list().await?;
+    for user in &active {
+        users
+            .update(user.id, Some(name.to_string()), None, None)
+            .await?;
+    }
+    Ok(active.len())
+}
+
+#[tokio::test]
+async fn test_sqlite_repository_runs_inside_and_outside_transactions() {
+    let db = memory_db().await;
+    let uow = Persistence::new(db.get_connection());
+    let user = uow
+        .users()
+        .create("tx@example.com".into(), "hash".into(), "Before".into())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rename_all(uow.users().as_ref(), "Outside").await.unwrap(),
+        1
+    );
+
+    let aborted: Result<(), AppError> = with_transaction!(uow, |ctx| {
+        rename_all(&ctx.users(), "Inside").await?;
+        assert_eq!(
+            ctx.users().find_by_id(user.id).await?.unwrap().name,
+            "Inside"
+        );
+        Err(AppError::internal("abort"))
+    });
+    assert!(aborted.is_err());
+
+    let name = uow.users().find_by_id(user.id).await.unwrap().unwrap().name;
+    assert_eq!(name, "Outside");
+}
+
+#[tokio::test]
+async fn test_sqlite_seeding_is_idempotent() {
+    let db = memory_db().await;
+    let users = Arc::new(UserStore::new(db.get_connection()));
+    let seeder = Seeder::new(Arc::new(Persistence::new(db.get_connection())));
+    let set = SeedSet::builtin("development").unwrap().with_generated(10);
+
+    let report = seeder.run(&set).await.unwrap();
+    assert_eq!(report.created, 14);
+    assert_eq!(report.deleted, 2);
+    assert_eq!(seeder.run(&set).await.unwrap().existing, 14);
+
+    let admin = users
+        .find_by_email("admin@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(admin.is_admin());
+    assert!(Password::from_hash(admin.password_hash).verify("Password123!"));
+    assert_eq!(users.list().await.unwrap().len(), 12);
+    assert_eq!(users.list_deleted().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_sqlite_seed_sets_load_from_files() {
+    let dir = std::env::temp_dir();
+    let yaml = dir.join(format!("seed-{}.yaml", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &yaml,
+        "name: demo\nusers:\n  - email: owner@example.com\n    name: Owner\n    password: Sup3r-secret\n    role: admin\n",
+    )
+    .unwrap();
+    let json = dir.join(format!("seed-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &json,
+        r#"{"name": "bad", "users": [{"email": "x@example.com", "name": "X", "role": "root"}]}"#,
+    )
+    .unwrap();
+
+    let db = memory_db().await;
+    let users = Arc::new(UserStore::new(db.get_connection()));
+    let seeder = Seeder::new(Arc::new(Persistence::new(db.get_connection())));
+
+    let demo = SeedSet::from_file(&yaml).unwrap();
+    assert_eq!(seeder.run(&demo).await.unwrap().created, 1);
+    let owner = users
+        .find_by_email("owner@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(owner.is_admin());
+    assert!(Password::from_hash(owner.password_hash).verify("Sup3r-secret"));
+
+    let bad = SeedSet::from_file(&json).unwrap();
+    assert!(matches!(
+        seeder.run(&bad).await,
+        Err(AppError::Validation(_))
+    ));
+
+    let _ = std::fs::remove_file(yaml);
+    let _ = std::fs::remove_file(json);
+}
+
+#[tokio::test]
+async fn test_sqlite_unit_of_work_commits_and_rolls_back() {
+    let db = memory_db().await;
+    let uow = Persistence::new(db.get_connection());
+
+    let committed = with_transaction!(uow, |ctx| {
+        let user = ctx
+            .users()
+            .create("kept@example.com".into(), "hash".into(), "Kept".into())
+            .await?;
+        ctx.outbox()
+            .enqueue(EmailJob::new("kept@example.com", "Hi", "Body").into())
+            .await?;
+        Ok(user)
+    })
+    .unwrap();
+
+    let rolled_back: Result<(), AppError> = with_transaction!(uow, |ctx| {
+        ctx.users()
+            .create(
+                "dropped@example.com".into(),
+                "hash".into(),
+                "Dropped".into(),
+            )
+            .await?;
+        ctx.outbox()
+            .enqueue(EmailJob::new("dropped@example.com", "Hi", "Body").into())
+            .await?;
+        Err(AppError::internal("abort"))
+    });
+    assert!(rolled_back.is_err());
+
+    let users = uow.users();
+    assert!(users.find_by_id(committed.id).await.unwrap().is_some());
+    assert!(users
+        .find_by_email("dropped@example.com")
+        .await
+        .unwrap()
+        .is_none());
+
+    // Only the committed outbox entry is relayed
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let relay = OutboxRelay::new(db.get_connection(), queue.clone(), Arc::new(NoopEventBus));
+    assert_eq!(relay.relay_batch().await.unwrap().dispatched, 1);
+    let emails = queue.emails();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "kept@example.com");
+}
+
+#[tokio::test]
+async fn test_sqlite_user_writes_queue_their_events_in_the_outbox() {
+    let db = memory_db().await;
+    let services = Services::from_connection(db.get_connection(), Config::from_env());
+
+    let user = services
+        .auth()
+        .register(
+            "lin@example.com".into(),
+            "Str0ng!Passw0rd".into(),
+            "Lin".into(),
+        )
+        .await
+        .unwrap();
+    let user_id = user.id;
+    let users = services.users();
+    users
+        .update_user(user_id, None, Some("admin".into()), None)
+        .await
+        .unwrap();
+    users.delete_user(user_id).await.unwrap();
+    users.restore_user(user_id).await.unwrap();
+    users.hard_delete_user(user_id).await.unwrap();
+
+    // A failed write queues nothing
+    assert!(users.restore_user(uuid::Uuid::new_v4()).await.is_err());
+
+    let published = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = published.clone();
+    let mut bus = MockEventBus::new();
+    bus.expect_publish().returning(move |e| {
+        sink.lock().unwrap().push(e.event);
+        Ok(())
+    });
+    let relay = OutboxRelay::new(
+        db.get_connection(),
+        Arc::new(InMemoryJobQueue::new()),
+        Arc::new(bus),
+    );
+    assert_eq!(relay.relay_batch().await.unwrap().dispatched, 6);
+
+    let published = published.lock().unwrap();
+    for event in [
+        UserEvent::Registered { user_id },
+        UserEvent::Updated { user_id },
+        UserEvent::RoleChanged {
+            user_id,
+            from: UserRole::User,
+            to: UserRole::Admin,
+        },
+        UserEvent::Deleted { user_id },
+        UserEvent::Restored { user_id },
+        UserEvent::Purged { user_id },
+    ] {
+        assert!(published.contains(&event), "missing {:?}", event);
+    }
+}
+
+#[tokio::test]
+async fn test_sqlite_exhausted_outbox_entries_stop_retrying_and_are_pruned() {
+    use rust_api_starter::config::OUTBOX_MAX_ATTEMPTS;
+    use rust_api_starter::domain::{DomainEvent, UserEvent};
+
+    let db = memory_db().await;
+    let uow = Persistence::new(db.get_connection());
+    let event = DomainEvent::new(UserEvent::Deleted {
+        user_id: uuid::Uuid::new_v4(),
+    });
+    with_transaction!(uow, |ctx| ctx.outbox().publish(event).await).unwrap();
+
+    let mut bus = MockEventBus::new();
+    bus.expect_publish()
+        .returning(|_| Err(AppError::internal("bus down")));
+    let relay = OutboxRelay::new(
+        db.get_connection(),
+        Arc::new(InMemoryJobQueue::new()),
+        Arc::new(bus),
+    );
+    let set_outbox = |assignment: String| {
+        db.connection().execute(Statement::from_string(
+            db.backend(),
+            format!("UPDATE outbox SET {}", assignment),
+        ))
+    };
+
+    // The last attempt fails: the entry is given up on, not retried
+    set_outbox(format!("attempts = {}", OUTBOX_MAX_ATTEMPTS - 1))
+        .await
+        .unwrap();
+    let stats = relay.relay_batch().await.unwrap();
+    assert_eq!((stats.failed, stats.exhausted), (0, 1));
+    assert_eq!(relay.relay_batch().await.unwrap().exhausted, 0);
+
+    // Kept for inspection for a while, then pruned
+    assert_eq!(relay.prune().await.unwrap(), 0);
+    set_outbox("available_at = '2000-01-01T00:00:00Z'".to_string())
+        .await
+        .unwrap();
+    assert_eq!(relay.prune().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_sqlite_expired_users_are_purged_in_batches() {
+    let db = memory_db().await;
+    let services = Services::from_connection(db.get_connection(), Config::from_env());
+    let users = UserStore::new(db.get_connection());
+
+    let mut ids = Vec::new();
+    for name in ["Old", "Older", "Recent"] {
+        let email = format!("{}@example.com", name.to_lowercase());
+        let user = users
+            .create(email, "hash".into(), name.into())
+            .await
+            .unwrap();
+        users.delete(user.id).await.unwrap();
+        ids.push(user.id);
+    }
+    let active = users
+        .create("active@example.com".into(), "hash".into(), "Active".into())
+        .await
+        .unwrap();
+
+    // Backdate the first two deletions past the retention period
+    let expired_at = chrono::Utc::now() - chrono::Duration::days(90);
+    for id in &ids[..2] {
+        db.connection()
+            .execute(Statement::from_sql_and_values(
+                db.backend(),
+                "UPDATE users SET deleted_at = ? WHERE id = ?",
+                [expired_at.into(), (*id).into()],
+            ))
+            .await
+            .unwrap();
+    }
+
+    // A retention under one day would purge users deleted moments ago
+    assert!(PurgeDeletedUsersTask::new(services.users(), 0).is_err());
+    assert!(PurgeDeletedUsersTask::new(services.users(), i64::MAX).is_err());
+
+    let task = PurgeDeletedUsersTask::new(services.users(), 30)
+        .unwrap()
+        .with_batch_size(1);
+    let due = services
+        .users()
+        .count_deleted_users_before(task.cutoff())
+        .await
+        .unwrap();
+    assert_eq!(due, 2);
+
+    let report = task.purge().await.unwrap();
+    assert_eq!(report.purged, 2);
+    assert_eq!(report.batches, 2);
+
+    assert!(users
+        .find_by_id_with_deleted(ids[0])
+        .await
+        .unwrap()
+        .is_none());
+    assert!(users
+        .find_by_id_with_deleted(ids[1])
+        .await
+        .unwrap()
+        .is_none());
+    assert!(users
+        .find_by_id_with_deleted(ids[2])
+        .await
+        .unwrap()
+        .is_some());
+    assert!(users.find_by_id(active.id).await.unwrap().is_some());
+
+    let logged = db
+        .connection()
+        .query_one(Statement::from_string(
+            db.backend(),
+            "SELECT COUNT(*) AS n FROM user_purges",
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(logged.try_get::<i64>("", "n").unwrap(), 2);
+
+    // One `user.purged` event per purged user
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let relay = OutboxRelay::new(db.get_connection(), queue, Arc::new(NoopEventBus));
+    assert_eq!(relay.relay_batch().await.unwrap().dispatched, 2);
+}
+
+#[tokio::test]
+async fn test_sqlite_purge_skips_users_restored_after_listing() {
+    let db = memory_db().await;
+    let users = UserStore::new(db.get_connection());
+
+    let mut ids = Vec::new();
+    for name in ["Kept", "Purged"] {
+        let email = format!("{}@example.com", name.to_lowercase());
+        let user = users
+            .create(email, "hash".into(), name.into())
+            .await
+            .unwrap();
+        users.delete(user.id).await.unwrap();
+        ids.push(user.id);
+    }
+    let before = chrono::Utc::now() + chrono::Duration::seconds(1);
+    let listed = users.list_deleted_before(before, 10).await.unwrap();
+    assert_eq!(listed.len(), 2);
+
+    // Restored between the listing and the delete
+    users.restore(ids[0]).await.unwrap();
+
+    let purged = users.purge_deleted(&ids, before).await.unwrap();
+    assert_eq!(purged.len(), 1);
+    assert_eq!(purged[0].id, ids[1]);
+    assert!(users.find_by_id(ids[0]).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_sqlite_register_and_login() {
+    let db = memory_db().await;
+    let services = Services::from_connection(db.get_connection(), Config::from_env());
+
+    let user = services
+        .auth()
+        .register(
+            "grace@example.com".into(),
+            "Str0ng!Passw0rd".into(),
+            "Grace".into(),
+        )
+        .await
+        .unwrap();
+    let token = services
+        .auth()
+        .login("grace@example.com".into(), "Str0ng!Passw0rd".into())
+        .await
+        .unwrap();
+
+    let claims = services.auth().verify_token(&token.access_token).unwrap();
+    assert_eq!(claims.sub, user.id);
+    assert!(matches!(
+        services
+            .auth()
+            .register(
+                "grace@example.com".into(),
+                "Str0ng!Passw0rd".into(),
+                "Again".into()
+            )
+            .await,
+        Err(AppError::Conflict(_))
+    ));
+
+    // Email identity ignores case
+    assert!(services
+        .auth()
+        .login("  GRACE@Example.com".into(), "Str0ng!Passw0rd".into())
+        .await
+        .is_ok());
+    assert!(matches!(
+        services
+            .auth()
+            .register(
+                "Grace@EXAMPLE.com".into(),
+                "Str0ng!Passw0rd".into(),
+                "Again".into()
+            )
+            .await,
+        Err(AppError::Conflict(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_sqlite_job_queue_is_unavailable() {
+    let db = memory_db().await;
+
+    assert!(db.require_postgres("The job queue").is_err());
+    assert!(!JobStore::new(db.get_connection())
+        .is_initialized()
+        .await
+        .unwrap());
+}
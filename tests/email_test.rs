@@ -0,0 +1,338 @@
+//! Email delivery integration tests.
+//!
+//! Runs against in-process SMTP and HTTP sinks, so no external server is needed.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use rust_api_starter::infra::mailer::{
+    Email, EmailConfig, EmailError, EmailTransport, FileTransport, HttpTransport, SmtpTls,
+    SmtpTransport, TransportKind,
+};
+use rust_api_starter::jobs::{EmailJob, Mailer};
+
+/// A message accepted by the sink
+#[derive(Debug, Clone)]
+struct Received {
+    from: String,
+    to: Vec<String>,
+    data: String,
+}
+
+/// Minimal SMTP server that records accepted messages.
+///
+/// Recipients containing "reject" get a 550, those containing "busy" a 451.
+struct SmtpSink {
+    port: u16,
+    messages: Arc<Mutex<Vec<Received>>>,
+}
+
+impl SmtpSink {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+
+        let store = messages.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_session(stream, store.clone()));
+            }
+        });
+
+        Self { port, messages }
+    }
+
+    fn config(&self) -> EmailConfig {
+        EmailConfig {
+            transport: TransportKind::Smtp,
+            from: "noreply@example.com".to_string(),
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: self.port,
+            smtp_user: None,
+            smtp_pass: None,
+            smtp_tls: SmtpTls::None,
+            file_dir: std::env::temp_dir(),
+            file_maildir: false,
+            http_url: None,
+            http_token: None,
+        }
+    }
+
+    fn transport(&self) -> SmtpTransport {
+        SmtpTransport::from_config(&self.config()).unwrap()
+    }
+
+    fn messages(&self) -> Vec<Received> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+fn email(to: &str) -> Email {
+    Email {
+        from: "noreply@example.com".to_string(),
+        to: to.to_string(),
+        subject: "Hi".to_string(),
+        text: "Body".to_string(),
+        html: None,
+    }
+}
+
+async fn handle_session(stream: tokio::net::TcpStream, store: Arc<Mutex<Vec<Received>>>) {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut current = Received {
+        from: String::new(),
+        to: Vec::new(),
+        data: String::new(),
+    };
+
+    write.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let upper = line.to_uppercase();
+        let reply: &[u8] = if upper.starts_with("EHLO") || upper.starts_with("HELO") {
+            b"250 sink\r\n"
+        } else if upper.starts_with("MAIL FROM:") {
+            current.from = line[10..].trim().to_string();
+            b"250 OK\r\n"
+        } else if upper.starts_with("RCPT TO:") {
+            let rcpt = line[8..].trim().to_string();
+            if rcpt.contains("reject") {
+                b"550 5.1.1 No such user\r\n"
+            } else if rcpt.contains("busy") {
+                b"451 4.3.0 Try again later\r\n"
+            } else {
+                current.to.push(rcpt);
+                b"250 OK\r\n"
+            }
+        } else if upper == "DATA" {
+            write.write_all(b"354 End with .\r\n").await.unwrap();
+            while let Ok(Some(data)) = lines.next_line().await {
+                if data == "." {
+                    break;
+                }
+                current.data.push_str(&data);
+                current.data.push('\n');
+            }
+            store.lock().unwrap().push(current.clone());
+            b"250 OK queued\r\n"
+        } else if upper == "RSET" {
+            current.to.clear();
+            b"250 OK\r\n"
+        } else if upper == "QUIT" {
+            write.write_all(b"221 Bye\r\n").await.unwrap();
+            break;
+        } else {
+            b"502 Not implemented\r\n"
+        };
+        write.write_all(reply).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_email_is_delivered_over_smtp() {
+    let sink = SmtpSink::start().await;
+    let mailer = Mailer::new(std::sync::Arc::new(sink.transport()), "noreply@example.com");
+    let job =
+        EmailJob::new("user@example.com", "Welcome", "Hello there").with_from("team@example.com");
+
+    mailer.send(&job).await.unwrap();
+
+    let messages = sink.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].from, "<team@example.com>");
+    assert_eq!(messages[0].to, vec!["<user@example.com>"]);
+    assert!(messages[0].data.contains("Subject: Welcome"));
+    assert!(messages[0].data.contains("Hello there"));
+}
+
+#[tokio::test]
+async fn test_rejected_recipient_is_permanent_failure() {
+    let sink = SmtpSink::start().await;
+
+    let err = sink
+        .transport()
+        .send(&email("reject@example.com"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, EmailError::Permanent(_)));
+    assert!(sink.messages().is_empty());
+}
+
+#[tokio::test]
+async fn test_temporary_rejection_is_transient_failure() {
+    let sink = SmtpSink::start().await;
+
+    let err = sink
+        .transport()
+        .send(&email("busy@example.com"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, EmailError::Transient(_)));
+}
+
+#[tokio::test]
+async fn test_unreachable_server_is_transient_failure() {
+    // Bind and drop to get a port nothing listens on
+    let port = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = EmailConfig {
+        smtp_port: port,
+        ..SmtpSink::start().await.config()
+    };
+
+    let err = SmtpTransport::from_config(&config)
+        .unwrap()
+        .send(&email("user@example.com"))
+        .await
+        .unwrap_err();
+
+    assert!(!err.is_permanent());
+}
+
+#[tokio::test]
+async fn test_config_selects_transport() {
+    let base = SmtpSink::start().await.config();
+
+    let log = EmailConfig {
+        transport: TransportKind::Log,
+        ..base.clone()
+    };
+    assert_eq!(log.build_transport().unwrap().name(), "log");
+
+    let http_without_url = EmailConfig {
+        transport: TransportKind::Http,
+        ..base
+    };
+    assert!(http_without_url.build_transport().is_err());
+}
+
+#[tokio::test]
+async fn test_file_transport_writes_eml() {
+    let dir = std::env::temp_dir().join(format!("emails-{}", uuid::Uuid::new_v4()));
+
+    FileTransport::new(&dir, false)
+        .send(&email("user@example.com"))
+        .await
+        .unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+    assert_eq!(files.len(), 1);
+    let path = files[0].as_ref().unwrap().path();
+    assert_eq!(path.extension().unwrap(), "eml");
+    let contents = std::fs::read_to_string(path).unwrap();
+    assert!(contents.contains("To: user@example.com"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_file_transport_maildir_layout() {
+    let dir = std::env::temp_dir().join(format!("maildir-{}", uuid::Uuid::new_v4()));
+
+    FileTransport::new(&dir, true)
+        .send(&email("user@example.com"))
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read_dir(dir.join("new")).unwrap().count(), 1);
+    assert_eq!(std::fs::read_dir(dir.join("tmp")).unwrap().count(), 0);
+    assert!(dir.join("cur").is_dir());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Start an HTTP provider stub answering with `status` and recording request bodies.
+async fn http_provider(status: u16) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Json, Router};
+
+    type Store = Arc<Mutex<Vec<serde_json::Value>>>;
+    let store: Store = Arc::new(Mutex::new(Vec::new()));
+
+    let app = Router::new()
+        .route(
+            "/send",
+            post(
+                move |State(store): State<Store>,
+                      headers: HeaderMap,
+                      Json(body): Json<serde_json::Value>| async move {
+                    assert_eq!(headers["authorization"], "Bearer secret");
+                    store.lock().unwrap().push(body);
+                    StatusCode::from_u16(status).unwrap()
+                },
+            ),
+        )
+        .with_state(store.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/send", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (url, store)
+}
+
+#[tokio::test]
+async fn test_http_transport_posts_json() {
+    let (url, store) = http_provider(202).await;
+    let transport = HttpTransport::new(url, Some("secret".to_string())).unwrap();
+
+    transport.send(&email("user@example.com")).await.unwrap();
+
+    let requests = store.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["to"], "user@example.com");
+    assert_eq!(requests[0]["subject"], "Hi");
+}
+
+#[tokio::test]
+async fn test_http_transport_classifies_status() {
+    let (url, _) = http_provider(503).await;
+    let transport = HttpTransport::new(url, Some("secret".to_string())).unwrap();
+    let err = transport
+        .send(&email("user@example.com"))
+        .await
+        .unwrap_err();
+    assert!(!err.is_permanent());
+
+    let (url, _) = http_provider(422).await;
+    let transport = HttpTransport::new(url, Some("secret".to_string())).unwrap();
+    let err = transport
+        .send(&email("user@example.com"))
+        .await
+        .unwrap_err();
+    assert!(err.is_permanent());
+}
+
+#[tokio::test]
+async fn test_templated_email_is_multipart() {
+    let sink = SmtpSink::start().await;
+    let mailer = Mailer::new(std::sync::Arc::new(sink.transport()), "noreply@example.com");
+    let job = EmailJob::from_template(
+        "user@example.com",
+        "verify_email",
+        "en",
+        serde_json::json!({
+            "name": "Ada",
+            "verify_url": "https://example.com/verify?token=abc",
+            "expires_in_hours": 24,
+        }),
+    )
+    .unwrap();
+
+    mailer.send(&job).await.unwrap();
+
+    let data = &sink.messages()[0].data;
+    assert!(data.contains("Subject: Confirm your email address"));
+    assert!(data.contains("multipart/alternative"));
+    assert!(data.contains("text/plain"));
+    assert!(data.contains("text/html"));
+}
@@ -0,0 +1,208 @@
+//! Job queue tests.
+//!
+//! The in-memory queue tests run everywhere. Postgres tests are ignored by
+//! default; run them with a database at DATABASE_URL:
+//! `cargo test --test jobs_test -- --ignored`
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use rust_api_starter::jobs::{EmailJob, InMemoryJobQueue, Job, JobQueue, PostgresJobQueue};
+
+#[tokio::test]
+async fn test_queue_is_usable_as_trait_object() {
+    let recorder = Arc::new(InMemoryJobQueue::new());
+    let queue: Arc<dyn JobQueue> = recorder.clone();
+
+    let at = Utc::now() + chrono::Duration::hours(1);
+    queue
+        .enqueue_at(EmailJob::new("user@example.com", "Hi", "Body").into(), at)
+        .await
+        .unwrap();
+
+    let jobs = recorder.jobs();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].run_at, Some(at));
+    assert!(matches!(&jobs[0].job, Job::Email(email) if email.to == "user@example.com"));
+}
+
+#[test]
+fn test_job_serialization_is_tagged() {
+    let job: Job = EmailJob::new("user@example.com", "Hi", "Body").into();
+    let value = serde_json::to_value(&job).unwrap();
+
+    assert_eq!(value["type"], "email");
+    assert_eq!(value["payload"]["to"], "user@example.com");
+    let back: Job = serde_json::from_value(value).unwrap();
+    assert!(matches!(back, Job::Email(email) if email.subject == "Hi"));
+}
+
+#[tokio::test]
+#[ignore = "Requires PostgreSQL"]
+async fn test_postgres_queue_persists_jobs() {
+    use apalis_sql::sqlx::{postgres::PgPoolOptions, Row};
+
+    let url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| rust_api_starter::config::DEFAULT_DATABASE_URL.to_string());
+    let pool = PgPoolOptions::new().connect(&url).await.unwrap();
+    PostgresJobQueue::setup(&pool).await.unwrap();
+    let queue = PostgresJobQueue::new(pool.clone());
+
+    let now_id = queue
+        .enqueue(EmailJob::new("now@example.com", "Now", "Body").into())
+        .await
+        .unwrap();
+    let later_id = queue
+        .enqueue_in(
+            EmailJob::new("later@example.com", "Later", "Body").into(),
+            Duration::from_secs(3600),
+        )
+        .await
+        .unwrap();
+
+    let row = apalis_sql::sqlx::query(
+        "SELECT job_type, run_at > NOW() + INTERVAL '59 minutes' AS delayed FROM apalis.jobs WHERE id = $1",
+    )
+    .bind(&later_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        row.get::<String, _>("job_type"),
+        rust_api_starter::config::JOB_NAME_EMAIL
+    );
+    assert!(row.get::<bool, _>("delayed"));
+
+    apalis_sql::sqlx::query("DELETE FROM apalis.jobs WHERE id = ANY($1)")
+        .bind(vec![now_id, later_id])
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "Requires PostgreSQL"]
+async fn test_outbox_relays_only_committed_entries() {
+    use rust_api_starter::config::Config;
+    use rust_api_starter::domain::NoopEventBus;
+    use rust_api_starter::errors::AppError;
+    use rust_api_starter::infra::{Database, Persistence, UnitOfWork};
+    use rust_api_starter::jobs::OutboxRelay;
+    use rust_api_starter::with_transaction;
+
+    let db = Database::connect_without_migrations(&Config::from_env())
+        .await
+        .unwrap();
+    db.run_migrations().await.unwrap();
+    let uow = Persistence::new(db.get_connection());
+
+    let committed: uuid::Uuid = with_transaction!(uow, |ctx| {
+        ctx.outbox()
+            .enqueue(EmailJob::new("committed@example.com", "Hi", "Body").into())
+            .await
+    })
+    .unwrap();
+    let rolled_back: Result<(), AppError> = with_transaction!(uow, |ctx| {
+        ctx.outbox()
+            .enqueue(EmailJob::new("rolled-back@example.com", "Hi", "Body").into())
+            .await?;
+        Err(AppError::internal("abort"))
+    });
+    assert!(rolled_back.is_err());
+
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let relay = OutboxRelay::new(db.get_connection(), queue.clone(), Arc::new(NoopEventBus));
+    while relay.relay_batch().await.unwrap().dispatched > 0 {}
+
+    let ids: Vec<_> = queue.jobs().into_iter().map(|j| j.id).collect();
+    assert!(ids.contains(&committed.to_string()));
+    assert!(!queue
+        .emails()
+        .iter()
+        .any(|e| e.to == "rolled-back@example.com"));
+
+    // Dispatched entries are not relayed again
+    let before = queue.jobs().len();
+    relay.relay_batch().await.unwrap();
+    assert_eq!(queue.jobs().len(), before);
+}
+
+#[tokio::test]
+#[ignore = "Requires PostgreSQL"]
+async fn test_failed_jobs_are_retried_or_dead_lettered() {
+    use apalis::prelude::{Attempt, TaskId};
+    use apalis_sql::sqlx::Row;
+    use rust_api_starter::config::{Config, JOB_NAME_EMAIL};
+    use rust_api_starter::infra::mailer::EmailError;
+    use rust_api_starter::infra::Database;
+    use rust_api_starter::jobs::{JobRetries, RetrySweeper};
+    use std::str::FromStr;
+
+    let db = Database::connect_without_migrations(&Config::from_env())
+        .await
+        .unwrap();
+    db.run_migrations().await.unwrap();
+    let pool = db.connection().get_postgres_connection_pool().clone();
+    PostgresJobQueue::setup(&pool).await.unwrap();
+    let queue = PostgresJobQueue::new(pool.clone());
+    let retries = JobRetries::from_env(db.get_connection()).unwrap();
+
+    let transient = queue
+        .enqueue(EmailJob::new("retry@example.com", "Hi", "Body").into())
+        .await
+        .unwrap();
+    let permanent = queue
+        .enqueue(EmailJob::new("dead@example.com", "Hi", "Body").into())
+        .await
+        .unwrap();
+
+    // What the handler and apalis do for a first attempt that failed
+    for (id, error, status) in [
+        (&transient, EmailError::Transient("timeout".into()), "Failed"),
+        (&permanent, EmailError::Permanent("550".into()), "Killed"),
+    ] {
+        retries
+            .on_failure(
+                JOB_NAME_EMAIL,
+                &TaskId::from_str(id).unwrap(),
+                &Attempt::default(),
+                error,
+            )
+            .await;
+        apalis_sql::sqlx::query("UPDATE apalis.jobs SET status = $1, attempts = 1 WHERE id = $2")
+            .bind(status)
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let stats = RetrySweeper::new(db.get_connection()).sweep().await.unwrap();
+    assert!(stats.retried >= 1 && stats.dead_lettered >= 1);
+
+    let status: String = apalis_sql::sqlx::query("SELECT status FROM apalis.jobs WHERE id = $1")
+        .bind(&transient)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .get("status");
+    assert_eq!(status, "Pending");
+
+    let dead = apalis_sql::sqlx::query(
+        "SELECT error_kind, jsonb_array_length(history) AS attempts FROM dead_letter_jobs WHERE job_id = $1",
+    )
+    .bind(&permanent)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(dead.get::<String, _>("error_kind"), "permanent");
+    assert_eq!(dead.get::<i32, _>("attempts"), 1);
+
+    apalis_sql::sqlx::query("DELETE FROM apalis.jobs WHERE id = $1")
+        .bind(&transient)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
@@ -23,6 +23,7 @@ fn create_test_user(id: Uuid) -> User {
         created_at: Utc::now(),
         updated_at: Utc::now(),
         deleted_at: None,
+        version: 1,
     }
 }
 
@@ -118,34 +119,3 @@ async fn test_list_users_success() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap().len(), 2);
 }
-
-#[tokio::test]
-async fn test_delete_user_success() {
-    let user_id = Uuid::new_v4();
-
-    let mut repo = MockUserRepository::new();
-    repo.expect_delete()
-        .returning(|_| Ok(()));
-
-    let uow = TestUnitOfWork::new(repo);
-    let service = UserManager::new(Arc::new(uow));
-    let result = service.delete_user(user_id).await;
-
-    assert!(result.is_ok());
-}
-
-#[tokio::test]
-async fn test_restore_user_success() {
-    let user_id = Uuid::new_v4();
-
-    let mut repo = MockUserRepository::new();
-    repo.expect_restore()
-        .returning(move |id| Ok(create_test_user(id)));
-
-    let uow = TestUnitOfWork::new(repo);
-    let service = UserManager::new(Arc::new(uow));
-    let result = service.restore_user(user_id).await;
-
-    assert!(result.is_ok());
-    assert_eq!(result.unwrap().id, user_id);
-}